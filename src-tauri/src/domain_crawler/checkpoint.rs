@@ -0,0 +1,255 @@
+//! Crawl checkpoints: the frontier (queued + in-flight URLs) and the visited set
+//! are written to the crawl's own database every `checkpoint_interval` seconds, so
+//! `resume_crawl` can pick a crawl back up after `stop_crawl_command`, a crash or
+//! an app restart.
+
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::crawl_overrides::CrawlOverrides;
+use super::database::DatabaseError;
+
+/// Where a checkpointed crawl stands.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckpointStatus {
+    /// Crawl in progress (or the app died while it was)
+    Running,
+    /// Stopped by the user or the crawl timeout; can be resumed
+    Stopped,
+    /// Finished; nothing left to resume
+    Completed,
+}
+
+impl CheckpointStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CheckpointStatus::Running => "running",
+            CheckpointStatus::Stopped => "stopped",
+            CheckpointStatus::Completed => "completed",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "running" => CheckpointStatus::Running,
+            "stopped" => CheckpointStatus::Stopped,
+            _ => CheckpointStatus::Completed,
+        }
+    }
+}
+
+/// What a crawl was started with, plus its progress counters at the last checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlCheckpoint {
+    /// Start URL (domain crawls) or the first URL's origin (list mode)
+    pub base_url: String,
+    /// `true` when the crawl is a list-mode crawl (no link following)
+    pub list_mode: bool,
+    pub overrides: Option<CrawlOverrides>,
+    pub status: CheckpointStatus,
+    pub total_urls: usize,
+    pub crawled_urls: usize,
+    pub failed_urls: usize,
+    /// Number of URLs still waiting to be fetched
+    pub frontier_urls: usize,
+    pub updated_at: String,
+}
+
+impl CrawlCheckpoint {
+    pub fn is_resumable(&self) -> bool {
+        self.status != CheckpointStatus::Completed && self.frontier_urls > 0
+    }
+}
+
+/// State captured from `CrawlerState` under a single lock, so the frontier and
+/// visited set written to disk are consistent with each other.
+pub struct FrontierSnapshot {
+    /// Queued and in-flight URLs with their crawl depth
+    pub frontier: Vec<(String, usize)>,
+    /// URLs marked visited since the previous checkpoint
+    pub new_visited: Vec<String>,
+    pub total_urls: usize,
+    pub crawled_urls: usize,
+    pub failed_urls: usize,
+}
+
+pub(crate) fn ensure_checkpoint_tables(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS crawl_checkpoint (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            base_url TEXT NOT NULL,
+            list_mode INTEGER NOT NULL,
+            overrides TEXT,
+            status TEXT NOT NULL,
+            total_urls INTEGER NOT NULL,
+            crawled_urls INTEGER NOT NULL,
+            failed_urls INTEGER NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS crawl_frontier (
+            url TEXT PRIMARY KEY,
+            depth INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS crawl_visited (
+            url TEXT PRIMARY KEY
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+pub(crate) fn clear_checkpoint_tables(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute_batch(
+        "DELETE FROM crawl_checkpoint; DELETE FROM crawl_frontier; DELETE FROM crawl_visited;",
+    )?;
+    Ok(())
+}
+
+/// Writes a checkpoint. The frontier is replaced wholesale; visited URLs are
+/// appended, since only the ones added since the last checkpoint are passed in.
+pub async fn save_checkpoint(
+    pool: Arc<Pool<SqliteConnectionManager>>,
+    base_url: String,
+    list_mode: bool,
+    overrides: Option<CrawlOverrides>,
+    status: CheckpointStatus,
+    snapshot: FrontierSnapshot,
+) -> Result<(), DatabaseError> {
+    let overrides_json = overrides
+        .as_ref()
+        .map(|overrides| serde_json::to_string(&overrides.without_secrets()))
+        .transpose()?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get()?;
+        ensure_checkpoint_tables(&conn)?;
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO crawl_checkpoint
+                (id, base_url, list_mode, overrides, status, total_urls, crawled_urls, failed_urls, updated_at)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                base_url,
+                list_mode as i64,
+                overrides_json,
+                status.as_str(),
+                snapshot.total_urls as i64,
+                snapshot.crawled_urls as i64,
+                snapshot.failed_urls as i64,
+                chrono::Local::now().to_rfc3339(),
+            ],
+        )?;
+        tx.execute("DELETE FROM crawl_frontier", [])?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR REPLACE INTO crawl_frontier (url, depth) VALUES (?1, ?2)")?;
+            for (url, depth) in &snapshot.frontier {
+                stmt.execute(params![url, *depth as i64])?;
+            }
+            let mut stmt = tx.prepare("INSERT OR IGNORE INTO crawl_visited (url) VALUES (?1)")?;
+            for url in &snapshot.new_visited {
+                stmt.execute(params![url])?;
+            }
+        }
+        if status == CheckpointStatus::Completed {
+            // Nothing left to resume — don't keep a 200k-row visited set around.
+            tx.execute("DELETE FROM crawl_frontier", [])?;
+            tx.execute("DELETE FROM crawl_visited", [])?;
+        }
+        tx.commit()?;
+        Ok(())
+    })
+    .await?
+}
+
+/// Reads the last checkpoint's metadata, if there is one.
+pub async fn load_checkpoint_info(
+    pool: Arc<Pool<SqliteConnectionManager>>,
+) -> Result<Option<CrawlCheckpoint>, DatabaseError> {
+    tokio::task::spawn_blocking(move || {
+        let conn = pool.get()?;
+        ensure_checkpoint_tables(&conn)?;
+        read_checkpoint_info(&conn)
+    })
+    .await?
+}
+
+/// Reads the last checkpoint together with its frontier and visited set.
+pub async fn load_checkpoint(
+    pool: Arc<Pool<SqliteConnectionManager>>,
+) -> Result<Option<(CrawlCheckpoint, Vec<(String, usize)>, Vec<String>)>, DatabaseError> {
+    tokio::task::spawn_blocking(move || {
+        let conn = pool.get()?;
+        ensure_checkpoint_tables(&conn)?;
+
+        let Some(info) = read_checkpoint_info(&conn)? else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare("SELECT url, depth FROM crawl_frontier")?;
+        let frontier = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare("SELECT url FROM crawl_visited")?;
+        let visited = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some((info, frontier, visited)))
+    })
+    .await?
+}
+
+fn read_checkpoint_info(conn: &Connection) -> Result<Option<CrawlCheckpoint>, DatabaseError> {
+    let frontier_urls: i64 =
+        conn.query_row("SELECT COUNT(*) FROM crawl_frontier", [], |row| row.get(0))?;
+
+    let row = conn
+        .query_row(
+            "SELECT base_url, list_mode, overrides, status, total_urls, crawled_urls, failed_urls, updated_at
+             FROM crawl_checkpoint WHERE id = 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)? != 0,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, i64>(5)?,
+                    row.get::<_, i64>(6)?,
+                    row.get::<_, String>(7)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    let Some((base_url, list_mode, overrides, status, total, crawled, failed, updated_at)) = row
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(CrawlCheckpoint {
+        base_url,
+        list_mode,
+        overrides: overrides
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?,
+        status: CheckpointStatus::from_str(&status),
+        total_urls: total as usize,
+        crawled_urls: crawled as usize,
+        failed_urls: failed as usize,
+        frontier_urls: frontier_urls as usize,
+        updated_at,
+    }))
+}
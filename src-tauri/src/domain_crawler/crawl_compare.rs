@@ -0,0 +1,305 @@
+//! Field-level comparison between two stored crawls (see `crawl_projects`).
+//!
+//! Pages are matched on their normalized URL and sorted into four categories:
+//! new (only in the current crawl), removed (only in the previous crawl),
+//! changed (at least one compared field differs) and unchanged. Changed pages
+//! carry before/after values for every field that differs.
+//!
+//! Response time always moves a little between crawls, so it only counts as a
+//! change when it differs by more than `RESPONSE_TIME_TOLERANCE` and 100ms.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use super::helpers::normalize_url::normalize_url;
+
+pub const FIELD_STATUS_CODE: &str = "Status Code";
+pub const FIELD_TITLE: &str = "Title";
+pub const FIELD_DESCRIPTION: &str = "Meta Description";
+pub const FIELD_H1: &str = "H1";
+pub const FIELD_CANONICAL: &str = "Canonical";
+pub const FIELD_META_ROBOTS: &str = "Meta Robots";
+pub const FIELD_INDEXABILITY: &str = "Indexability";
+pub const FIELD_WORD_COUNT: &str = "Word Count";
+pub const FIELD_INLINKS: &str = "Inlinks";
+pub const FIELD_RESPONSE_TIME: &str = "Response Time (s)";
+/// Prefix for Custom Search rule results, followed by the rule name
+pub const FIELD_CUSTOM_SEARCH_PREFIX: &str = "Custom Search: ";
+
+/// Relative change in response time below which it is not reported.
+const RESPONSE_TIME_TOLERANCE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeCategory {
+    New,
+    Removed,
+    Changed,
+    Unchanged,
+}
+
+/// The compared fields of one page, as display strings.
+#[derive(Debug, Clone, Default)]
+pub struct PageSnapshot {
+    pub url: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlComparison {
+    pub url: String,
+    pub category: ChangeCategory,
+    /// Only filled for `Changed` pages
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComparisonSummary {
+    pub new: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+    /// Number of changed pages per field
+    pub by_field: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrawlComparison {
+    pub previous_crawl_id: String,
+    pub current_crawl_id: String,
+    pub summary: ComparisonSummary,
+    /// Every page found in either crawl, one entry per URL
+    pub urls: Vec<UrlComparison>,
+}
+
+/// Builds a snapshot from a row of `Database::get_comparison_rows`.
+/// `inlinks` maps normalized target URLs to the number of pages linking to them.
+pub fn snapshot_from_row(row: &Value, inlinks: &HashMap<String, u64>) -> Option<PageSnapshot> {
+    let url = row.get("url")?.as_str()?.to_string();
+    let mut fields = BTreeMap::new();
+
+    let text = |key: &str| -> String {
+        match row.get(key) {
+            Some(Value::String(s)) => s.trim().to_string(),
+            Some(Value::Number(n)) => n.to_string(),
+            _ => String::new(),
+        }
+    };
+    let joined = |key: &str, sep: &str| -> String {
+        row.get(key)
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.trim())
+                    .collect::<Vec<_>>()
+                    .join(sep)
+            })
+            .unwrap_or_default()
+    };
+
+    fields.insert(FIELD_STATUS_CODE.to_string(), text("status_code"));
+    fields.insert(FIELD_TITLE.to_string(), text("title"));
+    fields.insert(FIELD_DESCRIPTION.to_string(), text("description"));
+    fields.insert(FIELD_H1.to_string(), joined("h1", " | "));
+    fields.insert(FIELD_CANONICAL.to_string(), joined("canonicals", ", "));
+    fields.insert(FIELD_META_ROBOTS.to_string(), joined("meta_robots", ", ").to_lowercase());
+    fields.insert(FIELD_INDEXABILITY.to_string(), text("indexability_reason"));
+    fields.insert(FIELD_WORD_COUNT.to_string(), text("word_count"));
+    fields.insert(
+        FIELD_INLINKS.to_string(),
+        inlinks.get(&normalize_url(&url)).copied().unwrap_or(0).to_string(),
+    );
+    let response_time = row
+        .get("response_time")
+        .and_then(|v| v.as_f64())
+        .map(|t| format!("{:.3}", t))
+        .unwrap_or_default();
+    fields.insert(FIELD_RESPONSE_TIME.to_string(), response_time);
+
+    if let Some(matches) = row.get("custom_search").and_then(|v| v.as_array()) {
+        for m in matches {
+            let Some(name) = m.get("rule_name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let matched = m.get("matched").and_then(|v| v.as_bool()).unwrap_or(false);
+            let value = match m.get("value").and_then(|v| v.as_str()) {
+                Some(v) if matched => v.to_string(),
+                _ if matched => "Match".to_string(),
+                _ => "No match".to_string(),
+            };
+            fields.insert(format!("{}{}", FIELD_CUSTOM_SEARCH_PREFIX, name), value);
+        }
+    }
+
+    Some(PageSnapshot { url, fields })
+}
+
+/// Compares two crawls page by page.
+pub fn compare_crawls(previous: Vec<PageSnapshot>, current: Vec<PageSnapshot>) -> CrawlComparison {
+    let mut previous_by_url: HashMap<String, PageSnapshot> = previous
+        .into_iter()
+        .map(|page| (normalize_url(&page.url), page))
+        .collect();
+
+    let mut summary = ComparisonSummary::default();
+    let mut urls = Vec::new();
+
+    for page in current {
+        let key = normalize_url(&page.url);
+        let Some(before) = previous_by_url.remove(&key) else {
+            summary.new += 1;
+            urls.push(UrlComparison {
+                url: page.url,
+                category: ChangeCategory::New,
+                changes: Vec::new(),
+            });
+            continue;
+        };
+
+        let changes = diff_fields(&before.fields, &page.fields);
+        if changes.is_empty() {
+            summary.unchanged += 1;
+            urls.push(UrlComparison {
+                url: page.url,
+                category: ChangeCategory::Unchanged,
+                changes,
+            });
+            continue;
+        }
+        summary.changed += 1;
+        for change in &changes {
+            *summary.by_field.entry(change.field.clone()).or_insert(0) += 1;
+        }
+        urls.push(UrlComparison {
+            url: page.url,
+            category: ChangeCategory::Changed,
+            changes,
+        });
+    }
+
+    let mut removed: Vec<String> = previous_by_url.into_values().map(|page| page.url).collect();
+    removed.sort();
+    summary.removed = removed.len();
+    urls.extend(removed.into_iter().map(|url| UrlComparison {
+        url,
+        category: ChangeCategory::Removed,
+        changes: Vec::new(),
+    }));
+
+    CrawlComparison {
+        summary,
+        urls,
+        ..Default::default()
+    }
+}
+
+fn diff_fields(before: &BTreeMap<String, String>, after: &BTreeMap<String, String>) -> Vec<FieldChange> {
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let b = before.get(field);
+            let a = after.get(field);
+            let changed = if field == FIELD_RESPONSE_TIME {
+                response_time_changed(b, a)
+            } else {
+                b != a
+            };
+            changed.then(|| FieldChange {
+                field: field.clone(),
+                before: b.cloned(),
+                after: a.cloned(),
+            })
+        })
+        .collect()
+}
+
+fn response_time_changed(before: Option<&String>, after: Option<&String>) -> bool {
+    let parse = |v: Option<&String>| v.and_then(|s| s.parse::<f64>().ok());
+    match (parse(before), parse(after)) {
+        (Some(b), Some(a)) => {
+            let delta = (a - b).abs();
+            delta > 0.1 && delta > b.max(a) * RESPONSE_TIME_TOLERANCE
+        }
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, fields: &[(&str, &str)]) -> PageSnapshot {
+        PageSnapshot {
+            url: url.to_string(),
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn categorises_pages_and_reports_before_after() {
+        let previous = vec![
+            page("https://example.com/a", &[(FIELD_TITLE, "Old"), (FIELD_STATUS_CODE, "200")]),
+            page("https://example.com/b", &[(FIELD_TITLE, "Same")]),
+            page("https://example.com/gone", &[(FIELD_TITLE, "Gone")]),
+        ];
+        let current = vec![
+            page("https://example.com/a/", &[(FIELD_TITLE, "New"), (FIELD_STATUS_CODE, "200")]),
+            page("https://example.com/b", &[(FIELD_TITLE, "Same")]),
+            page("https://example.com/fresh", &[(FIELD_TITLE, "Fresh")]),
+        ];
+
+        let result = compare_crawls(previous, current);
+        assert_eq!(result.summary.new, 1);
+        assert_eq!(result.summary.removed, 1);
+        assert_eq!(result.summary.changed, 1);
+        assert_eq!(result.summary.unchanged, 1);
+        assert_eq!(result.summary.by_field.get(FIELD_TITLE), Some(&1));
+        assert_eq!(result.urls.len(), 4);
+
+        let unchanged = result
+            .urls
+            .iter()
+            .find(|u| u.category == ChangeCategory::Unchanged)
+            .unwrap();
+        assert_eq!(unchanged.url, "https://example.com/b");
+        assert!(unchanged.changes.is_empty());
+
+        let changed = result
+            .urls
+            .iter()
+            .find(|u| u.category == ChangeCategory::Changed)
+            .unwrap();
+        assert_eq!(
+            changed.changes,
+            vec![FieldChange {
+                field: FIELD_TITLE.to_string(),
+                before: Some("Old".to_string()),
+                after: Some("New".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn small_response_time_jitter_is_ignored() {
+        let t = |s: &str| Some(s.to_string());
+        assert!(!response_time_changed(t("0.400").as_ref(), t("0.450").as_ref()));
+        assert!(response_time_changed(t("0.400").as_ref(), t("1.200").as_ref()));
+    }
+}
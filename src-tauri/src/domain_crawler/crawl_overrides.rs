@@ -0,0 +1,64 @@
+//! Per-crawl overrides passed from the frontend to `domain_crawl_command`.
+//! Every field is optional; anything left unset falls back to the saved Settings.
+//! Overrides only affect the crawl they are passed to and are never written to the
+//! settings file (they are kept in the crawl's checkpoint so it can be resumed).
+//! Credentials are the exception: `auth` is never serialized, so it has to be
+//! passed again to resume the crawl.
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::settings::Settings;
+
+use super::helpers::crawl_auth::CrawlAuth;
+use super::helpers::crawl_budget::CrawlBudget;
+use super::helpers::url_rewrite::RewriteRule;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrawlOverrides {
+    /// Replaces `Settings::crawl_include_patterns` for this crawl
+    pub include_patterns: Option<Vec<String>>,
+    /// Replaces `Settings::crawl_exclude_patterns` for this crawl
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Replaces `Settings::url_rewrite_rules` for this crawl
+    pub url_rewrite_rules: Option<Vec<RewriteRule>>,
+    /// Replaces `Settings::crawl_budgets` for this crawl
+    pub crawl_budgets: Option<Vec<CrawlBudget>>,
+    /// Credentials for sites behind a login wall. Not part of Settings: they are
+    /// only sent to the crawl's own hosts and only for this crawl. Never written
+    /// to a checkpoint
+    #[serde(default, skip_serializing)]
+    pub auth: Option<CrawlAuth>,
+    /// Set on stored copies when the crawl ran with `auth`, so a resumed crawl
+    /// knows to ask for the credentials again
+    #[serde(default)]
+    pub requires_auth: bool,
+}
+
+impl CrawlOverrides {
+    /// Copy that is safe to store: credentials are dropped and only
+    /// `requires_auth` records that there were any.
+    pub fn without_secrets(&self) -> Self {
+        let requires_auth = self.auth.as_ref().is_some_and(|auth| !auth.is_empty());
+        CrawlOverrides {
+            auth: None,
+            requires_auth: self.requires_auth || requires_auth,
+            ..self.clone()
+        }
+    }
+
+    /// Applies the overrides onto a crawl-local copy of the settings.
+    pub fn apply_to(&self, settings: &mut Settings) {
+        if let Some(include) = &self.include_patterns {
+            settings.crawl_include_patterns = include.clone();
+        }
+        if let Some(exclude) = &self.exclude_patterns {
+            settings.crawl_exclude_patterns = exclude.clone();
+        }
+        if let Some(rules) = &self.url_rewrite_rules {
+            settings.url_rewrite_rules = rules.clone();
+        }
+        if let Some(budgets) = &self.crawl_budgets {
+            settings.crawl_budgets = budgets.clone();
+        }
+    }
+}
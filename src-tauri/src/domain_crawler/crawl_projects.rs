@@ -0,0 +1,402 @@
+//! Stored crawl projects. Every crawl gets its own SQLite file under `db/crawls/`
+//! and a row in the `crawl_projects` registry (in `deep_crawl.db`) with its domain,
+//! start time and a snapshot of the settings it ran with. Opening a project points
+//! the read commands (`get_crawl_page_command`, exports, ...) at that crawl's file.
+
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::database;
+use super::db_deep::db::open_domain_db_connection;
+use crate::settings::settings::Settings;
+
+/// The single shared crawl database used before crawls were stored separately.
+/// If it still holds data it is registered as a project so the last crawl survives.
+pub const LEGACY_DB_FILE: &str = "deep_crawl_batches.db";
+const LEGACY_PROJECT_ID: &str = "legacy";
+
+/// Project id of the crawl currently running, if any. Used to refuse deleting a
+/// crawl mid-run and to tell a running crawl apart from one the app died during.
+static CURRENT_CRAWL: Lazy<std::sync::Mutex<Option<String>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlProject {
+    pub id: String,
+    pub name: String,
+    pub domain: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// running, stopped, completed, failed, or interrupted (the app quit mid-crawl)
+    pub status: String,
+    pub pages: i64,
+    /// Settings the crawl ran with (per-crawl overrides applied, API keys removed)
+    pub settings: Option<Value>,
+    /// Database file, relative to the app's `db` directory
+    pub db_file: String,
+    pub last_opened_at: Option<String>,
+}
+
+fn open_registry() -> Result<Connection, String> {
+    let conn = open_domain_db_connection("deep_crawl.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS crawl_projects (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            domain TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT,
+            status TEXT NOT NULL,
+            pages INTEGER NOT NULL DEFAULT 0,
+            settings TEXT,
+            db_file TEXT NOT NULL,
+            last_opened_at TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    register_legacy_db(&conn)?;
+    Ok(conn)
+}
+
+/// Registers the pre-projects `deep_crawl_batches.db` once, if it holds a crawl.
+fn register_legacy_db(conn: &Connection) -> Result<(), String> {
+    let registered: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM crawl_projects WHERE id = ?1",
+            params![LEGACY_PROJECT_ID],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count > 0)
+        .map_err(|e| e.to_string())?;
+    if registered {
+        return Ok(());
+    }
+
+    let legacy_path = db_dir()?.join(LEGACY_DB_FILE);
+    if !legacy_path.exists() {
+        return Ok(());
+    }
+    let legacy = Connection::open(&legacy_path).map_err(|e| e.to_string())?;
+    let first: Option<(String, i64)> = legacy
+        .query_row(
+            "SELECT (SELECT url FROM domain_crawl ORDER BY id LIMIT 1), COUNT(*) FROM domain_crawl",
+            [],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .ok()
+        .and_then(|(url, count)| url.map(|u| (u, count)));
+    let Some((first_url, pages)) = first else {
+        return Ok(());
+    };
+
+    let domain = url::Url::parse(&first_url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or(first_url);
+    let modified = std::fs::metadata(&legacy_path)
+        .and_then(|m| m.modified())
+        .map(|t| chrono::DateTime::<chrono::Local>::from(t).to_rfc3339())
+        .unwrap_or_else(|_| chrono::Local::now().to_rfc3339());
+
+    conn.execute(
+        "INSERT INTO crawl_projects (id, name, domain, started_at, finished_at, status, pages, settings, db_file)
+         VALUES (?1, ?2, ?3, ?4, ?4, 'completed', ?5, NULL, ?6)",
+        params![
+            LEGACY_PROJECT_ID,
+            format!("{} (previous crawl)", domain),
+            domain,
+            modified,
+            pages,
+            LEGACY_DB_FILE
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn db_dir() -> Result<std::path::PathBuf, String> {
+    let project_dirs = directories::ProjectDirs::from("", "", "rustyseo")
+        .ok_or_else(|| "Failed to get project directories".to_string())?;
+    Ok(project_dirs.data_dir().join("db"))
+}
+
+fn row_to_project(row: &rusqlite::Row) -> rusqlite::Result<CrawlProject> {
+    let id: String = row.get(0)?;
+    let mut status: String = row.get(5)?;
+    if status == "running" && !is_current_crawl(&id) {
+        status = "interrupted".to_string();
+    }
+    let settings: Option<String> = row.get(7)?;
+    Ok(CrawlProject {
+        id,
+        name: row.get(1)?,
+        domain: row.get(2)?,
+        started_at: row.get(3)?,
+        finished_at: row.get(4)?,
+        status,
+        pages: row.get(6)?,
+        settings: settings.and_then(|s| serde_json::from_str(&s).ok()),
+        db_file: row.get(8)?,
+        last_opened_at: row.get(9)?,
+    })
+}
+
+const PROJECT_COLUMNS: &str =
+    "id, name, domain, started_at, finished_at, status, pages, settings, db_file, last_opened_at";
+
+fn is_current_crawl(id: &str) -> bool {
+    CURRENT_CRAWL
+        .lock()
+        .map(|current| current.as_deref() == Some(id))
+        .unwrap_or(false)
+}
+
+/// Whether a crawl is running right now.
+pub fn crawl_in_progress() -> bool {
+    CURRENT_CRAWL
+        .lock()
+        .map(|current| current.is_some())
+        .unwrap_or(false)
+}
+
+/// Fails when a crawl is already running. Every crawl entry point checks this
+/// before it creates or reopens a project, as only one crawl runs at a time.
+pub fn ensure_no_crawl_in_progress() -> Result<(), String> {
+    if crawl_in_progress() {
+        return Err("Another crawl is already running".to_string());
+    }
+    Ok(())
+}
+
+/// Marks `id` as the crawl currently running. The check and the update happen
+/// under one lock, so of two crawls started together only one gets through.
+pub fn begin_crawl(id: &str) -> Result<(), String> {
+    let mut current = CURRENT_CRAWL.lock().map_err(|e| e.to_string())?;
+    if current.is_some() {
+        return Err("Another crawl is already running".to_string());
+    }
+    *current = Some(id.to_string());
+    Ok(())
+}
+
+/// Clears the crawl currently running.
+pub fn end_crawl() {
+    if let Ok(mut current) = CURRENT_CRAWL.lock() {
+        *current = None;
+    }
+}
+
+/// Settings snapshot stored with a project. API keys and proxy credentials are
+/// removed.
+pub fn settings_snapshot(settings: &Settings) -> Option<Value> {
+    let mut settings = settings.clone();
+    settings.page_speed_bulk_api_key = None;
+    settings.proxy_username.clear();
+    settings.proxy_password.clear();
+    serde_json::to_value(&settings).ok()
+}
+
+/// Registers a new crawl and returns it; its database file does not exist yet.
+pub fn create_project(domain: &str, settings: Option<Value>) -> Result<CrawlProject, String> {
+    let conn = open_registry()?;
+    std::fs::create_dir_all(db_dir()?.join("crawls"))
+        .map_err(|e| format!("Failed to create crawls directory: {}", e))?;
+
+    let now = chrono::Local::now();
+    let id = format!(
+        "{}-{}",
+        now.format("%Y%m%d-%H%M%S"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let project = CrawlProject {
+        name: format!("{} {}", domain, now.format("%Y-%m-%d %H:%M")),
+        domain: domain.to_string(),
+        started_at: now.to_rfc3339(),
+        finished_at: None,
+        status: "running".to_string(),
+        pages: 0,
+        settings,
+        db_file: format!("crawls/{}.db", id),
+        last_opened_at: Some(now.to_rfc3339()),
+        id,
+    };
+
+    conn.execute(
+        "INSERT INTO crawl_projects (id, name, domain, started_at, status, pages, settings, db_file, last_opened_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7, ?8)",
+        params![
+            project.id,
+            project.name,
+            project.domain,
+            project.started_at,
+            project.status,
+            project.settings.as_ref().map(|s| s.to_string()),
+            project.db_file,
+            project.last_opened_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(project)
+}
+
+/// Records how a crawl ended.
+pub fn finish_project(id: &str, status: &str, pages: i64) -> Result<(), String> {
+    let conn = open_registry()?;
+    conn.execute(
+        "UPDATE crawl_projects SET status = ?1, pages = ?2, finished_at = ?3 WHERE id = ?4",
+        params![status, pages, chrono::Local::now().to_rfc3339(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Flags a stored crawl as running again (used by `resume_crawl`).
+pub fn mark_project_running(id: &str) -> Result<(), String> {
+    let conn = open_registry()?;
+    conn.execute(
+        "UPDATE crawl_projects SET status = 'running', finished_at = NULL WHERE id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_project(id: &str) -> Result<Option<CrawlProject>, String> {
+    let conn = open_registry()?;
+    conn.query_row(
+        &format!("SELECT {} FROM crawl_projects WHERE id = ?1", PROJECT_COLUMNS),
+        params![id],
+        row_to_project,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn get_project_by_db_file(db_file: &str) -> Result<Option<CrawlProject>, String> {
+    let conn = open_registry()?;
+    conn.query_row(
+        &format!("SELECT {} FROM crawl_projects WHERE db_file = ?1", PROJECT_COLUMNS),
+        params![db_file],
+        row_to_project,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Database file of the most recently started or opened crawl, used as the
+/// default target for read commands after an app restart.
+pub fn latest_project_db_file() -> Option<String> {
+    let conn = open_registry().ok()?;
+    conn.query_row(
+        "SELECT db_file FROM crawl_projects
+         ORDER BY COALESCE(last_opened_at, started_at) DESC LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+#[tauri::command]
+pub fn list_crawl_projects_command() -> Result<Vec<CrawlProject>, String> {
+    let conn = open_registry()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM crawl_projects ORDER BY started_at DESC",
+            PROJECT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_project)
+        .map_err(|e| e.to_string())?;
+
+    let mut projects = Vec::new();
+    for row in rows {
+        projects.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(projects)
+}
+
+/// The crawl the read commands currently target.
+#[tauri::command]
+pub async fn get_active_crawl_project_command() -> Result<Option<CrawlProject>, String> {
+    // Resolves the default (most recent) crawl if nothing has been opened yet.
+    database::get_or_create_shared_db()
+        .await
+        .map_err(|e| e.to_string())?;
+    match database::active_crawl_db() {
+        Some(db_file) => get_project_by_db_file(&db_file),
+        None => Ok(None),
+    }
+}
+
+/// Opens (or re-opens) a stored crawl: every read command and export now targets it.
+#[tauri::command]
+pub fn open_crawl_project_command(crawl_id: String) -> Result<CrawlProject, String> {
+    let project = get_project(&crawl_id)?.ok_or_else(|| format!("Crawl {} not found", crawl_id))?;
+    if !db_dir()?.join(&project.db_file).exists() {
+        return Err(format!("Crawl data for {} is missing on disk", project.name));
+    }
+
+    database::open_crawl_db(&project.db_file).map_err(|e| e.to_string())?;
+    database::set_active_crawl_db(&project.db_file).map_err(|e| e.to_string())?;
+
+    let conn = open_registry()?;
+    conn.execute(
+        "UPDATE crawl_projects SET last_opened_at = ?1 WHERE id = ?2",
+        params![chrono::Local::now().to_rfc3339(), crawl_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(project)
+}
+
+#[tauri::command]
+pub fn rename_crawl_project_command(crawl_id: String, name: String) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Crawl name cannot be empty".to_string());
+    }
+    let conn = open_registry()?;
+    let updated = conn
+        .execute(
+            "UPDATE crawl_projects SET name = ?1 WHERE id = ?2",
+            params![name, crawl_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Crawl {} not found", crawl_id));
+    }
+    Ok(())
+}
+
+/// Deletes a stored crawl and its database file.
+#[tauri::command]
+pub fn delete_crawl_project_command(crawl_id: String) -> Result<(), String> {
+    if is_current_crawl(&crawl_id) {
+        return Err("Cannot delete a crawl while it is running".to_string());
+    }
+    let project = get_project(&crawl_id)?.ok_or_else(|| format!("Crawl {} not found", crawl_id))?;
+
+    database::close_crawl_db(&project.db_file).map_err(|e| e.to_string())?;
+    let db_path = db_dir()?.join(&project.db_file);
+    for suffix in ["", "-wal", "-shm"] {
+        let path = std::path::PathBuf::from(format!("{}{}", db_path.display(), suffix));
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+        }
+    }
+
+    let conn = open_registry()?;
+    conn.execute("DELETE FROM crawl_projects WHERE id = ?1", params![crawl_id])
+        .map_err(|e| e.to_string())?;
+
+    println!("Deleted crawl project: {}", crawl_id);
+    Ok(())
+}
@@ -4,30 +4,86 @@ use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::{Mutex, OnceCell};
+use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
+
+use super::helpers::crawl_budget::BudgetUsage;
+use super::helpers::normalize_url::normalize_url;
+use super::helpers::resources::ResourceRecord;
+use super::helpers::security_headers::{
+    HeaderCounts, SecurityHeadersReport, SecurityHeadersSummary,
+};
+use super::helpers::tls_audit::TlsHostReport;
+use super::helpers::spider_trap::SuspectedTrap;
+
+
+/// Connection pools for every crawl database opened so far, keyed by file name
+/// (relative to the app's `db` directory). Pools are reused so that frontend query
+/// commands don't create a new pool (and leak file descriptors) on every call.
+static CRAWL_DBS: Lazy<std::sync::Mutex<HashMap<String, Database>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// File name of the crawl that read commands currently target. `None` until a
+/// crawl is started or opened, in which case the most recent crawl is used.
+static ACTIVE_CRAWL_DB: Lazy<std::sync::RwLock<Option<String>>> =
+    Lazy::new(|| std::sync::RwLock::new(None));
+
+/// Get or create the Database for the currently open crawl (see `crawl_projects`).
+/// All read-only query commands should use this instead of Database::new().
+pub async fn get_or_create_shared_db() -> Result<Database, DatabaseError> {
+    let active = ACTIVE_CRAWL_DB.read().map_err(|_| DatabaseError::LockError)?.clone();
+    let db_file = match active {
+        Some(db_file) => db_file,
+        None => {
+            let db_file = tokio::task::spawn_blocking(super::crawl_projects::latest_project_db_file)
+                .await?
+                .unwrap_or_else(|| super::crawl_projects::LEGACY_DB_FILE.to_string());
+            set_active_crawl_db(&db_file)?;
+            db_file
+        }
+    };
+    open_crawl_db(&db_file)
+}
 
+/// Returns the (cached) Database for a crawl's database file.
+pub fn open_crawl_db(db_file: &str) -> Result<Database, DatabaseError> {
+    let mut dbs = CRAWL_DBS.lock().map_err(|_| DatabaseError::LockError)?;
+    if let Some(db) = dbs.get(db_file) {
+        return Ok(db.clone());
+    }
+    let db = Database::new(db_file)?;
+    dbs.insert(db_file.to_string(), db.clone());
+    Ok(db)
+}
 
-/// Lazy global shared Database instance for query commands.
-/// This prevents creating a new connection pool (and leaking file descriptors)
-/// every time a frontend query command is invoked.
-static SHARED_BATCHES_DB: OnceCell<Database> = OnceCell::const_new();
+/// Drops the cached pool for a crawl database so its file can be deleted.
+pub fn close_crawl_db(db_file: &str) -> Result<(), DatabaseError> {
+    CRAWL_DBS
+        .lock()
+        .map_err(|_| DatabaseError::LockError)?
+        .remove(db_file);
+    let mut active = ACTIVE_CRAWL_DB.write().map_err(|_| DatabaseError::LockError)?;
+    if active.as_deref() == Some(db_file) {
+        *active = None;
+    }
+    Ok(())
+}
 
-/// Get or create a shared Database instance for the deep_crawl_batches.db.
-/// All read-only query commands should use this instead of Database::new().
-pub async fn get_or_create_shared_db() -> Result<Database, DatabaseError> {
-    let db = SHARED_BATCHES_DB
-        .get_or_try_init(|| async {
-            let db = Database::new("deep_crawl_batches.db")?;
-            Ok::<Database, DatabaseError>(db)
-        })
-        .await?;
-    Ok(db.clone())
+/// File name of the crawl the read commands currently target, if resolved yet.
+pub fn active_crawl_db() -> Option<String> {
+    ACTIVE_CRAWL_DB.read().ok().and_then(|active| active.clone())
+}
+
+/// Points the read commands at a crawl's database file.
+pub fn set_active_crawl_db(db_file: &str) -> Result<(), DatabaseError> {
+    *ACTIVE_CRAWL_DB.write().map_err(|_| DatabaseError::LockError)? = Some(db_file.to_string());
+    Ok(())
 }
 
 #[derive(Error, Debug)]
@@ -182,6 +238,13 @@ impl Database {
                 CREATE INDEX IF NOT EXISTS idx_domain_crawl_url ON domain_crawl(url);
                 "#,
             )?;
+            ensure_excluded_urls_table(&conn)?;
+            ensure_crawl_budgets_table(&conn)?;
+            ensure_spider_traps_table(&conn)?;
+            ensure_sitemap_urls_table(&conn)?;
+            ensure_resources_table(&conn)?;
+            ensure_tls_audit_table(&conn)?;
+            super::checkpoint::ensure_checkpoint_tables(&conn)?;
             println!("Database schema initialized successfully");
             Ok(())
         })
@@ -263,6 +326,13 @@ impl Database {
                 DatabaseError::ConnectionError(format!("Failed to get connection for clear: {}", e))
             })?;
             let rows_affected = conn.execute("DELETE FROM domain_crawl", params![])?;
+            conn.execute("DELETE FROM excluded_urls", params![])?;
+            conn.execute("DELETE FROM crawl_budgets", params![])?;
+            conn.execute("DELETE FROM spider_traps", params![])?;
+            conn.execute("DELETE FROM sitemap_urls", params![])?;
+            conn.execute("DELETE FROM crawl_resources", params![])?;
+            conn.execute("DELETE FROM tls_audit", params![])?;
+            super::checkpoint::clear_checkpoint_tables(&conn)?;
             println!("Cleared database, affected {} rows", rows_affected);
             Ok(())
         })
@@ -345,7 +415,7 @@ impl Database {
                 "#
             )?;
 
-            let stats = stmt.query_row([], |row| {
+            let mut stats = stmt.query_row([], |row| {
                 Ok(serde_json::json!({
                     "pages": row.get::<_, i64>(0).unwrap_or(0),
                     "total_internal_links": row.get::<_, i64>(1).unwrap_or(0),
@@ -383,6 +453,41 @@ impl Database {
                 }))
             })?;
 
+            // Which crawl budgets ran out and how many URLs each left unvisited
+            ensure_crawl_budgets_table(&conn)?;
+            let mut stmt = conn.prepare(
+                "SELECT rule, bucket, max_urls, queued, skipped FROM crawl_budgets ORDER BY id",
+            )?;
+            let budgets: Vec<Value> = stmt
+                .query_map([], |row| {
+                    let max_urls: i64 = row.get(2)?;
+                    let queued: i64 = row.get(3)?;
+                    Ok(serde_json::json!({
+                        "rule": row.get::<_, String>(0)?,
+                        "bucket": row.get::<_, String>(1)?,
+                        "max_urls": max_urls,
+                        "queued": queued,
+                        "skipped": row.get::<_, i64>(4)?,
+                        "exhausted": queued >= max_urls,
+                    }))
+                })?
+                .filter_map(Result::ok)
+                .collect();
+            stats["budgets"] = Value::Array(budgets);
+
+            // Pages by how they were first discovered (link, sitemap, redirect...)
+            let mut stmt = conn.prepare(
+                "SELECT json_extract(data, '$.discovery.source'), COUNT(*) FROM domain_crawl
+                 WHERE json_extract(data, '$.discovery.source') IS NOT NULL GROUP BY 1",
+            )?;
+            let discovery_sources: serde_json::Map<String, Value> = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, Value::from(row.get::<_, i64>(1)?)))
+                })?
+                .filter_map(Result::ok)
+                .collect();
+            stats["discovery_sources"] = Value::Object(discovery_sources);
+
             Ok::<Value, DatabaseError>(stats)
         })
         .await??;
@@ -981,6 +1086,7 @@ impl Database {
                         .map(|a| Value::from(a.len()))
                         .unwrap_or(Value::from(0)),
                     "link_score": link_score.map(Value::from).unwrap_or(Value::Null),
+                    "blocked_by_robots": data.get("blocked_by_robots").cloned().unwrap_or(Value::Null),
                 });
 
                 results.push(light);
@@ -1043,6 +1149,704 @@ impl Database {
         .await?
     }
 
+    /// Stores the URLs the include/exclude rules kept out of the crawl, as
+    /// `(url, rule, found_on)` tuples.
+    pub async fn store_excluded_urls(
+        &self,
+        excluded: Vec<(String, String, String)>,
+    ) -> Result<(), DatabaseError> {
+        if excluded.is_empty() {
+            return Ok(());
+        }
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            ensure_excluded_urls_table(&conn)?;
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR REPLACE INTO excluded_urls (url, rule, found_on) VALUES (?1, ?2, ?3)",
+                )?;
+                for (url, rule, found_on) in &excluded {
+                    stmt.execute(params![url, rule, found_on])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Stores the end-of-crawl usage of every crawl budget bucket, replacing
+    /// the previous crawl's.
+    pub async fn store_crawl_budgets(&self, usage: Vec<BudgetUsage>) -> Result<(), DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            ensure_crawl_budgets_table(&conn)?;
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM crawl_budgets", params![])?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO crawl_budgets (rule, bucket, max_urls, queued, skipped) VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for bucket in &usage {
+                    stmt.execute(params![
+                        bucket.rule,
+                        bucket.bucket,
+                        bucket.max_urls as i64,
+                        bucket.queued as i64,
+                        bucket.skipped as i64
+                    ])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Stores the crawl's suspected spider traps, replacing the previous crawl's.
+    pub async fn store_spider_traps(&self, traps: Vec<SuspectedTrap>) -> Result<(), DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            ensure_spider_traps_table(&conn)?;
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM spider_traps", params![])?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO spider_traps (kind, pattern, count, data) VALUES (?1, ?2, ?3, ?4)",
+                )?;
+                for trap in &traps {
+                    stmt.execute(params![
+                        trap.kind.as_str(),
+                        trap.pattern,
+                        trap.count as i64,
+                        serde_json::to_string(trap)?
+                    ])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Suspected spider traps, most URLs first, plus the auto-stop decisions in
+    /// the order they were taken.
+    pub async fn get_spider_traps(&self) -> Result<Value, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            ensure_spider_traps_table(&conn)?;
+
+            let mut stmt = conn.prepare("SELECT data FROM spider_traps ORDER BY count DESC, pattern")?;
+            let traps: Vec<SuspectedTrap> = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .filter_map(Result::ok)
+                .filter_map(|data| serde_json::from_str(&data).ok())
+                .collect();
+
+            let mut decisions: Vec<Value> = traps
+                .iter()
+                .filter_map(|trap| {
+                    let stop = trap.stopped.as_ref()?;
+                    Some(serde_json::json!({
+                        "at": stop.at,
+                        "kind": trap.kind,
+                        "pattern": trap.pattern,
+                        "reason": stop.reason,
+                        "url": stop.url,
+                        "found_on": stop.found_on,
+                    }))
+                })
+                .collect();
+            decisions.sort_by(|a, b| a["at"].as_str().cmp(&b["at"].as_str()));
+
+            Ok(serde_json::json!({
+                "total": traps.len(),
+                "traps": traps,
+                "decisions": decisions,
+            }))
+        })
+        .await?
+    }
+
+    /// Stores the URLs listed in the site's sitemaps, replacing the previous crawl's.
+    pub async fn store_sitemap_urls(&self, urls: Vec<String>) -> Result<(), DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            ensure_sitemap_urls_table(&conn)?;
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM sitemap_urls", params![])?;
+            {
+                let mut stmt = tx.prepare("INSERT OR IGNORE INTO sitemap_urls (url) VALUES (?1)")?;
+                for url in &urls {
+                    stmt.execute(params![url])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn get_sitemap_urls(&self) -> Result<Vec<String>, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            ensure_sitemap_urls_table(&conn)?;
+            let mut stmt = conn.prepare("SELECT url FROM sitemap_urls ORDER BY url")?;
+            let urls = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .filter_map(Result::ok)
+                .collect();
+            Ok(urls)
+        })
+        .await?
+    }
+
+    /// Stores the resources requested in resource mode, replacing the previous crawl's.
+    pub async fn store_resources(&self, resources: Vec<ResourceRecord>) -> Result<(), DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            ensure_resources_table(&conn)?;
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM crawl_resources", params![])?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR REPLACE INTO crawl_resources (url, kind, status_code, size, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for resource in &resources {
+                    stmt.execute(params![
+                        resource.url,
+                        resource.kind.as_str(),
+                        resource.status_code,
+                        resource.size.map(|size| size as i64),
+                        serde_json::to_string(resource)?
+                    ])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Paged list of the resources requested in resource mode, largest first,
+    /// plus per-kind totals. `kind` narrows the rows to one kind ("image", "css"...).
+    pub async fn get_resources(
+        &self,
+        limit: i64,
+        offset: i64,
+        kind: Option<String>,
+    ) -> Result<Value, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            ensure_resources_table(&conn)?;
+
+            let mut stmt = conn.prepare(
+                "SELECT kind, COUNT(*), COALESCE(SUM(status_code IS NULL OR status_code >= 400), 0), COALESCE(SUM(size), 0)
+                 FROM crawl_resources GROUP BY kind ORDER BY COUNT(*) DESC",
+            )?;
+            let by_kind: Vec<Value> = stmt
+                .query_map([], |row| {
+                    Ok(serde_json::json!({
+                        "kind": row.get::<_, String>(0)?,
+                        "count": row.get::<_, i64>(1)?,
+                        "broken": row.get::<_, i64>(2)?,
+                        "total_size": row.get::<_, i64>(3)?,
+                    }))
+                })?
+                .filter_map(Result::ok)
+                .collect();
+
+            let total: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM crawl_resources WHERE ?1 IS NULL OR kind = ?1",
+                params![kind],
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = conn.prepare(
+                "SELECT data FROM crawl_resources WHERE ?1 IS NULL OR kind = ?1
+                 ORDER BY size DESC, url LIMIT ?2 OFFSET ?3",
+            )?;
+            let rows: Vec<Value> = stmt
+                .query_map(params![kind, limit, offset], |row| row.get::<_, String>(0))?
+                .filter_map(Result::ok)
+                .filter_map(|data| serde_json::from_str(&data).ok())
+                .collect();
+
+            Ok(serde_json::json!({
+                "total": total,
+                "by_kind": by_kind,
+                "rows": rows,
+            }))
+        })
+        .await?
+    }
+
+    /// Stores the per-host TLS audit, replacing the previous crawl's.
+    pub async fn store_tls_audit(&self, reports: Vec<TlsHostReport>) -> Result<(), DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            ensure_tls_audit_table(&conn)?;
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM tls_audit", params![])?;
+            {
+                let mut stmt =
+                    tx.prepare("INSERT OR REPLACE INTO tls_audit (host, data) VALUES (?1, ?2)")?;
+                for report in &reports {
+                    stmt.execute(params![report.host, serde_json::to_string(report)?])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// The per-host TLS audit of the last crawl, plus one entry per host and
+    /// issue (expiring certificates, incomplete chains, missing HSTS...).
+    pub async fn get_tls_audit(&self) -> Result<Value, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            ensure_tls_audit_table(&conn)?;
+
+            let mut stmt = conn.prepare("SELECT data FROM tls_audit ORDER BY host")?;
+            let hosts: Vec<TlsHostReport> = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .filter_map(Result::ok)
+                .filter_map(|data| serde_json::from_str(&data).ok())
+                .collect();
+            let issues: Vec<Value> = hosts
+                .iter()
+                .flat_map(|report| {
+                    report.issues.iter().map(|issue| {
+                        serde_json::json!({
+                            "host": report.host,
+                            "issue": issue,
+                            "days_until_expiry": report.days_until_expiry,
+                        })
+                    })
+                })
+                .collect();
+
+            Ok(serde_json::json!({
+                "hosts": hosts,
+                "issues": issues,
+            }))
+        })
+        .await?
+    }
+
+    /// Security header grades of the crawled pages, worst first, plus the
+    /// site-level summary (average score, pages per grade, missing/weak counts
+    /// per header).
+    pub async fn get_security_headers(&self, limit: i64, offset: i64) -> Result<Value, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+
+            let (pages, average_score): (i64, Option<f64>) = conn.query_row(
+                "SELECT COUNT(*), AVG(json_extract(data, '$.security_headers.score')) FROM domain_crawl
+                 WHERE json_extract(data, '$.security_headers') IS NOT NULL",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            let mut summary =
+                SecurityHeadersSummary::new(pages as usize, average_score.unwrap_or(0.0));
+
+            let mut stmt = conn.prepare(
+                "SELECT json_extract(data, '$.security_headers.grade'), COUNT(*) FROM domain_crawl
+                 WHERE json_extract(data, '$.security_headers') IS NOT NULL
+                 GROUP BY 1",
+            )?;
+            summary.grades = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize)))?
+                .filter_map(Result::ok)
+                .collect();
+
+            // Per page and header, whether any finding says missing; missing beats weak
+            let mut stmt = conn.prepare(
+                "SELECT header, SUM(missing), COUNT(*) - SUM(missing) FROM (
+                     SELECT domain_crawl.id, json_extract(finding.value, '$.header') AS header,
+                            MAX(json_extract(finding.value, '$.severity') = 'missing') AS missing
+                     FROM domain_crawl,
+                          json_each(domain_crawl.data, '$.security_headers.findings') AS finding
+                     GROUP BY domain_crawl.id, header
+                 )
+                 GROUP BY header",
+            )?;
+            let counts = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)? as usize,
+                        row.get::<_, i64>(2)? as usize,
+                    ))
+                })?
+                .filter_map(Result::ok);
+            for (header, missing, weak) in counts {
+                if let Ok(header) = serde_json::from_value(Value::String(header)) {
+                    summary.headers.insert(header, HeaderCounts { missing, weak });
+                }
+            }
+
+            let mut stmt = conn.prepare(
+                "SELECT url, json_extract(data, '$.security_headers') FROM domain_crawl
+                 WHERE json_extract(data, '$.security_headers') IS NOT NULL
+                 ORDER BY json_extract(data, '$.security_headers.score'), url
+                 LIMIT ?1 OFFSET ?2",
+            )?;
+            let rows: Vec<Value> = stmt
+                .query_map(params![limit, offset], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .filter_map(Result::ok)
+                .filter_map(|(url, data)| {
+                    let report: SecurityHeadersReport = serde_json::from_str(&data).ok()?;
+                    Some(serde_json::json!({
+                        "url": url,
+                        "score": report.score,
+                        "grade": report.grade,
+                        "findings": report.findings,
+                    }))
+                })
+                .collect();
+
+            Ok(serde_json::json!({
+                "summary": summary,
+                "rows": rows,
+            }))
+        })
+        .await?
+    }
+
+    /// Paged list of scope-excluded URLs plus per-rule totals.
+    pub async fn get_excluded_urls(&self, limit: i64, offset: i64) -> Result<Value, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            ensure_excluded_urls_table(&conn)?;
+
+            let total: i64 =
+                conn.query_row("SELECT COUNT(*) FROM excluded_urls", [], |row| row.get(0))?;
+
+            let mut stmt = conn.prepare(
+                "SELECT rule, COUNT(*) FROM excluded_urls GROUP BY rule ORDER BY COUNT(*) DESC",
+            )?;
+            let by_rule: Vec<Value> = stmt
+                .query_map([], |row| {
+                    Ok(serde_json::json!({
+                        "rule": row.get::<_, String>(0)?,
+                        "count": row.get::<_, i64>(1)?,
+                    }))
+                })?
+                .filter_map(Result::ok)
+                .collect();
+
+            let mut stmt = conn.prepare(
+                "SELECT url, rule, found_on FROM excluded_urls ORDER BY url LIMIT ?1 OFFSET ?2",
+            )?;
+            let rows: Vec<Value> = stmt
+                .query_map(params![limit, offset], |row| {
+                    Ok(serde_json::json!({
+                        "url": row.get::<_, String>(0)?,
+                        "rule": row.get::<_, String>(1)?,
+                        "found_on": row.get::<_, String>(2)?,
+                    }))
+                })?
+                .filter_map(Result::ok)
+                .collect();
+
+            Ok(serde_json::json!({
+                "total": total,
+                "by_rule": by_rule,
+                "rows": rows,
+            }))
+        })
+        .await?
+    }
+
+    /// Paged raw HTML vs rendered DOM differences for pages rendered with JavaScript,
+    /// plus totals. With `flagged_only`, only pages whose indexability signals or
+    /// internal links depend on JavaScript.
+    pub async fn get_render_diffs(
+        &self,
+        limit: i64,
+        offset: i64,
+        flagged_only: bool,
+    ) -> Result<Value, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            if !domain_crawl_exists(&conn) {
+                return Ok(serde_json::json!({
+                    "rendered": 0,
+                    "changed": 0,
+                    "indexability_depends_on_js": 0,
+                    "links_depend_on_js": 0,
+                    "total": 0,
+                    "rows": [],
+                }));
+            }
+
+            let (rendered, changed, indexability, links): (i64, i64, i64, i64) = conn.query_row(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(json_array_length(data, '$.render_diff.changes') > 0), 0),
+                        COALESCE(SUM(json_extract(data, '$.render_diff.indexability_depends_on_js')), 0),
+                        COALESCE(SUM(json_extract(data, '$.render_diff.links_depend_on_js')), 0)
+                 FROM domain_crawl
+                 WHERE json_type(data, '$.render_diff') = 'object'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )?;
+
+            let filter = if flagged_only {
+                "json_type(data, '$.render_diff') = 'object'
+                 AND (json_extract(data, '$.render_diff.indexability_depends_on_js')
+                      OR json_extract(data, '$.render_diff.links_depend_on_js'))"
+            } else {
+                "json_type(data, '$.render_diff') = 'object'
+                 AND (json_array_length(data, '$.render_diff.changes') > 0
+                      OR json_extract(data, '$.render_diff.js_only_links_count') > 0
+                      OR json_extract(data, '$.render_diff.raw_only_links_count') > 0
+                      OR json_extract(data, '$.render_diff.raw_word_count')
+                         != json_extract(data, '$.render_diff.rendered_word_count'))"
+            };
+
+            let total: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM domain_crawl WHERE {}", filter),
+                [],
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT url, json_extract(data, '$.render_diff')
+                 FROM domain_crawl WHERE {} ORDER BY url LIMIT ?1 OFFSET ?2",
+                filter
+            ))?;
+            let rows: Vec<Value> = stmt
+                .query_map(params![limit, offset], |row| {
+                    let diff: Option<String> = row.get(1)?;
+                    let mut diff = diff
+                        .and_then(|d| serde_json::from_str::<Value>(&d).ok())
+                        .unwrap_or(Value::Null);
+                    if let Value::Object(map) = &mut diff {
+                        map.insert("url".to_string(), Value::String(row.get(0)?));
+                    }
+                    Ok(diff)
+                })?
+                .filter_map(Result::ok)
+                .collect();
+
+            Ok(serde_json::json!({
+                "rendered": rendered,
+                "changed": changed,
+                "indexability_depends_on_js": indexability,
+                "links_depend_on_js": links,
+                "total": total,
+                "rows": rows,
+            }))
+        })
+        .await?
+    }
+
+    /// Paged mobile vs desktop parity results for pages that differ (or whose
+    /// counterpart fetch failed), plus how many pages have each kind of mismatch.
+    pub async fn get_mobile_parity(&self, limit: i64, offset: i64) -> Result<Value, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            if !domain_crawl_exists(&conn) {
+                return Ok(serde_json::json!({
+                    "checked": 0,
+                    "failed": 0,
+                    "by_mismatch": [],
+                    "total": 0,
+                    "rows": [],
+                }));
+            }
+
+            let (checked, failed): (i64, i64) = conn.query_row(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(json_type(data, '$.mobile_parity.error') = 'text'), 0)
+                 FROM domain_crawl
+                 WHERE json_type(data, '$.mobile_parity') = 'object'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            let mut stmt = conn.prepare(
+                "SELECT json_each.value, COUNT(*)
+                 FROM domain_crawl, json_each(data, '$.mobile_parity.mismatches')
+                 GROUP BY json_each.value ORDER BY COUNT(*) DESC",
+            )?;
+            let by_mismatch: Vec<Value> = stmt
+                .query_map([], |row| {
+                    Ok(serde_json::json!({
+                        "mismatch": row.get::<_, String>(0)?,
+                        "count": row.get::<_, i64>(1)?,
+                    }))
+                })?
+                .filter_map(Result::ok)
+                .collect();
+
+            let filter = "json_type(data, '$.mobile_parity') = 'object'
+                 AND (json_array_length(data, '$.mobile_parity.mismatches') > 0
+                      OR json_type(data, '$.mobile_parity.error') = 'text')";
+
+            let total: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM domain_crawl WHERE {}", filter),
+                [],
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT url, json_extract(data, '$.mobile_parity')
+                 FROM domain_crawl WHERE {} ORDER BY url LIMIT ?1 OFFSET ?2",
+                filter
+            ))?;
+            let rows: Vec<Value> = stmt
+                .query_map(params![limit, offset], |row| {
+                    let parity: Option<String> = row.get(1)?;
+                    let mut parity = parity
+                        .and_then(|p| serde_json::from_str::<Value>(&p).ok())
+                        .unwrap_or(Value::Null);
+                    if let Value::Object(map) = &mut parity {
+                        map.insert("url".to_string(), Value::String(row.get(0)?));
+                    }
+                    Ok(parity)
+                })?
+                .filter_map(Result::ok)
+                .collect();
+
+            Ok(serde_json::json!({
+                "checked": checked,
+                "failed": failed,
+                "by_mismatch": by_mismatch,
+                "total": total,
+                "rows": rows,
+            }))
+        })
+        .await?
+    }
+
+    /// Returns only the fields `crawl_compare` looks at, one object per page, so
+    /// comparing two large crawls doesn't deserialize every page blob.
+    pub async fn get_comparison_rows(&self) -> Result<Vec<Value>, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            if !domain_crawl_exists(&conn) {
+                return Ok(Vec::new());
+            }
+
+            let mut stmt = conn.prepare(
+                "SELECT url,
+                        json_extract(data, '$.status_code'),
+                        json_extract(data, '$.title[0].title'),
+                        json_extract(data, '$.description'),
+                        json_extract(data, '$.headings.h1'),
+                        json_extract(data, '$.canonicals'),
+                        json_extract(data, '$.meta_robots.meta_robots'),
+                        json_extract(data, '$.indexability.indexability_reason'),
+                        json_extract(data, '$.word_count'),
+                        json_extract(data, '$.response_time'),
+                        json_extract(data, '$.custom_search')
+                 FROM domain_crawl",
+            )?;
+
+            // Arrays come back from json_extract as JSON text
+            let json_text = |text: Option<String>| -> Value {
+                text.and_then(|t| serde_json::from_str(&t).ok())
+                    .unwrap_or(Value::Null)
+            };
+
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok(serde_json::json!({
+                        "url": row.get::<_, String>(0)?,
+                        "status_code": row.get::<_, Option<i64>>(1)?,
+                        "title": row.get::<_, Option<String>>(2)?,
+                        "description": row.get::<_, Option<String>>(3)?,
+                        "h1": json_text(row.get(4)?),
+                        "canonicals": json_text(row.get(5)?),
+                        "meta_robots": json_text(row.get(6)?),
+                        "indexability_reason": row.get::<_, Option<String>>(7)?,
+                        "word_count": row.get::<_, Option<i64>>(8)?,
+                        "response_time": row.get::<_, Option<f64>>(9)?,
+                        "custom_search": json_text(row.get(10)?),
+                    }))
+                })?
+                .filter_map(Result::ok)
+                .collect();
+
+            Ok(rows)
+        })
+        .await?
+    }
+
+    /// Number of distinct crawled pages linking to each internal URL, keyed by
+    /// the normalized target URL.
+    pub async fn get_inlink_counts(&self) -> Result<HashMap<String, u64>, DatabaseError> {
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            if !domain_crawl_exists(&conn) {
+                return Ok(HashMap::new());
+            }
+
+            // Linking pages are collected per normalized target, so a page linking
+            // to `/a` and `/a/` (or `/a?utm_source=x`) counts once
+            let mut stmt = conn.prepare(
+                "SELECT DISTINCT json_extract(link.value, '$.url'), domain_crawl.url
+                 FROM domain_crawl, json_each(domain_crawl.data, '$.inoutlinks_status_codes.internal') AS link
+                 WHERE json_extract(link.value, '$.url') IS NOT NULL",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+
+            let mut linking_pages: HashMap<String, HashSet<String>> = HashMap::new();
+            for row in rows {
+                let (target, page) = row?;
+                linking_pages
+                    .entry(normalize_url(&target))
+                    .or_default()
+                    .insert(page);
+            }
+            Ok(linking_pages
+                .into_iter()
+                .map(|(url, pages)| (url, pages.len() as u64))
+                .collect())
+        })
+        .await?
+    }
+
     /// Persists computed Link Score values (1-100) back onto their crawled page rows,
     /// keyed by URL. Adds the `link_score` column on first use if it isn't there yet.
     pub async fn store_link_scores(&self, scores: HashMap<String, u32>) -> Result<(), DatabaseError> {
@@ -1093,20 +1897,22 @@ impl Database {
     }
 }
 
+/// Whether the `domain_crawl` table exists yet (it doesn't before the first crawl).
+fn domain_crawl_exists(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='domain_crawl'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+    .unwrap_or(false)
+}
+
 /// Ensures the `domain_crawl` table has a `link_score` column, adding it if missing.
 /// No-op (rather than an error) if the table doesn't exist yet, since that's the
 /// normal state before any crawl has run.
 fn ensure_link_score_column(conn: &Connection) -> Result<(), DatabaseError> {
-    let table_exists: bool = conn
-        .query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='domain_crawl'",
-            [],
-            |row| row.get::<_, i64>(0),
-        )
-        .map(|count| count > 0)
-        .unwrap_or(false);
-
-    if !table_exists {
+    if !domain_crawl_exists(conn) {
         return Ok(());
     }
 
@@ -1124,6 +1930,94 @@ fn ensure_link_score_column(conn: &Connection) -> Result<(), DatabaseError> {
     Ok(())
 }
 
+/// Creates the `excluded_urls` table (URLs kept out of the crawl by the
+/// include/exclude rules) if it doesn't exist yet.
+fn ensure_excluded_urls_table(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS excluded_urls (
+            url TEXT PRIMARY KEY,
+            rule TEXT NOT NULL,
+            found_on TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Creates the `crawl_budgets` table (per-bucket usage of the crawl budgets)
+/// if it doesn't exist yet.
+fn ensure_crawl_budgets_table(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS crawl_budgets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rule TEXT NOT NULL,
+            bucket TEXT NOT NULL,
+            max_urls INTEGER NOT NULL,
+            queued INTEGER NOT NULL,
+            skipped INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Creates the `spider_traps` table (suspected traps of the last crawl, as
+/// JSON) if it doesn't exist yet.
+fn ensure_spider_traps_table(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS spider_traps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            count INTEGER NOT NULL,
+            data TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Creates the `sitemap_urls` table (URLs listed in the last crawl's sitemaps)
+/// if it doesn't exist yet.
+fn ensure_sitemap_urls_table(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sitemap_urls (
+            url TEXT PRIMARY KEY
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Creates the `crawl_resources` table (resources requested in resource mode,
+/// as JSON) if it doesn't exist yet.
+fn ensure_resources_table(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS crawl_resources (
+            url TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            status_code INTEGER,
+            size INTEGER,
+            data TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Creates the `tls_audit` table (one TLS report per crawled host, as JSON) if
+/// it doesn't exist yet.
+fn ensure_tls_audit_table(conn: &Connection) -> Result<(), DatabaseError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tls_audit (
+            host TEXT PRIMARY KEY,
+            data TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
 // Helper for file extension check. Deliberately an allowlist of known
 // downloadable-document/media/archive extensions, scoped to only the final
 // path segment (not the whole URL) — the previous denylist approach matched
@@ -1269,7 +2163,7 @@ pub fn create_diff_tables() -> Result<(), DatabaseError> {
     Ok(())
 }
 
-pub async fn clone_batched_crawl_into_persistent_db() -> Result<(), DatabaseError> {
+pub async fn clone_batched_crawl_into_persistent_db(db: &Database) -> Result<(), DatabaseError> {
     let project_dirs = ProjectDirs::from("", "", "rustyseo").ok_or_else(|| {
         DatabaseError::DirectoryError("Failed to get project directories".to_string())
     })?;
@@ -1277,7 +2171,6 @@ pub async fn clone_batched_crawl_into_persistent_db() -> Result<(), DatabaseErro
     let data_dir = project_dirs.data_dir();
     let db_dir = data_dir.join("db");
 
-    let db = Database::initialize_db(&db_dir.join("deep_crawl_batches.db")).await?;
     let urls = db.get_urls().await?;
 
     tokio::task::spawn_blocking(move || {
@@ -4,26 +4,47 @@ use serde_json::Value;
 use crate::{domain_crawler::domain_crawler, AppState};
 
 use super::{
+    checkpoint::{self, CrawlCheckpoint},
+    crawl_compare::{self, CrawlComparison},
+    crawl_overrides::CrawlOverrides,
+    crawl_projects,
     database::{self, DiffAnalysis},
     duplicate_content::{self, DuplicateGroup},
     excel::create_xlsx::{
-        generate_css_table, generate_excel_main_table, generate_excel_two_cols,
-        generate_keywords_excel, generate_links_table_excel, generate_xlsx,
+        generate_crawl_comparison_excel, generate_css_table, generate_excel_main_table,
+        generate_excel_two_cols, generate_keywords_excel, generate_links_table_excel,
+        generate_xlsx,
     },
+    helpers::{
+        crawl_auth::CrawlAuth,
+        url_rewrite::{RewritePreview, RewriteRule, UrlRewriter},
+    },
+    list_mode::{self, ListSource},
+    orphans::{self, OrphanReport, OrphanSource},
 };
 
-#[tauri::command]
-pub async fn domain_crawl_command(
-    domain: String,
-    app_handle: tauri::AppHandle,
-    settings_state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
+/// Registers a new stored crawl and creates its database ahead of the crawl. The
+/// new crawl becomes the one the read commands target. Fails while another crawl
+/// is running.
+pub(crate) async fn prepare_crawl_db(
+    domain: &str,
+    settings_state: &tauri::State<'_, AppState>,
+    overrides: Option<&CrawlOverrides>,
+) -> Result<(crawl_projects::CrawlProject, database::Database), String> {
+    crawl_projects::ensure_no_crawl_in_progress()?;
+    let mut settings = settings_state.settings.read().await.clone();
+    if let Some(overrides) = overrides {
+        overrides.apply_to(&mut settings);
+    }
+    let project = crawl_projects::create_project(domain, crawl_projects::settings_snapshot(&settings))?;
+
     // Create and initialize the database
-    let mut db = match database::Database::new("deep_crawl_batches.db") {
+    let mut db = match database::open_crawl_db(&project.db_file) {
         Ok(db) => db,
         Err(e) => {
             let error_msg = format!("Failed to create database: {}", e);
             eprintln!("{}", error_msg);
+            let _ = crawl_projects::finish_project(&project.id, "failed", 0);
             return Err(error_msg);
         }
     };
@@ -32,32 +53,197 @@ pub async fn domain_crawl_command(
     if let Err(e) = db.initialize().await {
         let error_msg = format!("Failed to initialize database: {}", e);
         eprintln!("{}", error_msg);
+        let _ = crawl_projects::finish_project(&project.id, "failed", 0);
         return Err(error_msg);
     }
 
-    // Clear existing data from the database
-    if let Err(e) = db.clear().await {
-        let error_msg = format!("Failed to clear database: {}", e);
-        eprintln!("{}", error_msg);
-        return Err(error_msg);
+    // Another crawl may have started while this one's database was created
+    if let Err(e) = crawl_projects::begin_crawl(&project.id) {
+        let _ = crawl_projects::finish_project(&project.id, "failed", 0);
+        return Err(e);
     }
+    database::set_active_crawl_db(&project.db_file).map_err(|e| {
+        crawl_projects::end_crawl();
+        e.to_string()
+    })?;
 
-    // Call the crawl_domain function with a clone of the database
-    match domain_crawler::crawl_domain(&domain, app_handle, Ok(db.clone()), settings_state).await {
+    Ok((project, db))
+}
+
+/// Logs the outcome of a finished crawl, records it on the stored project and
+/// passes the result through.
+pub(crate) async fn finish_crawl(
+    project_id: &str,
+    db: &database::Database,
+    result: Result<(), String>,
+) -> Result<(), String> {
+    crawl_projects::end_crawl();
+
+    // Verify database contents using the original db
+    let pages = match db.count_rows().await {
+        Ok(count) => {
+            println!("Database contains {} rows after crawl", count);
+            count
+        }
+        Err(e) => {
+            eprintln!("Failed to count rows: {}", e);
+            0
+        }
+    };
+
+    let status = match &result {
         Ok(_) => {
             println!("Crawl finished successfully.");
-            // Verify database contents using the original db
-            match db.count_rows().await {
-                Ok(count) => println!("Database contains {} rows after crawl", count),
-                Err(e) => eprintln!("Failed to count rows: {}", e),
+            // A stopped (or timed-out) crawl leaves a resumable checkpoint behind.
+            match checkpoint::load_checkpoint_info(db.get_pool()).await {
+                Ok(Some(checkpoint)) if checkpoint.is_resumable() => "stopped",
+                _ => "completed",
             }
-            Ok(())
         }
         Err(e) => {
             eprintln!("Crawl error: {}", e);
-            Err(e)
+            "failed"
         }
+    };
+    if let Err(e) = crawl_projects::finish_project(project_id, status, pages) {
+        eprintln!("Failed to update crawl project {}: {}", project_id, e);
     }
+
+    result
+}
+
+#[tauri::command]
+pub async fn domain_crawl_command(
+    domain: String,
+    app_handle: tauri::AppHandle,
+    settings_state: tauri::State<'_, AppState>,
+    overrides: Option<CrawlOverrides>,
+) -> Result<(), String> {
+    let (project, db) = prepare_crawl_db(&domain, &settings_state, overrides.as_ref()).await?;
+
+    // Call the crawl_domain function with a clone of the database
+    let result = domain_crawler::crawl_domain(
+        &domain,
+        app_handle,
+        Ok(db.clone()),
+        settings_state,
+        overrides,
+    )
+    .await;
+    finish_crawl(&project.id, &db, result).await
+}
+
+/// List mode: crawls exactly the URLs from a pasted list, a CSV/XLSX file or a
+/// single sitemap, without following links. Results land in `domain_crawl` like
+/// a regular crawl.
+#[tauri::command]
+pub async fn list_crawl_command(
+    source: ListSource,
+    app_handle: tauri::AppHandle,
+    settings_state: tauri::State<'_, AppState>,
+    overrides: Option<CrawlOverrides>,
+) -> Result<(), String> {
+    let label = match &source {
+        ListSource::Paste { .. } => "URL list".to_string(),
+        ListSource::File { path } => std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone()),
+        ListSource::Sitemap { url } => url.clone(),
+    };
+    // Reject an empty list or unreadable file before a project is created for it
+    list_mode::validate_list_source(&source).await?;
+    let (project, db) = prepare_crawl_db(&label, &settings_state, overrides.as_ref()).await?;
+
+    let result = domain_crawler::crawl_url_list(
+        &source,
+        app_handle,
+        Ok(db.clone()),
+        settings_state,
+        overrides,
+    )
+    .await;
+    finish_crawl(&project.id, &db, result).await
+}
+
+/// Continues a stored crawl from its checkpoint after `stop_crawl_command`, a crash
+/// or an app restart. Defaults to the open crawl when no `crawl_id` is given.
+/// Unlike the start commands this keeps the existing results. Credentials are
+/// never stored, so a crawl that ran with them needs `auth` passed again.
+#[tauri::command]
+pub async fn resume_crawl(
+    crawl_id: Option<String>,
+    auth: Option<CrawlAuth>,
+    app_handle: tauri::AppHandle,
+    settings_state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let project = match crawl_id {
+        Some(id) => crawl_projects::get_project(&id)?,
+        None => crawl_projects::get_active_crawl_project_command().await?,
+    }
+    .ok_or_else(|| "No stored crawl to resume".to_string())?;
+    crawl_projects::ensure_no_crawl_in_progress()?;
+
+    let mut db = database::open_crawl_db(&project.db_file).map_err(|e| {
+        let error_msg = format!("Failed to create database: {}", e);
+        eprintln!("{}", error_msg);
+        error_msg
+    })?;
+    db.initialize()
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    let (checkpoint, frontier, visited) = checkpoint::load_checkpoint(db.get_pool())
+        .await
+        .map_err(|e| format!("Failed to load crawl checkpoint: {}", e))?
+        .filter(|(checkpoint, _, _)| checkpoint.is_resumable())
+        .ok_or_else(|| "No interrupted crawl to resume".to_string())?;
+
+    crawl_projects::begin_crawl(&project.id)?;
+    if let Err(e) = crawl_projects::mark_project_running(&project.id) {
+        crawl_projects::end_crawl();
+        return Err(e);
+    }
+    database::set_active_crawl_db(&project.db_file).map_err(|e| {
+        crawl_projects::end_crawl();
+        e.to_string()
+    })?;
+
+    let result = domain_crawler::resume_crawl(
+        checkpoint,
+        frontier,
+        visited,
+        auth,
+        app_handle,
+        Ok(db.clone()),
+        settings_state,
+    )
+    .await;
+    finish_crawl(&project.id, &db, result).await
+}
+
+/// Returns the last crawl's checkpoint so the UI can offer to resume it.
+#[tauri::command]
+pub async fn get_crawl_checkpoint_command() -> Result<Option<CrawlCheckpoint>, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    checkpoint::load_checkpoint_info(db.get_pool())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Shows how a sample URL is rewritten, rule by rule. Uses `rules` when given
+/// (e.g. unsaved edits in the settings form), otherwise the saved rules.
+#[tauri::command]
+pub async fn preview_url_rewrite_command(
+    url: String,
+    rules: Option<Vec<RewriteRule>>,
+    settings_state: tauri::State<'_, AppState>,
+) -> Result<RewritePreview, String> {
+    let rules = match rules {
+        Some(rules) => rules,
+        None => settings_state.settings.read().await.url_rewrite_rules.clone(),
+    };
+    UrlRewriter::from_rules(&rules)?.preview(url.trim())
 }
 
 #[tauri::command]
@@ -162,7 +348,8 @@ pub async fn get_url_diff_command() -> Result<DiffAnalysis, String> {
 
 #[tauri::command]
 pub async fn clone_crawl_data_command() -> Result<(), String> {
-    database::clone_batched_crawl_into_persistent_db()
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    database::clone_batched_crawl_into_persistent_db(&db)
         .await
         .map_err(|e| e.to_string())
 }
@@ -220,6 +407,64 @@ pub async fn get_crawl_total_count_command(search: Option<String>) -> Result<i64
     db.get_crawl_total_count(search).await.map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_excluded_urls_command(limit: i64, offset: i64) -> Result<Value, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    db.get_excluded_urls(limit, offset).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_spider_traps_command() -> Result<Value, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    db.get_spider_traps().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_render_diffs_command(
+    limit: i64,
+    offset: i64,
+    flagged_only: Option<bool>,
+) -> Result<Value, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    db.get_render_diffs(limit, offset, flagged_only.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_mobile_parity_command(limit: i64, offset: i64) -> Result<Value, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    db.get_mobile_parity(limit, offset).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_crawl_resources_command(
+    limit: i64,
+    offset: i64,
+    kind: Option<String>,
+) -> Result<Value, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    db.get_resources(limit, offset, kind).await.map_err(|e| e.to_string())
+}
+
+// Per-host TLS report of the last crawl: certificate expiry, issuer, SAN
+// coverage and chain, the homepage's HTTP→HTTPS redirect and HSTS, plus the
+// issues found on each host.
+#[tauri::command]
+pub async fn get_tls_audit_command() -> Result<Value, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    db.get_tls_audit().await.map_err(|e| e.to_string())
+}
+
+// Security response header grades per crawled URL (CSP, HSTS,
+// X-Content-Type-Options, framing, Referrer-Policy, Permissions-Policy), worst
+// first, with the site-level summary.
+#[tauri::command]
+pub async fn get_security_headers_command(limit: i64, offset: i64) -> Result<Value, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    db.get_security_headers(limit, offset).await.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_crawl_summary_stats_command() -> Result<Value, String> {
     let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
@@ -236,6 +481,54 @@ pub async fn get_link_scores_command() -> Result<Value, String> {
     serde_json::to_value(&scores).map_err(|e| e.to_string())
 }
 
+// Orphan pages of the current crawl: URLs known from its sitemaps, Search Console,
+// the active log analysis and GA4 that no crawled page links to. GA4 isn't stored
+// locally, so the frontend passes the page paths from its own GA4 report. With
+// `crawl`, orphans the crawl never reached are fetched for their status and
+// indexability, with `auth` for sites behind a login (credentials aren't stored
+// with the crawl).
+#[tauri::command]
+pub async fn get_orphan_pages_command(
+    ga4_paths: Option<Vec<String>>,
+    crawl: Option<bool>,
+    auth: Option<CrawlAuth>,
+) -> Result<OrphanReport, String> {
+    let db = database::get_or_create_shared_db().await.map_err(|e| e.to_string())?;
+    let pages = db.get_all_crawl_data().await.map_err(|e| e.to_string())?;
+    let Some(origin) = orphans::site_origin(&pages) else {
+        return Ok(OrphanReport::default());
+    };
+
+    let mut known: Vec<(String, OrphanSource)> = db
+        .get_sitemap_urls()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|url| (url, OrphanSource::Sitemap))
+        .collect();
+    known.extend(orphans::gsc_urls().into_iter().map(|url| (url, OrphanSource::Gsc)));
+    known.extend(
+        orphans::log_paths()
+            .iter()
+            .filter_map(|path| orphans::resolve_path(&origin, path))
+            .map(|url| (url, OrphanSource::Logs)),
+    );
+    known.extend(
+        ga4_paths
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|path| orphans::resolve_path(&origin, path))
+            .map(|url| (url, OrphanSource::Ga4)),
+    );
+
+    let mut found = orphans::find_orphans(&pages, known);
+    if crawl.unwrap_or(false) {
+        let settings = crate::settings::settings::load_settings().await?;
+        orphans::fetch_uncrawled(&mut found, &origin, &settings, auth).await?;
+    }
+    Ok(orphans::report(found))
+}
+
 // EXPORT DATA DIRECTLY FROM DATABASE - BYPASS FRONTEND MEMORY LIMITS
 
 #[tauri::command]
@@ -383,3 +676,47 @@ pub async fn export_cwv_to_excel_command() -> Result<Vec<u8>, String> {
         _ => Err("Invalid data format for CWV".to_string()),
     }
 }
+
+/// Loads the compared fields of every page of a stored crawl.
+async fn load_crawl_snapshots(crawl_id: &str) -> Result<Vec<crawl_compare::PageSnapshot>, String> {
+    let project = crawl_projects::get_project(crawl_id)?
+        .ok_or_else(|| format!("Crawl {} not found", crawl_id))?;
+    let db = database::open_crawl_db(&project.db_file).map_err(|e| e.to_string())?;
+
+    let rows = db.get_comparison_rows().await.map_err(|e| e.to_string())?;
+    let inlinks = db.get_inlink_counts().await.map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| crawl_compare::snapshot_from_row(row, &inlinks))
+        .collect())
+}
+
+// Field-level comparison between two stored crawls: new, removed and changed
+// pages with before/after values.
+#[tauri::command]
+pub async fn compare_crawls_command(
+    previous_crawl_id: String,
+    current_crawl_id: String,
+) -> Result<CrawlComparison, String> {
+    if previous_crawl_id == current_crawl_id {
+        return Err("Pick two different crawls to compare".to_string());
+    }
+
+    let previous = load_crawl_snapshots(&previous_crawl_id).await?;
+    let current = load_crawl_snapshots(&current_crawl_id).await?;
+
+    let mut comparison = crawl_compare::compare_crawls(previous, current);
+    comparison.previous_crawl_id = previous_crawl_id;
+    comparison.current_crawl_id = current_crawl_id;
+    Ok(comparison)
+}
+
+#[tauri::command]
+pub async fn export_crawl_comparison_command(
+    previous_crawl_id: String,
+    current_crawl_id: String,
+) -> Result<Vec<u8>, String> {
+    let comparison = compare_crawls_command(previous_crawl_id, current_crawl_id).await?;
+    generate_crawl_comparison_excel(&comparison)
+}
@@ -5,6 +5,7 @@
 //! the `url_processor` module.
 
 use rand::seq::IndexedRandom;
+use reqwest::cookie::Jar;
 use reqwest::Client;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -22,10 +23,27 @@ use crate::domain_crawler::helpers::sitemap;
 use crate::domain_crawler::helpers::normalize_url::normalize_url;
 use crate::AppState;
 
+use super::checkpoint::{self, CheckpointStatus, CrawlCheckpoint};
+use super::crawl_overrides::CrawlOverrides;
 use super::database::{self, Database, DatabaseError};
+use super::helpers::crawl_auth::{self, CrawlAuth, CrawlCredentials};
+use super::helpers::crawl_budget::CrawlBudgets;
+use super::helpers::form_login::{self, LoginSession};
+use super::helpers::browser_pool::BrowserPool;
+use super::helpers::mobile_parity::ParityChecker;
+use super::helpers::proxy::ProxyConfig;
+use super::helpers::rate_limit;
+use super::helpers::resources::{self, ResourceCollector};
+use super::helpers::spider_trap::TrapDetector;
+use super::helpers::tls_audit;
+use super::helpers::url_rewrite::UrlRewriter;
+use super::helpers::url_scope::UrlScope;
+use super::list_mode::{self, ListSource};
+use super::models::DiscoverySource;
 use super::helpers::links_status_code_checker::SharedLinkChecker;
 use super::state::{to_database_results, CrawlerState, FailedUrl, ProgressData};
 use super::url_processor::process_url;
+use super::user_agents::CrawlIdentity;
 
 /// Payload for the `crawl_rate_limited` event so the frontend can surface
 /// server-driven backoff instead of the crawl silently appearing slow.
@@ -39,19 +57,140 @@ struct RateLimitedData {
     status: u16,
 }
 
+/// Payload for the `crawl_scope_excluded` event: how many discovered URLs the
+/// include/exclude rules kept out of the crawl, broken down by rule.
+#[derive(Clone, serde::Serialize)]
+struct ScopeExcludedData {
+    total: usize,
+    by_rule: std::collections::HashMap<String, usize>,
+}
+
+/// Payload for the `crawl_spider_traps` event: how many suspected traps the
+/// crawl found and how many of them auto-stop cut off.
+#[derive(Clone, serde::Serialize)]
+struct SpiderTrapsData {
+    total: usize,
+    stopped: usize,
+}
+
+/// Payload for the `crawl_resources` event: how many resources were requested in
+/// resource mode and how many of them failed or returned an error status.
+#[derive(Clone, serde::Serialize)]
+struct ResourcesData {
+    total: usize,
+    errors: usize,
+}
+
+/// Payload for the `crawl_tls_audit` event: how many hosts were audited and how
+/// many of them have at least one TLS issue.
+#[derive(Clone, serde::Serialize)]
+struct TlsAuditData {
+    hosts: usize,
+    with_issues: usize,
+}
+
+/// What a crawl starts from: a domain to spider, a fixed list of URLs, or the
+/// frontier and visited set of an interrupted crawl.
+enum CrawlTarget<'a> {
+    Domain(&'a str),
+    List(&'a ListSource),
+    Resume {
+        checkpoint: CrawlCheckpoint,
+        frontier: Vec<(String, usize)>,
+        visited: Vec<String>,
+    },
+}
+
 /// Main entry point for domain crawling
 pub async fn crawl_domain(
     domain: &str,
     app_handle: tauri::AppHandle,
     db: Result<Database, DatabaseError>,
     settings_state: tauri::State<'_, AppState>,
+    overrides: Option<CrawlOverrides>,
+) -> Result<(), String> {
+    run_crawl(CrawlTarget::Domain(domain), app_handle, db, settings_state, overrides).await
+}
+
+/// List mode entry point: fetches exactly the URLs in `source` through the same
+/// pipeline as a domain crawl, without following links or reading sitemaps.
+pub async fn crawl_url_list(
+    source: &ListSource,
+    app_handle: tauri::AppHandle,
+    db: Result<Database, DatabaseError>,
+    settings_state: tauri::State<'_, AppState>,
+    overrides: Option<CrawlOverrides>,
 ) -> Result<(), String> {
-    let settings = settings_state.settings.read().await.clone();
+    run_crawl(CrawlTarget::List(source), app_handle, db, settings_state, overrides).await
+}
+
+/// Continues a crawl from its last checkpoint, with the overrides it was started with.
+/// Credentials aren't checkpointed, so a crawl that ran with them needs `auth` again.
+/// Results are added to the existing `domain_crawl` rows rather than replacing them.
+pub async fn resume_crawl(
+    checkpoint: CrawlCheckpoint,
+    frontier: Vec<(String, usize)>,
+    visited: Vec<String>,
+    auth: Option<CrawlAuth>,
+    app_handle: tauri::AppHandle,
+    db: Result<Database, DatabaseError>,
+    settings_state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let mut overrides = checkpoint.overrides.clone().unwrap_or_default();
+    if overrides.requires_auth && auth.is_none() {
+        return Err(
+            "This crawl used credentials, which are not stored; provide them again to resume it"
+                .to_string(),
+        );
+    }
+    overrides.auth = auth;
+    let overrides = Some(overrides);
+    let target = CrawlTarget::Resume {
+        checkpoint,
+        frontier,
+        visited,
+    };
+    run_crawl(target, app_handle, db, settings_state, overrides).await
+}
+
+async fn run_crawl(
+    target: CrawlTarget<'_>,
+    app_handle: tauri::AppHandle,
+    db: Result<Database, DatabaseError>,
+    settings_state: tauri::State<'_, AppState>,
+    overrides: Option<CrawlOverrides>,
+) -> Result<(), String> {
+    let mut settings = settings_state.settings.read().await.clone();
+    if let Some(overrides) = &overrides {
+        overrides.apply_to(&mut settings);
+    }
+    let form_login = overrides
+        .as_ref()
+        .and_then(|o| o.auth.as_ref())
+        .and_then(|a| a.form_login.clone());
+    if form_login.is_some() {
+        // Following a logout link would end the session we just logged in with.
+        settings
+            .crawl_exclude_patterns
+            .push(form_login::LOGOUT_PATTERN.to_string());
+    }
+
+    // Compile the include/exclude rules up front so a bad regex fails the crawl
+    // immediately rather than silently crawling the wrong set of URLs.
+    let url_scope = UrlScope::from_patterns(
+        &settings.crawl_include_patterns,
+        &settings.crawl_exclude_patterns,
+    )?;
+    let url_scope = (!url_scope.is_empty()).then(|| Arc::new(url_scope));
+    let url_rewriter = UrlRewriter::from_rules(&settings.url_rewrite_rules)?;
+    let url_rewriter = (!url_rewriter.is_empty()).then(|| Arc::new(url_rewriter));
+    let crawl_budgets = CrawlBudgets::from_rules(&settings.crawl_budgets)?;
+    let crawl_budgets = (!crawl_budgets.is_empty()).then_some(crawl_budgets);
+
     let crawl_control = settings_state.crawl_control.clone();
     crawl_control.store(0, Ordering::Relaxed);
 
     // Extract all settings values we need to avoid borrowing issues
-    let _user_agents = settings.user_agents.clone();
     let client_timeout = settings.client_timeout;
     let client_connect_timeout = settings.client_connect_timeout;
     let concurrent_requests = settings.concurrent_requests;
@@ -61,46 +200,137 @@ pub async fn crawl_domain(
     let batch_size = settings.batch_size;
     let crawl_timeout = settings.crawl_timeout;
     let db_batch_size = settings.db_batch_size;
-    let base_delay = settings.base_delay;
-    let max_delay = settings.max_delay;
+    let mut base_delay = settings.base_delay;
+    let mut max_delay = settings.max_delay;
     let adaptive_crawling = settings.adaptive_crawling;
-    let min_crawl_delay = settings.min_crawl_delay;
-
-    let selected_user_agent = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".to_string();
-
-    let mut default_headers = reqwest::header::HeaderMap::new();
-    default_headers.insert(reqwest::header::ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7".parse().unwrap());
-    default_headers.insert(reqwest::header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
-    default_headers.insert("Sec-Ch-Ua", "\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\", \"Not-A.Brand\";v=\"99\"".parse().unwrap());
-    default_headers.insert("Sec-Ch-Ua-Mobile", "?0".parse().unwrap());
-    default_headers.insert("Sec-Ch-Ua-Platform", "\"Windows\"".parse().unwrap());
-    default_headers.insert("Sec-Fetch-Dest", "document".parse().unwrap());
-    default_headers.insert("Sec-Fetch-Mode", "navigate".parse().unwrap());
-    default_headers.insert("Sec-Fetch-Site", "none".parse().unwrap());
-    default_headers.insert("Sec-Fetch-User", "?1".parse().unwrap());
-    default_headers.insert(reqwest::header::UPGRADE_INSECURE_REQUESTS, "1".parse().unwrap());
-    default_headers.insert(reqwest::header::CACHE_CONTROL, "max-age=0".parse().unwrap());
-
-    let client = Client::builder()
-        .cookie_store(true)
-        .user_agent(&selected_user_agent)
-        .default_headers(default_headers)
-        .http1_only() // Bypasses Cloudflare's strict HTTP/2 reqwest fingerprinting
-        .timeout(Duration::from_secs(client_timeout))
-        .connect_timeout(Duration::from_secs(client_connect_timeout))
-        .redirect(reqwest::redirect::Policy::none())
-        .build()
-        .map_err(|e| e.to_string())?;
-
-
-
-    let url_checked = url_check(domain);
-    let base_url = Url::parse(&url_checked).map_err(|_| "Invalid URL")?;
+    let mut min_crawl_delay = settings.min_crawl_delay;
+    let checkpoint_interval = settings.checkpoint_interval;
+    let tls_expiry_warning_days = settings.tls_expiry_warning_days;
+
+    // The crawl identity drives the User-Agent and Sec-* headers of every client
+    // (pages, link checks, headless Chrome) and the robots.txt group we obey.
+    let identity = CrawlIdentity::from_settings(&settings);
+    tracing::info!(
+        "Crawling as {} ({})",
+        identity.preset.key(),
+        identity.user_agent
+    );
+
+    // Session cookies from the crawl's auth are seeded into this jar once the
+    // crawl's hosts are known.
+    let cookie_jar = Arc::new(Jar::default());
+
+    // Every client below waits on the same per-host buckets
+    rate_limit::configure(&settings);
+
+    // An invalid proxy fails the crawl rather than silently connecting directly.
+    let proxy = ProxyConfig::from_settings(&settings)?;
+    // Page clients for an identity; the parity check's counterpart client shares
+    // the cookie jar (and so the crawl's session) with the main one.
+    let build_page_client = |identity: &CrawlIdentity| -> Result<Client, String> {
+        let mut default_headers = identity.default_headers();
+        default_headers.insert(reqwest::header::UPGRADE_INSECURE_REQUESTS, "1".parse().unwrap());
+        default_headers.insert(reqwest::header::CACHE_CONTROL, "max-age=0".parse().unwrap());
+
+        let mut client_builder = Client::builder();
+        if let Some(proxy) = &proxy {
+            client_builder = proxy.apply(client_builder);
+        }
+
+        client_builder
+            .cookie_provider(cookie_jar.clone())
+            .user_agent(&identity.user_agent)
+            .default_headers(default_headers)
+            .http1_only() // Bypasses Cloudflare's strict HTTP/2 reqwest fingerprinting
+            .timeout(Duration::from_secs(client_timeout))
+            .connect_timeout(Duration::from_secs(client_connect_timeout))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())
+    };
+    let client = build_page_client(&identity)?;
+    let parity_checker = if settings.mobile_parity_check {
+        let counterpart = identity.counterpart();
+        tracing::info!("Mobile/desktop parity check as {}", counterpart.user_agent);
+        Some(Arc::new(ParityChecker::new(
+            build_page_client(&counterpart)?,
+            counterpart,
+        )))
+    } else {
+        None
+    };
+
+
+
+    // In list mode the first URL's origin stands in for the "domain" (robots.txt,
+    // favicon, history entry); every URL in the list is still fetched as given.
+    let (base_url, url_list, list_mode, resume_from) = match target {
+        CrawlTarget::Domain(domain) => {
+            let url_checked = url_check(domain);
+            (Url::parse(&url_checked).map_err(|_| "Invalid URL")?, None, false, None)
+        }
+        CrawlTarget::List(source) => {
+            let urls = list_mode::resolve_list_source(source, &client).await?;
+            tracing::info!("List mode: {} URLs supplied", urls.len());
+            (urls[0].join("/").map_err(|_| "Invalid URL")?, Some(urls), true, None)
+        }
+        CrawlTarget::Resume {
+            checkpoint,
+            frontier,
+            visited,
+        } => {
+            tracing::info!(
+                "Resuming crawl of {}: {} URLs in frontier, {} visited",
+                checkpoint.base_url,
+                frontier.len(),
+                visited.len()
+            );
+            let base_url = Url::parse(&checkpoint.base_url).map_err(|_| "Invalid URL")?;
+            let list_mode = checkpoint.list_mode;
+            (base_url, None, list_mode, Some((checkpoint, frontier, visited)))
+        }
+    };
+    let resuming = resume_from.is_some();
     let domain = base_url.clone();
 
+    // Credentials are bound to the hosts this crawl starts from: the base host, and
+    // in list mode the hosts of the supplied (or still queued) URLs.
+    let credentials = match overrides.as_ref().and_then(|o| o.auth.clone()) {
+        Some(auth) if !auth.is_empty() => {
+            let mut hosts = crawl_auth::crawl_hosts(&base_url);
+            if list_mode {
+                let list_urls = url_list.iter().flatten().cloned();
+                let frontier_urls = resume_from
+                    .iter()
+                    .flat_map(|(_, frontier, _)| frontier.iter())
+                    .filter_map(|(url, _)| Url::parse(url).ok());
+                for url in list_urls.chain(frontier_urls) {
+                    hosts.extend(crawl_auth::crawl_hosts(&url));
+                }
+                hosts.sort();
+                hosts.dedup();
+            }
+            let credentials = CrawlCredentials::new(auth, hosts, cookie_jar.clone())?;
+            tracing::info!("Crawl auth enabled for {} host(s)", credentials.hosts().count());
+            Some(Arc::new(credentials))
+        }
+        _ => None,
+    };
+
+    // Log in before anything else is fetched, so every request (robots.txt
+    // included) carries the session cookies.
+    let login_session = match (form_login, &credentials) {
+        (Some(config), Some(credentials)) => {
+            let session = LoginSession::new(config, identity.clone(), proxy.clone())?;
+            session.login(&client, credentials).await?;
+            Some(Arc::new(session))
+        }
+        _ => None,
+    };
+
     //TODO: Better to check the efficiency of this
     // INITIAL DATA FETCHING (Robots.txt)
-    let robots_data = robots::get_robots_data(&domain).await;
+    let robots_data = robots::get_robots_data(&domain, &client).await;
     let robots_blocked = robots_data
         .as_ref()
         .map(|d| d.blocked_urls.clone())
@@ -109,6 +339,24 @@ pub async fn crawl_domain(
     // Emit initial blocked info
     let _ = app_handle.emit("robots_blocked", &robots_blocked);
 
+    // Select the robots.txt group for our user agent. Its Allow/Disallow rules gate
+    // every URL before it is queued, and its Crawl-delay becomes the floor for the
+    // delay between requests (the global stagger below spaces requests by it).
+    let robots_matcher = if settings.obey_robots_txt {
+        robots_data
+            .as_ref()
+            .map(|d| Arc::new(d.rules.matcher_for(&identity.robots_token)))
+    } else {
+        None
+    };
+    if let Some(crawl_delay) = robots_matcher.as_ref().and_then(|m| m.crawl_delay()) {
+        let crawl_delay_ms = crawl_delay.as_millis() as u64;
+        tracing::info!("robots.txt Crawl-delay: {}ms", crawl_delay_ms);
+        base_delay = base_delay.max(crawl_delay_ms);
+        min_crawl_delay = min_crawl_delay.max(crawl_delay_ms);
+        max_delay = max_delay.max(crawl_delay_ms);
+    }
+
     // BACKGROUND TASKS (UI Updates & Favicon)
     let app_handle_for_spawn = app_handle.clone();
     let domain_clone = domain.clone();
@@ -149,27 +397,104 @@ pub async fn crawl_domain(
 
     let link_checker = Arc::new(SharedLinkChecker::new(
         &settings,
-        Some(selected_user_agent.clone()),
+        Some(&identity),
+        credentials.clone(),
         url_status_registry.clone(),
     ));
+    let browser_pool = settings
+        .javascript_rendering
+        .then(|| Arc::new(BrowserPool::new(js_concurrency, proxy.clone())));
     let state = Arc::new(Mutex::new(
         CrawlerState::new(None)
             .with_link_checker(link_checker.clone())
-            .with_url_status_registry(url_status_registry),
+            .with_url_status_registry(url_status_registry)
+            .with_robots(robots_matcher)
+            .with_scope(url_scope)
+            .with_url_rewriter(url_rewriter)
+            .with_budgets(crawl_budgets)
+            .with_spider_traps(TrapDetector::new(
+                settings.spider_trap_auto_stop,
+                settings.spider_trap_threshold,
+            ))
+            .with_resources(settings.crawl_resources.then(ResourceCollector::new))
+            .with_credentials(credentials)
+            .with_login_session(login_session)
+            .with_browser_pool(browser_pool)
+            .with_parity_checker(parity_checker)
+            .with_spidering(!list_mode)
+            .with_checkpointing(checkpoint_interval > 0),
     )); // DB is handled separately
-    {
+    if let Some((checkpoint, frontier, visited)) = resume_from {
+        let mut state_guard = state.lock().await;
+        state_guard.restore_checkpoint(
+            frontier,
+            visited,
+            checkpoint.crawled_urls,
+            checkpoint.failed_urls,
+        );
+    } else if let Some(urls) = url_list {
+        // Seed every supplied URL at depth 0. Scope rules only apply to discovered
+        // URLs, and robots.txt is only known for the base host.
+        let mut state_guard = state.lock().await;
+        for url in urls {
+            let normalized = normalize_url(url.as_str());
+            let Ok(normalized_url_obj) = Url::parse(&normalized) else {
+                continue;
+            };
+            if state_guard.total_urls >= settings.max_urls_per_domain {
+                tracing::warn!(
+                    "List mode: stopping at max_urls_per_domain ({})",
+                    settings.max_urls_per_domain
+                );
+                break;
+            }
+            if state_guard.queued_url_set.contains(&normalized) {
+                continue;
+            }
+            if normalized_url_obj.host_str() == base_url.host_str()
+                && !state_guard.robots_allows(&normalized_url_obj, &normalized)
+            {
+                continue;
+            }
+            state_guard.note_discovery(&normalized, DiscoverySource::List, None);
+            state_guard.queue.push_back((normalized_url_obj, 0));
+            state_guard.queued_url_set.insert(normalized);
+            state_guard.total_urls += 1;
+        }
+    } else {
         let normalized_base = normalize_url(base_url.as_str());
         let normalized_url_obj = Url::parse(&normalized_base).unwrap_or_else(|_| base_url.clone());
         
         let mut state_guard = state.lock().await;
-        state_guard.queue.push_back((normalized_url_obj, 0)); // Start at depth 0
-        state_guard.queued_url_set.insert(normalized_base.clone());
-        state_guard.total_urls = 1;
+        if state_guard.robots_allows(&normalized_url_obj, &normalized_base) {
+            if let Some(budgets) = state_guard.budgets.as_mut() {
+                budgets.charge(&normalized_url_obj);
+            }
+            state_guard.note_discovery(&normalized_base, DiscoverySource::Start, None);
+            state_guard.queue.push_back((normalized_url_obj, 0)); // Start at depth 0
+            state_guard.queued_url_set.insert(normalized_base.clone());
+            state_guard.total_urls = 1;
+        } else {
+            tracing::warn!("Start URL {} is disallowed by robots.txt", normalized_base);
+        }
         // pending_urls is populated at dequeue time (in the main loop batch drain), not here.
     }
 
-    // DISCOVER URLS FROM SITEMAPS
-    let sitemap_urls = sitemap::extract_urls_from_sitemaps(&domain, &client).await;
+    // DISCOVER URLS FROM SITEMAPS (list mode crawls exactly the supplied URLs; a
+    // resumed crawl already has its sitemap URLs in the checkpointed frontier)
+    let sitemap_urls = if list_mode || resuming {
+        Default::default()
+    } else {
+        sitemap::extract_urls_from_sitemaps(&domain, &client).await
+    };
+    // Kept for the orphan report: sitemap URLs that no crawled page links to.
+    if !resuming {
+        if let Ok(db) = &db {
+            if let Err(e) = db.store_sitemap_urls(sitemap_urls.iter().cloned().collect()).await {
+                tracing::error!("Failed to store sitemap URLs: {}", e);
+            }
+        }
+    }
     if !sitemap_urls.is_empty() {
         tracing::info!("Found {} URLs in sitemaps", sitemap_urls.len());
         let mut state_guard = state.lock().await;
@@ -254,10 +579,39 @@ pub async fn crawl_domain(
 
     let mut last_log_time = Instant::now();
 
+    // Checkpoint right away so even a crawl that dies in its first minute can be resumed.
+    let checkpoint_pool = if checkpoint_interval > 0 {
+        db.as_ref().ok().map(|database| database.get_pool())
+    } else {
+        None
+    };
+    let save_checkpoint = |status: CheckpointStatus| {
+        let state = state.clone();
+        let pool = checkpoint_pool.clone();
+        let base_url = base_url.to_string();
+        let overrides = overrides.clone();
+        async move {
+            let Some(pool) = pool else {
+                return;
+            };
+            let snapshot = state.lock().await.checkpoint_snapshot();
+            if let Err(e) =
+                checkpoint::save_checkpoint(pool, base_url, list_mode, overrides, status, snapshot)
+                    .await
+            {
+                tracing::error!("Failed to save crawl checkpoint: {}", e);
+            }
+        }
+    };
+    save_checkpoint(CheckpointStatus::Running).await;
+    let mut last_checkpoint = Instant::now();
+    let mut interrupted = false;
+
     while state.lock().await.should_continue() {
         let control_status = crawl_control.load(Ordering::Relaxed);
         if control_status == 2 {
             tracing::info!("Crawl stopped by user.");
+            interrupted = true;
             break;
         }
         if control_status == 1 {
@@ -297,6 +651,7 @@ pub async fn crawl_domain(
                     state_guard.active_tasks,
                     state_guard.total_failed_count
                 );
+                rate_limit::prune();
                 last_log_time = Instant::now();
             }
 
@@ -335,7 +690,8 @@ pub async fn crawl_domain(
             // But actually, the inner loop handles the semaphore, so we just pull a reasonable batch
             let available_batch = std::cmp::min(batch_size, state_guard.queue.len());
             let batch: Vec<(url::Url, usize)> = state_guard.queue.drain(..available_batch).collect();
-            for (url, _) in &batch {
+            for (url, depth) in &batch {
+                state_guard.in_flight.insert(url.to_string(), *depth);
                 let url_str = normalize_url(url.as_str());
                 state_guard.queued_url_set.remove(&url_str);
                 // Move from "queued" to "pending" (actively being fetched).
@@ -352,7 +708,13 @@ pub async fn crawl_domain(
 
         for (url, depth) in to_spawn {
             let client_clone = client.clone();
-            let base_url_clone = base_url.clone();
+            // A list can span hosts, so internal/external links are judged
+            // against each URL's own origin rather than the first URL's.
+            let base_url_clone = if list_mode {
+                url.join("/").unwrap_or_else(|_| base_url.clone())
+            } else {
+                base_url.clone()
+            };
             let state_clone = state.clone();
             let app_handle_clone = app_handle.clone();
             let semaphore_clone = semaphore.clone();
@@ -526,11 +888,13 @@ pub async fn crawl_domain(
 
                 let mut state_guard = state_clone.lock().await;
                 state_guard.pending_urls.remove(&url_str);
+                state_guard.in_flight.remove(&url_str);
                 state_guard.active_tasks = state_guard.active_tasks.saturating_sub(1);
                 state_guard.active_urls.remove(&url_str);
 
                 if let Err(e) = result {
                     tracing::error!("Failed to process {}: {}", url_str, e);
+                    state_guard.forget_discovery(&url_str);
                     state_guard.record_failure(FailedUrl {
                         url: url_str,
                         error: e,
@@ -545,13 +909,31 @@ pub async fn crawl_domain(
         if crawl_start_time.elapsed() > Duration::from_secs(crawl_timeout) {
             tracing::info!("Crawl timeout reached, terminating...");
             app_handle.emit("crawl_interrupted", ()).unwrap_or_default();
+            interrupted = true;
             break;
         }
 
+        if checkpoint_interval > 0
+            && last_checkpoint.elapsed() > Duration::from_secs(checkpoint_interval)
+        {
+            save_checkpoint(CheckpointStatus::Running).await;
+            last_checkpoint = Instant::now();
+        }
+
         // Small sleep to prevent tight-looping the state lock
         sleep(Duration::from_millis(50)).await;
     }
 
+    rate_limit::prune();
+
+    // A stopped or timed-out crawl keeps its frontier so `resume_crawl` can continue it.
+    save_checkpoint(if interrupted {
+        CheckpointStatus::Stopped
+    } else {
+        CheckpointStatus::Completed
+    })
+    .await;
+
     // Final cleanup and status report
     {
 
@@ -577,6 +959,145 @@ pub async fn crawl_domain(
         tracing::info!("  Final completion: {:.2}%", final_percentage);
     }
 
+    // Report URLs that robots.txt kept us from crawling as result rows, so they
+    // appear in the tables/exports with the rule (and line) that blocked them.
+    {
+        let mut state_guard = state.lock().await;
+        let blocked: Vec<_> = state_guard.robots_blocked.drain().collect();
+        if !blocked.is_empty() {
+            tracing::info!("  URLs blocked by robots.txt: {}", blocked.len());
+        }
+        for (url, block) in blocked {
+            let result = super::models::DomainCrawlResults {
+                url: url.clone(),
+                original_url: url,
+                indexability: super::helpers::indexability::Indexability {
+                    indexability: 0.0,
+                    indexability_reason: format!(
+                        "Blocked by robots.txt (line {}: {})",
+                        block.line, block.rule
+                    ),
+                    directives: Vec::new(),
+                },
+                url_depth: None,
+                blocked_by_robots: Some(block),
+                ..Default::default()
+            };
+            if state_guard.pending_results.len() < settings.max_urls_stored {
+                state_guard
+                    .pending_results
+                    .push(super::models::LightCrawlResult::from_full(&result));
+            }
+            if let Ok(db_result) = to_database_results(&result) {
+                let _ = db_tx.send(db_result).await;
+            }
+        }
+    }
+
+    // Persist and summarise URLs the include/exclude rules kept out of the crawl.
+    {
+        let mut state_guard = state.lock().await;
+        if state_guard.scope_excluded_count > 0 {
+            let total = state_guard.scope_excluded_count;
+            let excluded: Vec<(String, String, String)> = state_guard
+                .scope_excluded
+                .drain()
+                .map(|(url, (rule, found_on))| (url, rule, found_on))
+                .collect();
+            drop(state_guard);
+
+            let mut by_rule = std::collections::HashMap::new();
+            for (_, rule, _) in &excluded {
+                *by_rule.entry(rule.clone()).or_insert(0) += 1;
+            }
+            tracing::info!("  URLs excluded by scope rules: {}", total);
+
+            if let Ok(db) = &db {
+                if let Err(e) = db.store_excluded_urls(excluded).await {
+                    tracing::error!("Failed to store excluded URLs: {}", e);
+                }
+            }
+            let _ = app_handle.emit("crawl_scope_excluded", ScopeExcludedData { total, by_rule });
+        }
+    }
+
+    // Persist and summarise how the crawl budgets were used.
+    {
+        let state_guard = state.lock().await;
+        if let Some(budgets) = state_guard.budgets.as_ref() {
+            let usage = budgets.report();
+            drop(state_guard);
+
+            for bucket in usage.iter().filter(|b| b.exhausted) {
+                tracing::info!(
+                    "  Budget exhausted: {} {} ({} queued, {} left unvisited)",
+                    bucket.rule,
+                    bucket.bucket,
+                    bucket.queued,
+                    bucket.skipped
+                );
+            }
+            if let Ok(db) = &db {
+                if let Err(e) = db.store_crawl_budgets(usage.clone()).await {
+                    tracing::error!("Failed to store crawl budgets: {}", e);
+                }
+            }
+            let _ = app_handle.emit("crawl_budgets", &usage);
+        }
+    }
+
+    // Persist the suspected spider traps and the auto-stop decisions.
+    {
+        let traps = state.lock().await.spider_traps.report();
+        if !traps.is_empty() {
+            let stopped = traps.iter().filter(|t| t.stopped.is_some()).count();
+            tracing::info!("  Suspected spider traps: {} ({} stopped)", traps.len(), stopped);
+            if let Ok(db) = &db {
+                if let Err(e) = db.store_spider_traps(traps.clone()).await {
+                    tracing::error!("Failed to store spider traps: {}", e);
+                }
+            }
+            let _ = app_handle.emit(
+                "crawl_spider_traps",
+                SpiderTrapsData {
+                    total: traps.len(),
+                    stopped,
+                },
+            );
+        }
+    }
+
+    // Resource mode: request every collected resource once and store the results.
+    {
+        let (collected, credentials) = {
+            let mut state_guard = state.lock().await;
+            let collected = state_guard.resources.as_mut().map(|r| r.take());
+            (collected, state_guard.credentials.clone())
+        };
+        if let Some(collected) = collected.filter(|c| !c.is_empty()) {
+            tracing::info!("  Fetching {} referenced resources", collected.len());
+            let resources = resources::fetch_all(
+                &client,
+                collected,
+                concurrent_requests,
+                credentials.as_deref(),
+            )
+            .await;
+            let errors = resources
+                .iter()
+                .filter(|r| r.error.is_some() || r.status_code.is_some_and(|code| code >= 400))
+                .count();
+            tracing::info!("  Resources: {} ({} broken)", resources.len(), errors);
+            let total = resources.len();
+            if let Ok(db) = &db {
+                if let Err(e) = db.store_resources(resources).await {
+                    tracing::error!("Failed to store resources: {}", e);
+                }
+            }
+            let _ = app_handle.emit("crawl_resources", ResourcesData { total, errors });
+        }
+    }
+
     // Flush any remaining buffered crawl results before completing
     {
         let mut state_guard = state.lock().await;
@@ -595,6 +1116,34 @@ pub async fn crawl_domain(
         handle.await.unwrap_or_default();
     }
 
+    // TLS audit: certificate, chain, HTTP→HTTPS redirect and HSTS, once per host.
+    // Opt-in, skipped for stopped crawls, and run after the results are flushed
+    // so they aren't held back by the handshakes.
+    if settings.tls_audit && !interrupted {
+        let hosts = tls_audit::crawled_hosts(&state.lock().await.visited);
+        if !hosts.is_empty() {
+            tracing::info!("  Auditing TLS on {} hosts", hosts.len());
+            let reports = tls_audit::audit_all(
+                &client,
+                &hosts,
+                proxy.is_none(),
+                Duration::from_secs(client_connect_timeout),
+                tls_expiry_warning_days,
+                concurrent_requests,
+            )
+            .await;
+            let with_issues = reports.iter().filter(|r| !r.issues.is_empty()).count();
+            tracing::info!("  TLS audit: {} hosts ({} with issues)", reports.len(), with_issues);
+            let hosts = reports.len();
+            if let Ok(db) = &db {
+                if let Err(e) = db.store_tls_audit(reports).await {
+                    tracing::error!("Failed to store TLS audit: {}", e);
+                }
+            }
+            let _ = app_handle.emit("crawl_tls_audit", TlsAuditData { hosts, with_issues });
+        }
+    }
+
     // Crawl Analysis: Link Score. Runs automatically at the end of every crawl when
     // enabled in Settings, and must complete before `crawl_complete` is emitted below
     // so the frontend's post-crawl refetch already sees the persisted scores.
@@ -656,8 +1205,10 @@ pub async fn crawl_domain(
         eprintln!("Failed to create diff tables: {}", e);
     }
 
-    if let Err(e) = database::clone_batched_crawl_into_persistent_db().await {
-        eprintln!("Failed to clone batched crawl into persistent db: {}", e);
+    if let Ok(db) = &db {
+        if let Err(e) = database::clone_batched_crawl_into_persistent_db(db).await {
+            eprintln!("Failed to clone batched crawl into persistent db: {}", e);
+        }
     }
 
     // --- RECORD HISTORY (Backend-driven) ---
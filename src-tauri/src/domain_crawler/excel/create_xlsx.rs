@@ -1,6 +1,8 @@
 use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
 use serde_json::Value;
 
+use crate::domain_crawler::crawl_compare::{ChangeCategory, CrawlComparison};
+
 #[tauri::command]
 pub fn generate_xlsx(data: Vec<Value>) -> Result<Vec<u8>, String> {
     // println!("Received Data: {:?}", data);
@@ -1086,3 +1088,96 @@ pub fn generate_cwv_excel(data: Vec<Value>) -> Result<Vec<u8>, String> {
     let buffer = workbook.save_to_buffer().map_err(|e| e.to_string())?;
     Ok(buffer)
 }
+
+// COMPARISON BETWEEN TWO STORED CRAWLS: A SUMMARY SHEET AND ONE ROW PER CHANGED FIELD
+pub fn generate_crawl_comparison_excel(comparison: &CrawlComparison) -> Result<Vec<u8>, String> {
+    let mut workbook = Workbook::new();
+
+    let header_format = Format::new()
+        .set_bold()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Center);
+
+    let summary = &comparison.summary;
+    let summary_sheet = workbook.add_worksheet();
+    summary_sheet.set_name("Summary").map_err(|e| e.to_string())?;
+
+    let mut summary_rows: Vec<(String, String)> = vec![
+        ("Previous crawl".to_string(), comparison.previous_crawl_id.clone()),
+        ("Current crawl".to_string(), comparison.current_crawl_id.clone()),
+        ("New".to_string(), summary.new.to_string()),
+        ("Removed".to_string(), summary.removed.to_string()),
+        ("Changed".to_string(), summary.changed.to_string()),
+        ("Unchanged".to_string(), summary.unchanged.to_string()),
+    ];
+    summary_rows.extend(
+        summary
+            .by_field
+            .iter()
+            .map(|(field, count)| (format!("Changed: {}", field), count.to_string())),
+    );
+
+    for (col_idx, header) in ["Metric", "Value"].iter().enumerate() {
+        summary_sheet
+            .write_with_format(0, col_idx as u16, *header, &header_format)
+            .map_err(|e| format!("Failed to write header '{}': {}", header, e))?;
+    }
+    for (row_idx, (metric, value)) in summary_rows.iter().enumerate() {
+        summary_sheet
+            .write((row_idx + 1) as u32, 0, metric)
+            .map_err(|e| e.to_string())?;
+        summary_sheet
+            .write((row_idx + 1) as u32, 1, value)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let changes_sheet = workbook.add_worksheet();
+    changes_sheet.set_name("Changes").map_err(|e| e.to_string())?;
+
+    let headers = ["URL", "Category", "Field", "Before", "After"];
+    for (col_idx, header) in headers.iter().enumerate() {
+        changes_sheet
+            .write_with_format(0, col_idx as u16, *header, &header_format)
+            .map_err(|e| format!("Failed to write header '{}': {}", header, e))?;
+    }
+
+    let mut row_idx: u32 = 1;
+    for entry in &comparison.urls {
+        let category = match entry.category {
+            ChangeCategory::New => "New",
+            ChangeCategory::Removed => "Removed",
+            ChangeCategory::Changed => "Changed",
+            ChangeCategory::Unchanged => "Unchanged",
+        };
+
+        if entry.changes.is_empty() {
+            let row_data = [entry.url.as_str(), category, "", "", ""];
+            for (col_idx, cell) in row_data.iter().enumerate() {
+                changes_sheet
+                    .write(row_idx, col_idx as u16, *cell)
+                    .map_err(|e| e.to_string())?;
+            }
+            row_idx += 1;
+            continue;
+        }
+
+        for change in &entry.changes {
+            let row_data = [
+                entry.url.as_str(),
+                category,
+                change.field.as_str(),
+                change.before.as_deref().unwrap_or(""),
+                change.after.as_deref().unwrap_or(""),
+            ];
+            for (col_idx, cell) in row_data.iter().enumerate() {
+                changes_sheet
+                    .write(row_idx, col_idx as u16, *cell)
+                    .map_err(|e| e.to_string())?;
+            }
+            row_idx += 1;
+        }
+    }
+
+    let buffer = workbook.save_to_buffer().map_err(|e| e.to_string())?;
+    Ok(buffer)
+}
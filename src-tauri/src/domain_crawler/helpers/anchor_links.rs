@@ -158,6 +158,36 @@ pub fn extract_internal_external_links(
     })
 }
 
+/// Internal and external links of a document that has no anchor markup (a PDF's
+/// link annotations), so they have no anchor text or attributes.
+pub fn links_from_urls(
+    hrefs: &[String],
+    resolve_url: &Url,
+    scope_url: &Url,
+) -> InternalExternalLinks {
+    let (internal, external): (Vec<Url>, Vec<Url>) = hrefs
+        .iter()
+        .map(|href| resolve_relative_url(href, resolve_url))
+        .partition(|url| is_internal_link(url, scope_url));
+    let to_strings = |links: Vec<Url>| links.into_iter().map(String::from).collect::<Vec<_>>();
+    let (internal, external) = (to_strings(internal), to_strings(external));
+    let absolute: Vec<String> = internal.iter().chain(&external).cloned().collect();
+    let anchors = |links: Vec<String>| LinksAnchors {
+        anchors: vec![String::new(); links.len()],
+        rels: vec![None; links.len()],
+        titles: vec![None; links.len()],
+        targets: vec![None; links.len()],
+        inlinks: LinkTypes {
+            relative: links.clone(),
+            absolute: absolute.clone(),
+        },
+        links,
+    };
+    InternalExternalLinks {
+        internal: anchors(internal),
+        external: anchors(external),
+    }
+}
 
 /// Resolves a URL relative to a base URL.
 ///
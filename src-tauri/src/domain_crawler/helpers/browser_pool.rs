@@ -0,0 +1,61 @@
+//! Headless Chrome instances shared by a crawl's JS renders, so Chrome is
+//! launched once per render slot instead of once per page. Each render opens a
+//! new tab and closes it afterwards; the browser goes back to the pool.
+//!
+//! Renders are limited to `javascript_concurrency` by the crawl's JS semaphore,
+//! so the pool never holds more browsers than that.
+
+use headless_chrome::Browser;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::headless_fetch::launch_browser_with_idle_timeout;
+use super::proxy::ProxyConfig;
+
+/// How long a pooled browser may go without commands before headless_chrome
+/// drops its connection (e.g. while the crawl is paused). Dropped browsers are
+/// replaced on the next checkout.
+const POOLED_BROWSER_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+pub struct BrowserPool {
+    idle: Mutex<Vec<Browser>>,
+    size: usize,
+    proxy: Option<ProxyConfig>,
+}
+
+impl BrowserPool {
+    pub fn new(size: usize, proxy: Option<ProxyConfig>) -> Self {
+        let size = size.max(1);
+        BrowserPool {
+            idle: Mutex::new(Vec::with_capacity(size)),
+            size,
+            proxy,
+        }
+    }
+
+    pub fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    /// Takes an idle browser, or launches one when none is left. Idle browsers
+    /// that no longer respond are discarded.
+    pub fn checkout(&self) -> Result<Browser, String> {
+        loop {
+            let browser = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            match browser {
+                Some(browser) if browser.get_version().is_ok() => return Ok(browser),
+                Some(_) => tracing::debug!("Discarding unresponsive pooled browser"),
+                None => break,
+            }
+        }
+        launch_browser_with_idle_timeout(self.proxy.as_ref(), POOLED_BROWSER_IDLE_TIMEOUT)
+    }
+
+    /// Returns a browser to the pool. Browsers beyond the pool size are closed.
+    pub fn checkin(&self, browser: Browser) {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        if idle.len() < self.size {
+            idle.push(browser);
+        }
+    }
+}
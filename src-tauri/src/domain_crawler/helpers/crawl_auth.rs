@@ -0,0 +1,487 @@
+//! Credentials for crawling sites that sit behind a login wall (typically staging
+//! sites): HTTP basic or digest auth, extra request headers (e.g. a bearer token)
+//! seeded session cookies and form-based login (see `form_login`).
+//!
+//! Credentials are only ever attached to requests for the crawl's own hosts.
+//! External links checked by the link checker never see them, and redirects
+//! that leave those hosts are not followed with them attached.
+
+use md5::{Digest, Md5};
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use super::form_login::FormLogin;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthScheme {
+    #[default]
+    None,
+    Basic,
+    Digest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// Per-crawl authentication, passed in through `CrawlOverrides::auth`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrawlAuth {
+    #[serde(default)]
+    pub scheme: AuthScheme,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// Sent with every request to the crawl's hosts, e.g. `Authorization: Bearer ...`
+    #[serde(default)]
+    pub headers: Vec<AuthHeader>,
+    /// Session cookies as `name=value` (several can be joined with `;`)
+    #[serde(default)]
+    pub cookies: Vec<String>,
+    /// Log in through a form before crawling
+    #[serde(default)]
+    pub form_login: Option<FormLogin>,
+}
+
+impl CrawlAuth {
+    pub fn is_empty(&self) -> bool {
+        self.scheme == AuthScheme::None
+            && self.headers.is_empty()
+            && self.cookies.is_empty()
+            && self.form_login.is_none()
+    }
+
+    /// Individual `(name, value)` cookie pairs.
+    fn cookie_pairs(&self) -> Vec<(String, String)> {
+        self.cookies
+            .iter()
+            .flat_map(|line| line.split(';'))
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                let name = name.trim();
+                (!name.is_empty()).then(|| (name.to_string(), value.trim().to_string()))
+            })
+            .collect()
+    }
+}
+
+/// The hosts credentials may be sent to for a crawl starting at `url`: its host
+/// plus the `www.` / bare variant, which most sites redirect between.
+pub fn crawl_hosts(url: &Url) -> Vec<String> {
+    let Some(host) = url.host_str() else {
+        return Vec::new();
+    };
+    let host = host.to_ascii_lowercase();
+    let variant = match host.strip_prefix("www.") {
+        Some(bare) => bare.to_string(),
+        None => format!("www.{}", host),
+    };
+    vec![host, variant]
+}
+
+/// `CrawlAuth` bound to the hosts of one crawl, shared by every client of that crawl.
+pub struct CrawlCredentials {
+    auth: CrawlAuth,
+    hosts: Arc<HashSet<String>>,
+    headers: HeaderMap,
+    /// The crawl's cookie store, shared by the page client and the link checker so
+    /// session cookies (seeded or from a form login) reach both.
+    jar: Arc<Jar>,
+    digest: Mutex<Option<DigestChallenge>>,
+    nonce_count: AtomicU32,
+}
+
+impl CrawlCredentials {
+    /// Seeds the configured cookies into `jar`. Fails on header names or values
+    /// that can't be sent.
+    pub fn new(auth: CrawlAuth, hosts: Vec<String>, jar: Arc<Jar>) -> Result<Self, String> {
+        let mut headers = HeaderMap::new();
+        for header in &auth.headers {
+            let name = HeaderName::from_bytes(header.name.trim().as_bytes())
+                .map_err(|_| format!("Invalid header name: {}", header.name))?;
+            let value = HeaderValue::from_str(header.value.trim())
+                .map_err(|_| format!("Invalid value for header {}", header.name))?;
+            headers.insert(name, value);
+        }
+
+        let credentials = CrawlCredentials {
+            auth,
+            hosts: Arc::new(hosts.into_iter().map(|h| h.to_ascii_lowercase()).collect()),
+            headers,
+            jar,
+            digest: Mutex::new(None),
+            nonce_count: AtomicU32::new(0),
+        };
+        credentials.seed_cookies();
+        Ok(credentials)
+    }
+
+    pub fn auth(&self) -> &CrawlAuth {
+        &self.auth
+    }
+
+    pub fn scheme(&self) -> AuthScheme {
+        self.auth.scheme
+    }
+
+    pub fn username(&self) -> &str {
+        &self.auth.username
+    }
+
+    pub fn password(&self) -> &str {
+        &self.auth.password
+    }
+
+    pub fn hosts(&self) -> impl Iterator<Item = &String> {
+        self.hosts.iter()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn cookie_jar(&self) -> Arc<Jar> {
+        self.jar.clone()
+    }
+
+    /// Cookies the jar would send to `url`, as `(name, value)` pairs.
+    pub fn cookies_for(&self, url: &Url) -> Vec<(String, String)> {
+        self.jar
+            .cookies(url)
+            .and_then(|header| header.to_str().ok().map(str::to_string))
+            .map(|header| {
+                header
+                    .split(';')
+                    .filter_map(|pair| {
+                        let (name, value) = pair.split_once('=')?;
+                        Some((name.trim().to_string(), value.trim().to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether credentials may be attached to a request for `url`.
+    pub fn covers(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| self.hosts.contains(&host.to_ascii_lowercase()))
+    }
+
+    /// Adds the configured cookies to the jar, scoped to each crawl host.
+    fn seed_cookies(&self) {
+        let pairs = self.auth.cookie_pairs();
+        if pairs.is_empty() {
+            return;
+        }
+        for host in self.hosts.iter() {
+            let Ok(url) = Url::parse(&format!("https://{}/", host)) else {
+                continue;
+            };
+            for (name, value) in &pairs {
+                self.jar
+                    .add_cookie_str(&format!("{}={}; Path=/", name, value), &url);
+            }
+        }
+    }
+
+    /// Redirect policy for clients that follow redirects (the link checker): a
+    /// request that went out with credentials is not followed off the crawl's hosts,
+    /// since custom headers would be re-sent to the new host.
+    pub fn redirect_policy(&self, max_redirects: usize) -> reqwest::redirect::Policy {
+        let hosts = self.hosts.clone();
+        reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > max_redirects {
+                return attempt.error("too many redirects");
+            }
+            let on_host = |url: &Url| {
+                url.host_str()
+                    .is_some_and(|h| hosts.contains(&h.to_ascii_lowercase()))
+            };
+            let started_on_host = attempt.previous().first().is_some_and(on_host);
+            if started_on_host && !on_host(attempt.url()) {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        })
+    }
+
+    /// Attaches headers and the `Authorization` header to a request for `url`.
+    pub fn apply(&self, mut builder: RequestBuilder, method: &Method, url: &Url) -> RequestBuilder {
+        if !self.covers(url) {
+            return builder;
+        }
+        builder = builder.headers(self.headers.clone());
+
+        match self.auth.scheme {
+            AuthScheme::None => builder,
+            AuthScheme::Basic => builder.basic_auth(&self.auth.username, Some(&self.auth.password)),
+            AuthScheme::Digest => {
+                let challenge = self.digest.lock().unwrap().clone();
+                match challenge {
+                    Some(challenge) => {
+                        let nc = self.nonce_count.fetch_add(1, Ordering::Relaxed) + 1;
+                        match challenge.authorization(
+                            &self.auth.username,
+                            &self.auth.password,
+                            method.as_str(),
+                            &request_uri(url),
+                            nc,
+                        ) {
+                            Some(value) => builder.header(AUTHORIZATION, value),
+                            None => builder,
+                        }
+                    }
+                    None => builder,
+                }
+            }
+        }
+    }
+
+    /// Picks up a digest challenge from a 401. Returns `true` when the request
+    /// should be retried with the new challenge.
+    fn accept_challenge(&self, response: &Response) -> bool {
+        if self.auth.scheme != AuthScheme::Digest || response.status() != StatusCode::UNAUTHORIZED {
+            return false;
+        }
+        let challenge = response
+            .headers()
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .find_map(DigestChallenge::parse);
+
+        let Some(challenge) = challenge else {
+            return false;
+        };
+        if !challenge.is_supported() {
+            tracing::warn!("Unsupported digest algorithm: {}", challenge.algorithm);
+            return false;
+        }
+        *self.digest.lock().unwrap() = Some(challenge);
+        self.nonce_count.store(0, Ordering::Relaxed);
+        true
+    }
+}
+
+/// Sends a request with credentials attached when `url` is on one of the crawl's
+/// hosts. A digest challenge is answered by retrying once.
+pub async fn send_with_credentials(
+    client: &Client,
+    method: Method,
+    url: &str,
+    credentials: Option<&CrawlCredentials>,
+) -> Result<Response, reqwest::Error> {
+    let parsed = Url::parse(url).ok();
+    let (Some(credentials), Some(parsed)) = (credentials, parsed) else {
+        return client.request(method, url).send().await;
+    };
+    if !credentials.covers(&parsed) {
+        return client.request(method, url).send().await;
+    }
+
+    let request = credentials.apply(client.request(method.clone(), url), &method, &parsed);
+    let response = request.send().await?;
+    if credentials.accept_challenge(&response) {
+        let retry = credentials.apply(client.request(method.clone(), url), &method, &parsed);
+        return retry.send().await;
+    }
+    Ok(response)
+}
+
+fn request_uri(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// A `WWW-Authenticate: Digest` challenge (RFC 7616, MD5 and MD5-sess only).
+#[derive(Debug, Clone, Default)]
+struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: String,
+    /// `true` when the server offers `qop=auth`
+    qop_auth: bool,
+}
+
+impl DigestChallenge {
+    fn parse(header: &str) -> Option<Self> {
+        let (scheme, params) = header.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+        let params = parse_auth_params(params);
+
+        Some(DigestChallenge {
+            realm: params.get("realm").cloned().unwrap_or_default(),
+            nonce: params.get("nonce").cloned()?,
+            opaque: params.get("opaque").cloned(),
+            algorithm: params
+                .get("algorithm")
+                .cloned()
+                .unwrap_or_else(|| "MD5".to_string()),
+            qop_auth: params
+                .get("qop")
+                .is_some_and(|qop| qop.split(',').any(|q| q.trim().eq_ignore_ascii_case("auth"))),
+        })
+    }
+
+    fn is_supported(&self) -> bool {
+        self.algorithm.eq_ignore_ascii_case("MD5") || self.algorithm.eq_ignore_ascii_case("MD5-sess")
+    }
+
+    fn authorization(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        nc: u32,
+    ) -> Option<String> {
+        self.authorization_with_cnonce(
+            username,
+            password,
+            method,
+            uri,
+            nc,
+            &format!("{:016x}", rand::random::<u64>()),
+        )
+    }
+
+    fn authorization_with_cnonce(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        nc: u32,
+        cnonce: &str,
+    ) -> Option<String> {
+        if !self.is_supported() {
+            return None;
+        }
+        let nc = format!("{:08x}", nc);
+
+        let mut ha1 = md5_hex(format!("{}:{}:{}", username, self.realm, password).as_bytes());
+        if self.algorithm.eq_ignore_ascii_case("MD5-sess") {
+            ha1 = md5_hex(format!("{}:{}:{}", ha1, self.nonce, cnonce).as_bytes());
+        }
+        let ha2 = md5_hex(format!("{}:{}", method, uri).as_bytes());
+
+        let response = if self.qop_auth {
+            md5_hex(format!("{}:{}:{}:{}:auth:{}", ha1, self.nonce, nc, cnonce, ha2).as_bytes())
+        } else {
+            md5_hex(format!("{}:{}:{}", ha1, self.nonce, ha2).as_bytes())
+        };
+
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            username, self.realm, self.nonce, uri, self.algorithm, response
+        );
+        if self.qop_auth {
+            header.push_str(&format!(", qop=auth, nc={}, cnonce=\"{}\"", nc, cnonce));
+        }
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(", opaque=\"{}\"", opaque));
+        }
+        Some(header)
+    }
+}
+
+/// Parses `key=value, key="quoted, value"` auth parameters (keys lowercased).
+fn parse_auth_params(input: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        let Some((key, after)) = rest.split_once('=') else {
+            break;
+        };
+        let key = key.trim().trim_start_matches(',').trim().to_ascii_lowercase();
+        let after = after.trim_start();
+
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (quoted[..end].to_string(), &quoted[end + 1..]),
+                None => (quoted.to_string(), ""),
+            }
+        } else {
+            match after.find(',') {
+                Some(end) => (after[..end].trim().to_string(), &after[end..]),
+                None => (after.trim().to_string(), ""),
+            }
+        };
+
+        params.insert(key, value);
+        rest = remaining.trim_start().trim_start_matches(',').trim_start();
+    }
+
+    params
+}
+
+/// MD5 (RFC 1321), needed for digest auth only.
+fn md5_hex(input: &[u8]) -> String {
+    format!("{:x}", Md5::digest(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn md5_matches_rfc_1321_vectors() {
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            md5_hex(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn digest_response_matches_rfc_2617_example() {
+        let challenge = DigestChallenge::parse(
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        )
+        .unwrap();
+        let header = challenge
+            .authorization_with_cnonce("Mufasa", "Circle Of Life", "GET", "/dir/index.html", 1, "0a4f113b")
+            .unwrap();
+        assert!(header.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+        assert!(header.contains("nc=00000001"));
+        assert!(header.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+    }
+
+    #[test]
+    fn credentials_only_cover_crawl_hosts() {
+        let auth = CrawlAuth {
+            scheme: AuthScheme::Basic,
+            cookies: vec!["session=abc; theme=dark".to_string()],
+            ..Default::default()
+        };
+        let site = Url::parse("https://staging.example.com/").unwrap();
+        let credentials =
+            CrawlCredentials::new(auth, crawl_hosts(&site), Arc::new(Jar::default())).unwrap();
+
+        assert!(credentials.covers(&Url::parse("https://staging.example.com/a").unwrap()));
+        assert!(credentials.covers(&Url::parse("http://www.staging.example.com/").unwrap()));
+        assert!(!credentials.covers(&Url::parse("https://cdn.example.com/app.js").unwrap()));
+        assert_eq!(credentials.cookies_for(&site).len(), 2);
+        assert!(credentials
+            .cookies_for(&Url::parse("https://cdn.example.com/").unwrap())
+            .is_empty());
+    }
+}
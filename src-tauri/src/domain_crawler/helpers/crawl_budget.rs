@@ -0,0 +1,240 @@
+//! Crawl budgets (`Settings::crawl_budgets`): caps on how many URLs are queued
+//! per path prefix or URL pattern, so a large site can be sampled template by
+//! template instead of crawling every near-identical URL. A discovered URL is
+//! queued only while every budget it falls under has room; the others are
+//! counted per budget as left unvisited.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CrawlBudget {
+    /// URLs whose path starts with `prefix`, e.g. "/products/"
+    PathPrefix { prefix: String, max_urls: usize },
+    /// URLs matching the regex (on the full URL). With capture groups, each
+    /// distinct capture gets its own budget, e.g. `/category/([^/]+)/` allows
+    /// `max_urls` per category
+    Pattern { pattern: String, max_urls: usize },
+}
+
+impl CrawlBudget {
+    fn describe(&self) -> String {
+        match self {
+            CrawlBudget::PathPrefix { prefix, .. } => format!("prefix: {}", prefix),
+            CrawlBudget::Pattern { pattern, .. } => format!("pattern: {}", pattern),
+        }
+    }
+}
+
+enum Matcher {
+    Prefix(String),
+    Pattern(Regex),
+}
+
+struct CompiledBudget {
+    rule: String,
+    matcher: Matcher,
+    max_urls: usize,
+}
+
+impl CompiledBudget {
+    /// The bucket `url` falls into: the captured values for a pattern with
+    /// capture groups, "" otherwise. `None` when the budget doesn't apply.
+    fn bucket(&self, url: &Url) -> Option<String> {
+        match &self.matcher {
+            Matcher::Prefix(prefix) => url.path().starts_with(prefix.as_str()).then(String::new),
+            Matcher::Pattern(re) => re.captures(url.as_str()).map(|caps| {
+                caps.iter()
+                    .skip(1)
+                    .map(|m| m.map_or("", |m| m.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            }),
+        }
+    }
+}
+
+/// A budget bucket: the budget's index and the bucket within it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BucketKey(usize, String);
+
+/// How one bucket was used, for the crawl summary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    pub rule: String,
+    /// Captured values for a pattern with capture groups; empty otherwise
+    pub bucket: String,
+    pub max_urls: usize,
+    /// URLs queued against this bucket
+    pub queued: usize,
+    /// Unique URLs left unvisited because the bucket was used up
+    pub skipped: usize,
+    pub exhausted: bool,
+}
+
+pub struct CrawlBudgets {
+    budgets: Vec<CompiledBudget>,
+    usage: HashMap<BucketKey, (usize, usize)>,
+}
+
+impl CrawlBudgets {
+    /// Compiles the budgets. An invalid regex is reported with the offending
+    /// pattern, like the include/exclude rules.
+    pub fn from_rules(rules: &[CrawlBudget]) -> Result<Self, String> {
+        let budgets = rules
+            .iter()
+            .filter_map(|rule| {
+                let (matcher, max_urls) = match rule {
+                    CrawlBudget::PathPrefix { prefix, max_urls } => {
+                        let prefix = prefix.trim();
+                        if prefix.is_empty() {
+                            return None;
+                        }
+                        let prefix = if prefix.starts_with('/') {
+                            prefix.to_string()
+                        } else {
+                            format!("/{}", prefix)
+                        };
+                        (Ok(Matcher::Prefix(prefix)), *max_urls)
+                    }
+                    CrawlBudget::Pattern { pattern, max_urls } => {
+                        let pattern = pattern.trim();
+                        if pattern.is_empty() {
+                            return None;
+                        }
+                        let matcher = Regex::new(pattern)
+                            .map(Matcher::Pattern)
+                            .map_err(|e| format!("Invalid budget pattern '{}': {}", pattern, e));
+                        (matcher, *max_urls)
+                    }
+                };
+                Some(matcher.map(|matcher| CompiledBudget {
+                    rule: rule.describe(),
+                    matcher,
+                    max_urls,
+                }))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(CrawlBudgets {
+            budgets,
+            usage: HashMap::new(),
+        })
+    }
+
+    /// `true` when no budgets are configured, so the crawl can skip them entirely.
+    pub fn is_empty(&self) -> bool {
+        self.budgets.is_empty()
+    }
+
+    fn buckets(&self, url: &Url) -> Vec<BucketKey> {
+        self.budgets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, budget)| budget.bucket(url).map(|bucket| BucketKey(i, bucket)))
+            .collect()
+    }
+
+    /// Charges `url` to every bucket it falls under, when all of them have
+    /// room. Otherwise nothing is charged and the first full bucket is returned.
+    pub fn take(&mut self, url: &Url) -> Option<BucketKey> {
+        let buckets = self.buckets(url);
+        let full = buckets.iter().find(|key| {
+            let queued = self.usage.get(key).map_or(0, |(queued, _)| *queued);
+            queued >= self.budgets[key.0].max_urls
+        });
+        if let Some(full) = full {
+            return Some(full.clone());
+        }
+        for key in buckets {
+            self.usage.entry(key).or_default().0 += 1;
+        }
+        None
+    }
+
+    /// Charges `url` whether or not its buckets have room (the start URL, and
+    /// URLs restored from a checkpoint).
+    pub fn charge(&mut self, url: &Url) {
+        for key in self.buckets(url) {
+            self.usage.entry(key).or_default().0 += 1;
+        }
+    }
+
+    /// Counts a URL left unvisited because `bucket` was full.
+    pub fn skip(&mut self, bucket: &BucketKey) {
+        self.usage.entry(bucket.clone()).or_default().1 += 1;
+    }
+
+    /// Usage of every bucket that saw a URL, in budget order.
+    pub fn report(&self) -> Vec<BudgetUsage> {
+        let mut keys: Vec<&BucketKey> = self.usage.keys().collect();
+        keys.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        keys.into_iter()
+            .map(|key| {
+                let budget = &self.budgets[key.0];
+                let (queued, skipped) = self.usage[key];
+                BudgetUsage {
+                    rule: budget.rule.clone(),
+                    bucket: key.1.clone(),
+                    max_urls: budget.max_urls,
+                    queued,
+                    skipped,
+                    exhausted: queued >= budget.max_urls,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_each_bucket() {
+        let mut budgets = CrawlBudgets::from_rules(&[
+            CrawlBudget::PathPrefix {
+                prefix: "/products/".to_string(),
+                max_urls: 3,
+            },
+            CrawlBudget::Pattern {
+                pattern: r"/category/([^/]+)/".to_string(),
+                max_urls: 1,
+            },
+        ])
+        .unwrap();
+        let url = |path: &str| Url::parse(&format!("https://shop.example{}", path)).unwrap();
+
+        assert_eq!(budgets.take(&url("/products/category/shoes/1")), None);
+        let full = budgets.take(&url("/products/category/shoes/2")).unwrap();
+        budgets.skip(&full);
+        assert_eq!(budgets.take(&url("/products/category/hats/1")), None);
+        assert_eq!(budgets.take(&url("/products/sale")), None);
+        let full = budgets.take(&url("/products/other")).unwrap();
+        budgets.skip(&full);
+        assert_eq!(budgets.take(&url("/about")), None);
+
+        let report = budgets.report();
+        let row = |bucket: &str| report.iter().find(|r| r.bucket == bucket).unwrap();
+        assert_eq!(report.len(), 3);
+        assert_eq!(row("").queued, 3);
+        assert_eq!(row("").skipped, 1);
+        assert!(row("").exhausted);
+        assert_eq!((row("shoes").queued, row("shoes").skipped), (1, 1));
+        assert_eq!((row("hats").queued, row("hats").skipped), (1, 0));
+    }
+
+    #[test]
+    fn invalid_pattern_is_reported() {
+        let err = CrawlBudgets::from_rules(&[CrawlBudget::Pattern {
+            pattern: "(unclosed".to_string(),
+            max_urls: 10,
+        }])
+        .err()
+        .unwrap();
+        assert!(err.contains("(unclosed"));
+    }
+}
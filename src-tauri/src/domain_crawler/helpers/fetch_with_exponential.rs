@@ -1,7 +1,9 @@
 use std::time::Duration;
-use reqwest::Client;
+use reqwest::{Client, Method};
 use tokio::time::{sleep, Instant};
 
+use crate::domain_crawler::helpers::crawl_auth::{send_with_credentials, CrawlCredentials};
+use crate::domain_crawler::helpers::rate_limit;
 use crate::settings::settings::Settings;
 
 // Fetch URL with exponential backoff
@@ -9,14 +11,15 @@ pub async fn fetch_with_exponential_backoff(
     client: &Client,
     url: &str,
     settings: &Settings,
+    credentials: Option<&CrawlCredentials>,
 ) -> Result<(reqwest::Response, f64), reqwest::Error> {
     let mut attempt = 0;
     loop {
+        // Waiting for the per-host rate limit isn't part of the response time
+        rate_limit::until_ready(url).await;
         let start = Instant::now();
         
-        let request_builder = client.get(url);
-
-        match request_builder.send().await {
+        match send_with_credentials(client, Method::GET, url, credentials).await {
             Ok(response) => {
                 let duration = start.elapsed().as_secs_f64();
                 let status = response.status();
@@ -0,0 +1,426 @@
+//! Form-based login before a crawl starts, for staging sites that use a login
+//! page rather than HTTP auth. The form is either posted directly (hidden inputs
+//! such as CSRF tokens are picked up from the login page) or filled in and
+//! submitted in headless Chrome for JS-driven forms.
+//!
+//! The resulting session cookies land in the crawl's cookie jar (see
+//! `CrawlCredentials::cookie_jar`), so the page client, the link checker and
+//! headless rendering all crawl as the logged-in user. When a page redirects to
+//! the login URL mid-crawl the session has expired: the crawler logs in again
+//! (up to `MAX_RELOGINS` times) and refetches the page.
+
+use reqwest::{Client, Method, StatusCode};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+use super::crawl_auth::{send_with_credentials, CrawlCredentials};
+use super::headless_fetch;
+use super::proxy::ProxyConfig;
+use crate::domain_crawler::user_agents::CrawlIdentity;
+
+/// How many times a crawl logs in again after its session expired.
+const MAX_RELOGINS: usize = 5;
+
+/// Excluded from crawls that log in through a form, so the crawler never
+/// follows a logout link.
+pub const LOGOUT_PATTERN: &str = r"(?i)/(log-?out|sign-?out|log-?off)\b";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormField {
+    pub name: String,
+    pub value: String,
+}
+
+/// Login form settings, part of `CrawlAuth`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FormLogin {
+    /// Page with the login form
+    pub login_url: String,
+    /// Values for the form's fields, e.g. `username` and `password`
+    #[serde(default)]
+    pub fields: Vec<FormField>,
+    /// Where the form is posted. Defaults to the login form's `action`
+    #[serde(default)]
+    pub action_url: Option<String>,
+    /// Fill in and submit the form in headless Chrome (for JS-driven forms)
+    #[serde(default)]
+    pub use_browser: bool,
+    /// Browser mode: the button to click. Defaults to submitting the form that
+    /// contains the first field
+    #[serde(default)]
+    pub submit_selector: Option<String>,
+}
+
+/// A crawl's login state, shared by every fetch task.
+pub struct LoginSession {
+    config: FormLogin,
+    login_url: Url,
+    identity: CrawlIdentity,
+    proxy: Option<ProxyConfig>,
+    /// Bumped on every successful login, so tasks that saw the same expired
+    /// session don't all log in again
+    generation: AtomicU64,
+    relogins: AtomicUsize,
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl LoginSession {
+    pub fn new(
+        config: FormLogin,
+        identity: CrawlIdentity,
+        proxy: Option<ProxyConfig>,
+    ) -> Result<Self, String> {
+        let login_url = Url::parse(config.login_url.trim())
+            .map_err(|_| format!("Invalid login URL: {}", config.login_url))?;
+        if config.fields.is_empty() {
+            return Err("The login form needs at least one field".to_string());
+        }
+
+        Ok(LoginSession {
+            config,
+            login_url,
+            identity,
+            proxy,
+            generation: AtomicU64::new(0),
+            relogins: AtomicUsize::new(0),
+            lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn login_url(&self) -> &Url {
+        &self.login_url
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Logs in. `client` must use the credentials' cookie jar (the crawl's page
+    /// client does), since that's where the session cookies are captured.
+    pub async fn login(
+        &self,
+        client: &Client,
+        credentials: &Arc<CrawlCredentials>,
+    ) -> Result<(), String> {
+        if self.config.use_browser {
+            self.browser_login(credentials).await?;
+        } else {
+            self.post_login(client, credentials).await?;
+        }
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        tracing::info!("Logged in via {}", self.login_url);
+        Ok(())
+    }
+
+    /// Whether a redirect from `from` to `to` means the session has expired.
+    pub fn is_login_redirect(&self, from: &Url, to: &Url) -> bool {
+        same_page(to, &self.login_url) && !same_page(from, &self.login_url)
+    }
+
+    /// Logs in again after a task saw the session expire at `seen_generation`.
+    /// Returns `true` when the page should be refetched.
+    pub async fn relogin(
+        &self,
+        seen_generation: u64,
+        client: &Client,
+        credentials: &Arc<CrawlCredentials>,
+    ) -> bool {
+        let _guard = self.lock.lock().await;
+        if self.generation() != seen_generation {
+            // Another task already logged in again while we waited
+            return true;
+        }
+        if self.relogins.fetch_add(1, Ordering::AcqRel) >= MAX_RELOGINS {
+            return false;
+        }
+
+        tracing::warn!("Session expired, logging in again");
+        match self.login(client, credentials).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Re-login failed: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn post_login(
+        &self,
+        client: &Client,
+        credentials: &Arc<CrawlCredentials>,
+    ) -> Result<(), String> {
+        // The page client doesn't follow redirects, so walk them here (e.g. http -> https).
+        let mut page_url = self.login_url.clone();
+        let mut response = None;
+        for _ in 0..5 {
+            let res = send_with_credentials(client, Method::GET, page_url.as_str(), Some(credentials))
+                .await
+                .map_err(|e| format!("Failed to load login page: {}", e))?;
+            let next = res
+                .status()
+                .is_redirection()
+                .then(|| res.headers().get(reqwest::header::LOCATION))
+                .flatten()
+                .and_then(|l| l.to_str().ok())
+                .and_then(|l| page_url.join(l).ok());
+            match next {
+                Some(next) => page_url = next,
+                None => {
+                    response = Some(res);
+                    break;
+                }
+            }
+        }
+        let response = response.ok_or("Too many redirects loading the login page")?;
+        if !response.status().is_success() {
+            return Err(format!("Login page returned {}", response.status()));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read login page: {}", e))?;
+
+        let first_field = self.config.fields[0].name.as_str();
+        let (form_action, mut form) = parse_login_form(&body, &page_url, first_field);
+        for field in &self.config.fields {
+            form.retain(|(name, _)| name != &field.name);
+            form.push((field.name.clone(), field.value.clone()));
+        }
+
+        let action = match &self.config.action_url {
+            Some(action) if !action.trim().is_empty() => page_url
+                .join(action.trim())
+                .map_err(|_| format!("Invalid login form action: {}", action))?,
+            _ => form_action,
+        };
+
+        let request = credentials
+            .apply(client.post(action.clone()), &Method::POST, &action)
+            .header(reqwest::header::REFERER, page_url.as_str())
+            .form(&form);
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Login request failed: {}", e))?;
+
+        let status = response.status();
+        if status.is_client_error() || status.is_server_error() {
+            return Err(format!("Login returned {}", status));
+        }
+        if status.is_redirection() {
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|l| l.to_str().ok())
+                .and_then(|l| action.join(l).ok());
+            if location.is_some_and(|l| same_page(&l, &self.login_url)) {
+                return Err("Login failed: redirected back to the login page".to_string());
+            }
+            return Ok(());
+        }
+        if status == StatusCode::OK {
+            let body = response.text().await.unwrap_or_default();
+            if has_password_field(&body) {
+                return Err("Login failed: the login form was shown again".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    async fn browser_login(&self, credentials: &Arc<CrawlCredentials>) -> Result<(), String> {
+        let config = self.config.clone();
+        let login_url = self.login_url.clone();
+        let identity = self.identity.clone();
+        let proxy = self.proxy.clone();
+        let tab_credentials = credentials.clone();
+
+        let cookies = tokio::task::spawn_blocking(move || {
+            browser_submit(&config, &login_url, &identity, &tab_credentials, proxy.as_ref())
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let jar = credentials.cookie_jar();
+        for cookie in cookies {
+            jar.add_cookie_str(&cookie, &self.login_url);
+        }
+        Ok(())
+    }
+}
+
+/// Fills in and submits the login form in headless Chrome. Returns the session
+/// cookies as `Set-Cookie` strings.
+fn browser_submit(
+    config: &FormLogin,
+    login_url: &Url,
+    identity: &CrawlIdentity,
+    credentials: &CrawlCredentials,
+    proxy: Option<&ProxyConfig>,
+) -> Result<Vec<String>, String> {
+    let browser = headless_fetch::launch_browser(proxy)?;
+    let tab = browser
+        .new_tab()
+        .map_err(|e| format!("Failed to create tab: {}", e))?;
+    tab.set_user_agent(
+        &identity.user_agent,
+        Some("en-US,en;q=0.9"),
+        Some(identity.platform()),
+    )
+    .map_err(|e| format!("Failed to set user agent: {}", e))?;
+    headless_fetch::prepare_tab(&tab, Some(credentials), proxy, login_url.as_str())?;
+
+    tab.navigate_to(login_url.as_str())
+        .map_err(|e| format!("Failed to open login page: {}", e))?;
+    let _ = tab.wait_until_navigated();
+
+    for field in &config.fields {
+        let selector = format!("[name=\"{}\"]", field.name.replace('"', "\\\""));
+        let element = tab
+            .wait_for_element(&selector)
+            .map_err(|_| format!("Login field not found: {}", field.name))?;
+        element
+            .call_js_fn(
+                "function(v) { this.focus(); this.value = v; \
+                 this.dispatchEvent(new Event('input', { bubbles: true })); \
+                 this.dispatchEvent(new Event('change', { bubbles: true })); }",
+                vec![serde_json::Value::String(field.value.clone())],
+                false,
+            )
+            .map_err(|e| format!("Failed to fill in {}: {}", field.name, e))?;
+    }
+
+    match config.submit_selector.as_deref().map(str::trim) {
+        Some(selector) if !selector.is_empty() => {
+            tab.wait_for_element(selector)
+                .and_then(|button| button.click().map(|_| ()))
+                .map_err(|e| format!("Failed to click {}: {}", selector, e))?;
+        }
+        _ => {
+            let selector = format!("[name=\"{}\"]", config.fields[0].name.replace('"', "\\\""));
+            tab.find_element(&selector)
+                .and_then(|field| {
+                    field.call_js_fn(
+                        "function() { const f = this.form; \
+                         if (f) { f.requestSubmit ? f.requestSubmit() : f.submit(); } }",
+                        Vec::new(),
+                        false,
+                    )
+                })
+                .map_err(|e| format!("Failed to submit the login form: {}", e))?;
+        }
+    }
+
+    let _ = tab.wait_until_navigated();
+    // Give client-side redirects after login a moment to settle
+    thread::sleep(Duration::from_secs(2));
+
+    let still_on_login = Url::parse(&tab.get_url()).is_ok_and(|u| same_page(&u, login_url));
+    let content = tab.get_content().unwrap_or_default();
+    if still_on_login && has_password_field(&content) {
+        return Err("Login failed: the login form is still shown".to_string());
+    }
+
+    let cookies = tab
+        .get_cookies()
+        .map_err(|e| format!("Failed to read cookies: {}", e))?;
+    Ok(cookies
+        .into_iter()
+        .map(|c| format!("{}={}; Domain={}; Path={}", c.name, c.value, c.domain, c.path))
+        .collect())
+}
+
+/// Finds the login form (the one containing `field_name`, else the one with a
+/// password input, else the first form) and returns its resolved action and
+/// hidden inputs, e.g. CSRF tokens.
+fn parse_login_form(html: &str, page_url: &Url, field_name: &str) -> (Url, Vec<(String, String)>) {
+    let document = Html::parse_document(html);
+    let form_selector = Selector::parse("form").unwrap();
+    let input_selector = Selector::parse("input").unwrap();
+
+    let forms: Vec<_> = document.select(&form_selector).collect();
+    let has_input = |form: &scraper::ElementRef, pred: &dyn Fn(&scraper::node::Element) -> bool| {
+        form.select(&input_selector).any(|i| pred(i.value()))
+    };
+    let form = forms
+        .iter()
+        .find(|f| has_input(f, &|i| i.attr("name") == Some(field_name)))
+        .or_else(|| {
+            forms
+                .iter()
+                .find(|f| has_input(f, &|i| i.attr("type") == Some("password")))
+        })
+        .or_else(|| forms.first());
+
+    let Some(form) = form else {
+        return (page_url.clone(), Vec::new());
+    };
+
+    let action = form
+        .value()
+        .attr("action")
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .and_then(|a| page_url.join(a).ok())
+        .unwrap_or_else(|| page_url.clone());
+
+    let hidden = form
+        .select(&input_selector)
+        .filter(|i| i.value().attr("type").is_some_and(|t| t.eq_ignore_ascii_case("hidden")))
+        .filter_map(|i| {
+            let name = i.value().attr("name")?;
+            Some((name.to_string(), i.value().attr("value").unwrap_or("").to_string()))
+        })
+        .collect();
+
+    (action, hidden)
+}
+
+fn has_password_field(html: &str) -> bool {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("input[type=password]").unwrap();
+    document.select(&selector).next().is_some()
+}
+
+/// Same host and path, ignoring scheme, query and a trailing slash.
+fn same_page(a: &Url, b: &Url) -> bool {
+    a.host_str() == b.host_str()
+        && a.path().trim_end_matches('/') == b.path().trim_end_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_up_login_form_action_and_hidden_inputs() {
+        let html = r#"
+            <form action="/search"><input name="q"></form>
+            <form action="/account/login" method="post">
+                <input type="hidden" name="csrf_token" value="abc123">
+                <input name="email">
+                <input type="password" name="password">
+            </form>
+        "#;
+        let page = Url::parse("https://staging.example.com/login").unwrap();
+        let (action, hidden) = parse_login_form(html, &page, "email");
+
+        assert_eq!(action.as_str(), "https://staging.example.com/account/login");
+        assert_eq!(hidden, vec![("csrf_token".to_string(), "abc123".to_string())]);
+        assert!(has_password_field(html));
+    }
+
+    #[test]
+    fn login_page_matches_ignoring_query_and_trailing_slash() {
+        let login = Url::parse("https://staging.example.com/login/").unwrap();
+        let to_login = Url::parse("http://staging.example.com/login?next=/products").unwrap();
+        let page = Url::parse("https://staging.example.com/products").unwrap();
+
+        assert!(same_page(&to_login, &login));
+        assert!(!same_page(&page, &login));
+    }
+}
@@ -1,30 +1,191 @@
-use headless_chrome::{Browser, LaunchOptions};
-use std::time::Duration;
+use headless_chrome::browser::tab::RequestPausedDecision;
+use headless_chrome::browser::transport::{SessionId, Transport};
+use headless_chrome::protocol::cdp::Fetch::{
+    events::RequestPausedEvent, ContinueRequest, HeaderEntry, RequestPattern,
+};
+use headless_chrome::browser::tab::EventListener;
+use headless_chrome::protocol::cdp::types::Event;
+use headless_chrome::protocol::cdp::Emulation;
+use headless_chrome::protocol::cdp::Network::{self, CookieParam};
+use headless_chrome::{Browser, LaunchOptions, Tab};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex as StdMutex, Weak};
+use std::time::{Duration, Instant};
 use std::thread;
+use url::Url;
 
-pub fn fetch_js_body(url: &str) -> Result<(String, Vec<String>), String> {
-    // Create a new browser instance
-    // We enable headless mode (default is true, but being explicit)
-    let browser = Browser::new(LaunchOptions {
-        headless: true,
-        ..Default::default()
-    }).map_err(|e| format!("Failed to create browser: {}", e))?;
+use crate::domain_crawler::helpers::browser_pool::BrowserPool;
+use crate::domain_crawler::helpers::crawl_auth::{AuthScheme, CrawlCredentials};
+use crate::domain_crawler::helpers::proxy::ProxyConfig;
+use crate::domain_crawler::user_agents::CrawlIdentity;
+use crate::settings::settings::Settings;
+
+/// A page is network-idle once at most this many requests have been in flight
+/// for `NETWORK_IDLE_QUIET`. Allowing a couple keeps long-polling and analytics
+/// beacons from holding every render to the full timeout.
+const NETWORK_IDLE_MAX_INFLIGHT: usize = 2;
+const NETWORK_IDLE_QUIET: Duration = Duration::from_millis(500);
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Hard limit on a whole render, tab setup to reading the DOM. Checked between
+/// steps, and navigation and the wait strategy are cut short to fit in it.
+pub const RENDER_TIMEOUT: Duration = Duration::from_secs(45);
+/// Longest allowed `javascript_wait_timeout_ms`; leaves 15s of `RENDER_TIMEOUT`
+/// for navigation and reading the DOM.
+pub const MAX_WAIT_TIMEOUT_MS: u64 = 30_000;
+
+/// What a JS render waits for after the page has loaded, before reading the DOM.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsWaitStrategy {
+    /// Until the network goes quiet, up to the wait timeout
+    NetworkIdle,
+    /// Until `javascript_wait_selector` matches an element, up to the wait timeout
+    Selector,
+    /// For the whole wait timeout
+    #[default]
+    Timeout,
+}
+
+/// The wait settings for a crawl's JS renders.
+#[derive(Debug, Clone)]
+pub struct JsWait {
+    pub strategy: JsWaitStrategy,
+    pub selector: String,
+    pub timeout: Duration,
+}
+
+impl JsWait {
+    /// Reads the `javascript_wait_*` settings. A selector wait without a selector
+    /// falls back to a fixed timeout, and the timeout is capped at
+    /// `MAX_WAIT_TIMEOUT_MS` so the wait can't outlast the render.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let selector = settings.javascript_wait_selector.trim().to_string();
+        let strategy = match settings.javascript_wait_strategy {
+            JsWaitStrategy::Selector if selector.is_empty() => JsWaitStrategy::Timeout,
+            strategy => strategy,
+        };
+        JsWait {
+            strategy,
+            selector,
+            timeout: Duration::from_millis(
+                settings.javascript_wait_timeout_ms.min(MAX_WAIT_TIMEOUT_MS),
+            ),
+        }
+    }
+}
+
+/// How long a page took to render in headless Chrome.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenderTiming {
+    pub strategy: JsWaitStrategy,
+    /// From navigation start until the page finished loading
+    pub navigation_ms: u64,
+    /// Time spent on the wait strategy after loading
+    pub wait_ms: u64,
+    /// Whole render, including tab setup and reading the DOM
+    pub total_ms: u64,
+    /// Whether the network went idle / the selector matched before the timeout.
+    /// Always true for `Timeout`.
+    pub condition_met: bool,
+}
+
+/// Renders `url` in a tab of one of the pool's browsers and returns the rendered
+/// HTML, the cookies set by the page, and how long the render took.
+pub fn fetch_js_body(
+    pool: &BrowserPool,
+    url: &str,
+    identity: &CrawlIdentity,
+    credentials: Option<&CrawlCredentials>,
+    wait: &JsWait,
+) -> Result<(String, Vec<String>, RenderTiming), String> {
+    let started = Instant::now();
+    let deadline = started + RENDER_TIMEOUT;
+    let browser = pool.checkout()?;
+    // A browser that can't open tabs is dropped rather than returned to the pool
+    let tab = browser
+        .new_tab()
+        .map_err(|e| format!("Failed to create tab: {}", e))?;
+
+    let result = render_in_tab(&tab, url, identity, credentials, pool.proxy(), wait, deadline);
+    let _ = tab.close(false);
+    pool.checkin(browser);
+
+    let (content, cookies, mut timing) = result?;
+    timing.total_ms = started.elapsed().as_millis() as u64;
+    Ok((content, cookies, timing))
+}
 
-    // Create a new tab
-    let tab = browser.new_tab().map_err(|e| format!("Failed to create tab: {}", e))?;
-    
-    // Navigate to the URL
+fn render_in_tab(
+    tab: &Tab,
+    url: &str,
+    identity: &CrawlIdentity,
+    credentials: Option<&CrawlCredentials>,
+    proxy: Option<&ProxyConfig>,
+    wait: &JsWait,
+    deadline: Instant,
+) -> Result<(String, Vec<String>, RenderTiming), String> {
+    // Render as the crawl identity, not as HeadlessChrome
+    tab.set_user_agent(
+        &identity.user_agent,
+        Some("en-US,en;q=0.9"),
+        Some(identity.platform()),
+    )
+    .map_err(|e| format!("Failed to set user agent: {}", e))?;
+
+    if identity.is_mobile() {
+        emulate_smartphone(tab)?;
+    }
+
+    prepare_tab(tab, credentials, proxy, url)?;
+
+    // Requests have to be tracked from before navigation starts
+    let network = match wait.strategy {
+        JsWaitStrategy::NetworkIdle => Some(NetworkTracker::attach(tab)?),
+        _ => None,
+    };
+
+    // Waiting for navigation uses the tab's default timeout
+    tab.set_default_timeout(time_left(deadline, url)?);
+    let navigation_start = Instant::now();
     tab.navigate_to(url).map_err(|e| format!("Failed to navigate to {}: {}", url, e))?;
-    
-    // Wait for the page to navigate
-    // We attempt to wait, but don't hard fail if it times out as we have a manual sleep
+
+    // We attempt to wait, but don't hard fail if it times out; the wait strategy
+    // below still gives the page time to render
     let _ = tab.wait_until_navigated();
+    let navigation_ms = navigation_start.elapsed().as_millis() as u64;
 
-    // Sleep to allow JavaScript execution (React/Vue/Angular hydration, API calls)
-    // 3 seconds is a reasonable compromise between speed and correctness for most SPAs
-    thread::sleep(Duration::from_secs(3));
+    // Let JavaScript run (React/Vue/Angular hydration, API calls)
+    let wait_timeout = wait.timeout.min(time_left(deadline, url)?);
+    let wait_start = Instant::now();
+    let condition_met = match wait.strategy {
+        JsWaitStrategy::NetworkIdle => network
+            .as_ref()
+            .map(|tracker| tracker.wait_for_idle(wait_timeout))
+            .unwrap_or(false),
+        JsWaitStrategy::Selector => tab
+            .wait_for_element_with_custom_timeout(&wait.selector, wait_timeout)
+            .is_ok(),
+        JsWaitStrategy::Timeout => {
+            thread::sleep(wait_timeout);
+            true
+        }
+    };
+    let wait_ms = wait_start.elapsed().as_millis() as u64;
+    if !condition_met {
+        tracing::debug!(
+            "JS wait ({:?}) timed out after {}ms for {}",
+            wait.strategy,
+            wait_ms,
+            url
+        );
+    }
+    if let Some(tracker) = network {
+        tracker.detach(tab);
+    }
 
     // Get the rendered HTML content
+    time_left(deadline, url)?;
     let content = tab.get_content().map_err(|e| format!("Failed to get content from {}: {}", url, e))?;
 
     // Enable extracting cookies from the javascript execution.
@@ -41,6 +202,353 @@ pub fn fetch_js_body(url: &str) -> Result<(String, Vec<String>), String> {
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .collect();
-    
-    Ok((content, cookies))
+
+    let timing = RenderTiming {
+        strategy: wait.strategy,
+        navigation_ms,
+        wait_ms,
+        total_ms: 0,
+        condition_met,
+    };
+    Ok((content, cookies, timing))
+}
+
+/// Time left before `deadline`, or an error once the render has used it all.
+fn time_left(deadline: Instant, url: &str) -> Result<Duration, String> {
+    deadline
+        .checked_duration_since(Instant::now())
+        .filter(|left| !left.is_zero())
+        .ok_or_else(|| {
+            format!(
+                "Rendering {} took longer than {}s",
+                url,
+                RENDER_TIMEOUT.as_secs()
+            )
+        })
+}
+
+/// Gives the tab a smartphone viewport (a 412x915 Android screen, the size
+/// Google's mobile tools render at) with touch input, so responsive layouts
+/// and mobile-only markup render as they would for a phone.
+fn emulate_smartphone(tab: &Tab) -> Result<(), String> {
+    tab.call_method(Emulation::SetDeviceMetricsOverride {
+        width: 412,
+        height: 915,
+        device_scale_factor: 2.625,
+        mobile: true,
+        scale: None,
+        screen_width: None,
+        screen_height: None,
+        position_x: None,
+        position_y: None,
+        dont_set_visible_size: None,
+        screen_orientation: None,
+        viewport: None,
+        display_feature: None,
+        device_posture: None,
+    })
+    .map_err(|e| format!("Failed to set mobile viewport: {}", e))?;
+    tab.call_method(Emulation::SetTouchEmulationEnabled {
+        enabled: true,
+        max_touch_points: Some(5),
+    })
+    .map_err(|e| format!("Failed to enable touch emulation: {}", e))?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct InFlight {
+    requests: HashSet<String>,
+    /// When the in-flight count last dropped to the idle threshold; `None` while
+    /// it's above it.
+    idle_since: Option<Instant>,
+}
+
+impl InFlight {
+    fn update(&mut self, now: Instant) {
+        if self.requests.len() > NETWORK_IDLE_MAX_INFLIGHT {
+            self.idle_since = None;
+        } else if self.idle_since.is_none() {
+            self.idle_since = Some(now);
+        }
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        self.idle_since
+            .is_some_and(|since| now.duration_since(since) >= NETWORK_IDLE_QUIET)
+    }
+}
+
+/// Counts a tab's in-flight requests from CDP network events.
+struct NetworkTracker {
+    state: Arc<StdMutex<InFlight>>,
+    listener: Weak<dyn EventListener<Event> + Send + Sync>,
+}
+
+impl NetworkTracker {
+    fn attach(tab: &Tab) -> Result<Self, String> {
+        tab.call_method(Network::Enable {
+            max_total_buffer_size: None,
+            max_resource_buffer_size: None,
+            max_post_data_size: None,
+            report_direct_socket_traffic: None,
+            enable_durable_messages: None,
+        })
+        .map_err(|e| format!("Failed to enable network events: {}", e))?;
+
+        let state = Arc::new(StdMutex::new(InFlight::default()));
+        let events = state.clone();
+        let listener = tab
+            .add_event_listener(Arc::new(move |event: &Event| {
+                let mut in_flight = events.lock().unwrap_or_else(|e| e.into_inner());
+                match event {
+                    Event::NetworkRequestWillBeSent(ev) => {
+                        in_flight.requests.insert(ev.params.request_id.clone());
+                    }
+                    Event::NetworkLoadingFinished(ev) => {
+                        in_flight.requests.remove(&ev.params.request_id);
+                    }
+                    Event::NetworkLoadingFailed(ev) => {
+                        in_flight.requests.remove(&ev.params.request_id);
+                    }
+                    _ => return,
+                }
+                in_flight.update(Instant::now());
+            }))
+            .map_err(|e| format!("Failed to listen for network events: {}", e))?;
+
+        Ok(NetworkTracker { state, listener })
+    }
+
+    /// Blocks until the network has been idle for `NETWORK_IDLE_QUIET`, or
+    /// `timeout` passes. Returns whether it went idle.
+    fn wait_for_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let now = Instant::now();
+            let idle = {
+                let mut in_flight = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                // Pages that made no requests after loading are idle too
+                in_flight.update(now);
+                in_flight.is_idle(now)
+            };
+            if idle {
+                return true;
+            }
+            if now >= deadline {
+                return false;
+            }
+            thread::sleep(WAIT_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    fn detach(self, tab: &Tab) {
+        let _ = tab.remove_event_listener(&self.listener);
+    }
+}
+
+/// Launches headless Chrome, through the crawl's proxy when one is set.
+pub(crate) fn launch_browser(proxy: Option<&ProxyConfig>) -> Result<Browser, String> {
+    launch_browser_with_idle_timeout(proxy, LaunchOptions::default().idle_browser_timeout)
+}
+
+/// Like `launch_browser`, but keeps the connection open through `idle_timeout`
+/// without any commands, for browsers that sit in a pool between pages.
+pub(crate) fn launch_browser_with_idle_timeout(
+    proxy: Option<&ProxyConfig>,
+    idle_timeout: Duration,
+) -> Result<Browser, String> {
+    // We enable headless mode (default is true, but being explicit)
+    let mut options = LaunchOptions {
+        headless: true,
+        idle_browser_timeout: idle_timeout,
+        ..Default::default()
+    };
+    if let Some(proxy) = proxy {
+        proxy.configure_launch(&mut options);
+    }
+    Browser::new(options).map_err(|e| format!("Failed to create browser: {}", e))
+}
+
+/// Copies the crawl's cookies for `url` into the tab and answers auth challenges /
+/// adds extra headers. Extra headers and the crawl's HTTP auth only ever reach the
+/// crawl's own hosts, so third-party requests made by the page never see them.
+///
+/// Chrome answers every auth challenge with the same credentials, so proxy auth
+/// is only handled when the crawl doesn't use HTTP auth itself.
+pub(crate) fn prepare_tab(
+    tab: &Tab,
+    credentials: Option<&CrawlCredentials>,
+    proxy: Option<&ProxyConfig>,
+    url: &str,
+) -> Result<(), String> {
+    let site_auth = credentials.filter(|c| c.scheme() != AuthScheme::None);
+    let mut proxy_auth = proxy.and_then(|p| p.chrome_credentials());
+    if site_auth.is_some() && proxy_auth.is_some() {
+        tracing::warn!("Headless Chrome can't use proxy credentials alongside crawl HTTP auth");
+        proxy_auth = None;
+    }
+
+    let mut extra_headers: Vec<HeaderEntry> = Vec::new();
+    let mut hosts: HashSet<String> = HashSet::new();
+    if let Some(credentials) = credentials {
+        let cookies: Vec<CookieParam> = Url::parse(url)
+            .map(|page_url| credentials.cookies_for(&page_url))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| CookieParam {
+                name,
+                value,
+                url: Some(url.to_string()),
+                domain: None,
+                path: Some("/".to_string()),
+                secure: None,
+                http_only: None,
+                same_site: None,
+                expires: None,
+                priority: None,
+                same_party: None,
+                source_scheme: None,
+                source_port: None,
+                partition_key: None,
+            })
+            .collect();
+        if !cookies.is_empty() {
+            tab.set_cookies(cookies)
+                .map_err(|e| format!("Failed to set cookies: {}", e))?;
+        }
+
+        extra_headers = credentials
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some(HeaderEntry {
+                    name: name.to_string(),
+                    value: value.to_str().ok()?.to_string(),
+                })
+            })
+            .collect();
+        hosts = credentials.hosts().map(|h| h.to_string()).collect();
+    }
+
+    if extra_headers.is_empty() && site_auth.is_none() && proxy_auth.is_none() {
+        return Ok(());
+    }
+
+    if !extra_headers.is_empty() {
+        let hosts = hosts.clone();
+        let interceptor = move |_transport: Arc<Transport>,
+                                _session_id: SessionId,
+                                event: RequestPausedEvent| {
+            // With proxy auth every request is paused, not just the crawl's hosts
+            let own_host = Url::parse(&event.params.request.url)
+                .ok()
+                .and_then(|u| u.host_str().map(|h| hosts.contains(h)))
+                .unwrap_or(false);
+            if !own_host {
+                return RequestPausedDecision::Continue(None);
+            }
+
+            // Continuing with headers replaces the request's headers, so carry the
+            // original ones over.
+            let mut headers: Vec<HeaderEntry> = event
+                .params
+                .request
+                .headers
+                .0
+                .as_ref()
+                .and_then(|h| h.as_object())
+                .map(|original| {
+                    original
+                        .iter()
+                        .filter(|(name, _)| {
+                            !extra_headers.iter().any(|e| e.name.eq_ignore_ascii_case(name))
+                        })
+                        .filter_map(|(name, value)| {
+                            Some(HeaderEntry {
+                                name: name.clone(),
+                                value: value.as_str()?.to_string(),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            headers.extend(extra_headers.iter().cloned());
+
+            RequestPausedDecision::Continue(Some(ContinueRequest {
+                request_id: event.params.request_id,
+                url: None,
+                method: None,
+                post_data: None,
+                headers: Some(headers),
+                intercept_response: None,
+            }))
+        };
+        tab.enable_request_interception(Arc::new(interceptor))
+            .map_err(|e| format!("Failed to intercept requests: {}", e))?;
+    }
+
+    if let Some(credentials) = site_auth {
+        tab.authenticate(
+            Some(credentials.username().to_string()),
+            Some(credentials.password().to_string()),
+        )
+        .map_err(|e| format!("Failed to set credentials: {}", e))?;
+    } else if let Some((username, password)) = proxy_auth {
+        tab.authenticate(Some(username.to_string()), Some(password.to_string()))
+            .map_err(|e| format!("Failed to set proxy credentials: {}", e))?;
+    }
+
+    // Proxy challenges can come from any request; otherwise only the crawl's hosts
+    // need intercepting.
+    let patterns: Option<Vec<RequestPattern>> = proxy_auth.is_none().then(|| {
+        hosts
+            .iter()
+            .map(|host| RequestPattern {
+                url_pattern: Some(format!("*://{}/*", host)),
+                resource_Type: None,
+                request_stage: None,
+            })
+            .collect()
+    });
+    let handle_auth = site_auth.is_some() || proxy_auth.is_some();
+    tab.enable_fetch(patterns.as_deref(), Some(handle_auth))
+        .map_err(|e| format!("Failed to enable request interception: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_idle_needs_a_quiet_period() {
+        let start = Instant::now();
+        let mut in_flight = InFlight::default();
+        in_flight.requests.extend(["1", "2", "3"].map(String::from));
+        in_flight.update(start);
+        assert!(!in_flight.is_idle(start + NETWORK_IDLE_QUIET));
+
+        // Dropping to the threshold starts the quiet period
+        in_flight.requests.remove("3");
+        in_flight.update(start + Duration::from_millis(100));
+        assert!(!in_flight.is_idle(start + Duration::from_millis(300)));
+        assert!(in_flight.is_idle(start + Duration::from_millis(100) + NETWORK_IDLE_QUIET));
+
+        // A new burst of requests resets it
+        in_flight.requests.insert("4".to_string());
+        in_flight.update(start + Duration::from_millis(700));
+        assert!(!in_flight.is_idle(start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn selector_wait_needs_a_selector() {
+        let settings = Settings {
+            javascript_wait_strategy: JsWaitStrategy::Selector,
+            javascript_wait_selector: "  ".to_string(),
+            ..Settings::new()
+        };
+        assert_eq!(JsWait::from_settings(&settings).strategy, JsWaitStrategy::Timeout);
+    }
 }
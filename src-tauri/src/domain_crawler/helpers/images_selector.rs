@@ -5,6 +5,8 @@ use scraper::{Html, Selector};
 use tokio::time::{timeout, Duration};
 use url::Url;
 
+use super::rate_limit;
+
 /// Maximum number of concurrent image HEAD requests per page.
 /// Prevents opening too many sockets at once.
 const MAX_CONCURRENT_IMAGE_FETCHES: usize = 10;
@@ -42,6 +44,8 @@ pub fn extract_image_urls_and_alts(document: &Html, base_url: &Url) -> Vec<(Url,
 async fn fetch_image_size(client: &reqwest::Client, url: &Url) -> Result<(u64, String, u16), String> {
     let timeout_duration = Duration::from_secs(5);
 
+    // The timeout covers the request, not the wait for the per-host rate limit
+    rate_limit::until_ready(url.as_str()).await;
     let response = timeout(
         timeout_duration,
         client.head(url.as_str()).send(),
@@ -6,17 +6,69 @@ use serde::{Deserialize, Serialize};
 pub struct Indexability {
     pub indexability: f32,
     pub indexability_reason: String,
+    /// Robots directives in effect for the crawl's agent, merged from the meta
+    /// tags and the `X-Robots-Tag` header
+    #[serde(default)]
+    pub directives: Vec<RobotsDirective>,
 }
 
-static ROBOTS_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("meta[name='robots']").unwrap());
-static GOOGLEBOT_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("meta[name='googlebot']").unwrap());
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectiveSource {
+    MetaRobots,
+    XRobotsTag,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RobotsDirective {
+    /// Lowercased directive, with its value for `max-snippet: 50` and the like
+    pub directive: String,
+    pub source: DirectiveSource,
+    /// The crawler the directive is scoped to (`<meta name="googlebot">`,
+    /// `X-Robots-Tag: googlebot: noindex`); `None` when it applies to all
+    pub user_agent: Option<String>,
+}
+
+impl RobotsDirective {
+    /// Where the directive came from, for indexability reasons.
+    fn origin(&self) -> String {
+        match (self.source, &self.user_agent) {
+            (DirectiveSource::MetaRobots, None) => "meta robots tag".to_string(),
+            (DirectiveSource::MetaRobots, Some(agent)) => format!("meta {} tag", agent),
+            (DirectiveSource::XRobotsTag, None) => "X-Robots-Tag header".to_string(),
+            (DirectiveSource::XRobotsTag, Some(agent)) => {
+                format!("X-Robots-Tag header for {}", agent)
+            }
+        }
+    }
+}
+
+/// Directives that carry a value after a colon, so `name: value` is not a
+/// user-agent prefix.
+const VALUE_DIRECTIVES: &[&str] = &[
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
+
+static META_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("meta[name][content]").unwrap());
 static CANONICAL_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("link[rel='canonical']").unwrap());
 
-pub fn extract_indexability(document: &Html) -> Indexability {
-    // Check for meta robots tags
-    let robots_indexability = check_meta_robots(document);
-    if robots_indexability.indexability != 0.5 {
-        return robots_indexability;
+/// Indexability of an HTML page for `agent` (a robots.txt-style product token
+/// such as "googlebot"): meta robots and `X-Robots-Tag` directives first, then
+/// the canonical tag.
+pub fn extract_indexability(
+    document: &Html,
+    headers: &[(String, String)],
+    agent: &str,
+) -> Indexability {
+    let mut directives = meta_robots_directives(document, agent);
+    directives.extend(x_robots_tag_directives(headers, agent));
+    dedup(&mut directives);
+
+    if let Some(indexability) = directive_indexability(&directives) {
+        return indexability;
     }
 
     // Check for canonical tags
@@ -29,50 +81,147 @@ pub fn extract_indexability(document: &Html) -> Indexability {
     Indexability {
         indexability: 0.5,
         indexability_reason: "No meta robots or canonical tags found".to_string(),
+        directives,
     }
 }
 
-/// Checks for `<meta name="robots">` or `<meta name="googlebot">` tags.
-fn check_meta_robots(document: &Html) -> Indexability {
-    let meta_selectors = [
-        &ROBOTS_SELECTOR,
-        &GOOGLEBOT_SELECTOR,
-    ];
-
-    for selector in meta_selectors {
-        if let Some(meta_element) = document.select(selector).next() {
-            if let Some(content) = meta_element.value().attr("content") {
-                let content = content.trim().to_lowercase();
-                let (indexability, reason) = match content.as_str() {
-                    "index, follow" => {
-                        (1.0, "Indexable: 'index, follow' meta tag found".to_string())
+/// Indexability of a document without HTML (a PDF, say), where only the
+/// `X-Robots-Tag` response header can carry robots directives.
+pub fn header_indexability(headers: &[(String, String)], agent: &str) -> Indexability {
+    let mut directives = x_robots_tag_directives(headers, agent);
+    dedup(&mut directives);
+
+    directive_indexability(&directives).unwrap_or_else(|| Indexability {
+        indexability: 1.0,
+        indexability_reason: "Indexable: no 'noindex' X-Robots-Tag header".to_string(),
+        directives,
+    })
+}
+
+/// The `X-Robots-Tag` directives that apply to `agent`: unscoped ones and
+/// those following an `agent:` prefix in the same header.
+pub fn x_robots_tag_directives(headers: &[(String, String)], agent: &str) -> Vec<RobotsDirective> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("x-robots-tag"))
+        .flat_map(|(_, value)| parse_directives(value, true))
+        .filter(|(scope, _)| scope.as_deref().is_none_or(|s| s.eq_ignore_ascii_case(agent)))
+        .map(|(user_agent, directive)| RobotsDirective {
+            directive,
+            source: DirectiveSource::XRobotsTag,
+            user_agent,
+        })
+        .collect()
+}
+
+/// `<meta name="robots">` and `<meta name="{agent}">` directives.
+fn meta_robots_directives(document: &Html, agent: &str) -> Vec<RobotsDirective> {
+    document
+        .select(&META_SELECTOR)
+        .filter_map(|meta| {
+            let name = meta.value().attr("name")?.trim().to_lowercase();
+            let user_agent = if name == "robots" {
+                None
+            } else if name.eq_ignore_ascii_case(agent) {
+                Some(name)
+            } else {
+                return None;
+            };
+            let content = meta.value().attr("content")?;
+            Some(
+                parse_directives(content, false)
+                    .into_iter()
+                    .map(move |(_, directive)| RobotsDirective {
+                        directive,
+                        source: DirectiveSource::MetaRobots,
+                        user_agent: user_agent.clone(),
+                    }),
+            )
+        })
+        .flatten()
+        .collect()
+}
+
+/// Splits a comma-separated directive list into `(agent scope, directive)`.
+/// With `scoped`, a `name:` prefix that isn't a value directive switches the
+/// scope for the rest of the value (`googlebot: noindex, nofollow`).
+fn parse_directives(value: &str, scoped: bool) -> Vec<(Option<String>, String)> {
+    let mut scope: Option<String> = None;
+    let mut out: Vec<(Option<String>, String)> = Vec::new();
+
+    for token in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        // "unavailable_after: Friday, 25-Jun-2010 15:00:00 GMT" holds a comma
+        if let Some((_, last)) = out.last_mut() {
+            if last.starts_with("unavailable_after:")
+                && token.starts_with(|c: char| c.is_ascii_digit())
+            {
+                last.push_str(", ");
+                last.push_str(token);
+                continue;
+            }
+        }
+
+        let mut directive = token;
+        if let Some((name, rest)) = token.split_once(':') {
+            let name = name.trim().to_lowercase();
+            if VALUE_DIRECTIVES.contains(&name.as_str()) {
+                out.push((scope.clone(), format!("{}: {}", name, rest.trim())));
+                continue;
+            }
+            let is_agent = !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if scoped && is_agent {
+                scope = Some(name);
+                directive = rest.trim();
+                // "googlebot: max-snippet: 20"
+                if let Some((inner, inner_rest)) = directive.split_once(':') {
+                    let inner = inner.trim().to_lowercase();
+                    if VALUE_DIRECTIVES.contains(&inner.as_str()) {
+                        out.push((scope.clone(), format!("{}: {}", inner, inner_rest.trim())));
+                        continue;
                     }
-                    "noindex, follow" => (
-                        0.0,
-                        "Not indexable: 'noindex, follow' meta tag found".to_string(),
-                    ),
-                    "index, nofollow" => (
-                        0.8,
-                        "Partially indexable: 'index, nofollow' meta tag found".to_string(),
-                    ),
-                    "noindex, nofollow" => (
-                        0.0,
-                        "Not indexable: 'noindex, nofollow' meta tag found".to_string(),
-                    ),
-                    _ => (0.5, format!("Unknown meta tag content: '{}'", content)),
-                };
-                return Indexability {
-                    indexability,
-                    indexability_reason: reason,
-                };
+                }
             }
         }
+        if !directive.is_empty() {
+            out.push((scope.clone(), directive.to_lowercase()));
+        }
     }
+    out
+}
 
-    Indexability {
-        indexability: 0.5,
-        indexability_reason: "No meta robots tags found".to_string(),
+fn dedup(directives: &mut Vec<RobotsDirective>) {
+    let mut seen = Vec::with_capacity(directives.len());
+    directives.retain(|d| {
+        if seen.contains(d) {
+            false
+        } else {
+            seen.push(d.clone());
+            true
+        }
+    });
+}
+
+/// Indexability from the effective directives; `None` when there are none.
+/// The most restrictive directive wins, whichever source it came from.
+fn directive_indexability(directives: &[RobotsDirective]) -> Option<Indexability> {
+    if directives.is_empty() {
+        return None;
     }
+    let find = |names: &[&str]| directives.iter().find(|d| names.contains(&d.directive.as_str()));
+
+    let (indexability, indexability_reason) = if let Some(d) = find(&["noindex", "none"]) {
+        (0.0, format!("Not indexable: '{}' in {}", d.directive, d.origin()))
+    } else if let Some(d) = find(&["nofollow"]) {
+        (0.8, format!("Partially indexable: '{}' in {}", d.directive, d.origin()))
+    } else {
+        (1.0, "Indexable: no 'noindex' in the robots directives".to_string())
+    };
+    Some(Indexability {
+        indexability,
+        indexability_reason,
+        directives: directives.to_vec(),
+    })
 }
 
 /// Checks for canonical tags.
@@ -82,6 +231,7 @@ fn check_canonical_tag(document: &Html) -> Indexability {
             return Indexability {
                 indexability: 0.8, // Canonical tags suggest partial indexability
                 indexability_reason: format!("Canonical tag found pointing to: {}", href),
+                directives: Vec::new(),
             };
         }
     }
@@ -89,6 +239,61 @@ fn check_canonical_tag(document: &Html) -> Indexability {
     Indexability {
         indexability: 0.5,
         indexability_reason: "No canonical tag found".to_string(),
+        directives: Vec::new(),
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(value: &str) -> Vec<(String, String)> {
+        vec![("X-Robots-Tag".to_string(), value.to_string())]
+    }
+
+    #[test]
+    fn merges_meta_and_scoped_header_directives() {
+        let html = Html::parse_document(
+            r#"<head><meta name="robots" content="index, follow">
+            <meta name="bingbot" content="noindex"></head>"#,
+        );
+        let headers = header("noarchive, googlebot: nofollow, max-snippet: 20, otherbot: noindex");
+
+        let result = extract_indexability(&html, &headers, "Googlebot");
+        assert_eq!(result.indexability, 0.8);
+        assert_eq!(
+            result.indexability_reason,
+            "Partially indexable: 'nofollow' in X-Robots-Tag header for googlebot"
+        );
+        let directives: Vec<_> = result
+            .directives
+            .iter()
+            .map(|d| (d.directive.as_str(), d.source, d.user_agent.as_deref()))
+            .collect();
+        assert_eq!(
+            directives,
+            vec![
+                ("index", DirectiveSource::MetaRobots, None),
+                ("follow", DirectiveSource::MetaRobots, None),
+                ("noarchive", DirectiveSource::XRobotsTag, None),
+                ("nofollow", DirectiveSource::XRobotsTag, Some("googlebot")),
+                ("max-snippet: 20", DirectiveSource::XRobotsTag, Some("googlebot")),
+            ]
+        );
+
+        let bing = extract_indexability(&html, &headers, "bingbot");
+        assert_eq!(bing.indexability, 0.0);
+        assert_eq!(bing.indexability_reason, "Not indexable: 'noindex' in meta bingbot tag");
+
+        let pdf = header_indexability(
+            &header("unavailable_after: Friday, 25-Jun-2010 15:00:00 GMT, none"),
+            "googlebot",
+        );
+        assert_eq!(pdf.indexability, 0.0);
+        assert_eq!(
+            pdf.directives[0].directive,
+            "unavailable_after: Friday, 25-Jun-2010 15:00:00 GMT"
+        );
+    }
+}
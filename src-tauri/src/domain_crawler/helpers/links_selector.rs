@@ -20,6 +20,11 @@ pub fn extract_links(document: &Html, resolve_url: &Url, scope_url: &Url) -> Has
     unique_urls
 }
 
+/// Resolves one `href` found on `resolve_url` (a canonical or hreflang, say),
+/// with the same rules as page links. `None` when it's not an internal URL.
+pub fn resolve_link(resolve_url: &Url, scope_url: &Url, href: &str) -> Option<Url> {
+    process_link(resolve_url, scope_url, href)
+}
 
 /// Process a single link
 fn process_link(resolve_url: &Url, scope_url: &Url, href: &str) -> Option<Url> {
@@ -18,13 +18,17 @@ use reqwest::{
         HeaderMap, ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CACHE_CONTROL, CONNECTION, DNT,
         UPGRADE_INSECURE_REQUESTS,
     },
-    Client, Url,
+    Client, Method, Url,
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, Notify, Semaphore};
 use tokio::time::{sleep, timeout};
 
 use crate::domain_crawler::helpers::anchor_links::InternalExternalLinks;
+use crate::domain_crawler::helpers::crawl_auth::{send_with_credentials, CrawlCredentials};
+use crate::domain_crawler::helpers::rate_limit;
+use crate::domain_crawler::helpers::proxy::ProxyConfig;
+use crate::domain_crawler::user_agents::CrawlIdentity;
 use crate::settings::settings::Settings;
 
 #[derive(Debug, Clone)]
@@ -70,6 +74,8 @@ impl LinkCheckConfig {
 
 pub struct SharedLinkChecker {
     client: Arc<Client>,
+    /// Auth for the crawl's own hosts; external links are checked without it
+    credentials: Option<Arc<CrawlCredentials>>,
     domain_tracker: Arc<DomainTracker>,
     semaphore: Arc<Semaphore>,
     pub config: LinkCheckConfig,
@@ -88,13 +94,29 @@ pub struct SharedLinkChecker {
 impl SharedLinkChecker {
     pub fn new(
         settings: &Settings,
-        user_agent: Option<String>,
+        identity: Option<&CrawlIdentity>,
+        credentials: Option<Arc<CrawlCredentials>>,
         url_status_registry: Arc<DashMap<String, u16>>,
     ) -> Self {
         let config = LinkCheckConfig::from_settings(settings);
-        let client = build_client(&config, settings, user_agent);
+        let proxy = ProxyConfig::configured(settings);
+        let client = match identity {
+            Some(identity) => build_client(
+                &config,
+                identity,
+                credentials.as_deref(),
+                proxy.as_ref(),
+            ),
+            None => build_client(
+                &config,
+                &CrawlIdentity::from_settings(settings),
+                credentials.as_deref(),
+                proxy.as_ref(),
+            ),
+        };
         SharedLinkChecker {
             client: Arc::new(client),
+            credentials,
             domain_tracker: Arc::new(DomainTracker::new(&config)),
             semaphore: Arc::new(Semaphore::new(config.concurrent_requests)),
             config,
@@ -124,6 +146,7 @@ impl SharedLinkChecker {
             let mut stream = stream::iter(links_iter)
                 .map(|(link, anchor, rel, title, target, is_internal)| {
                     let client = self.client.clone();
+                    let credentials = self.credentials.clone();
                     let semaphore = self.semaphore.clone();
                     let base_url_arc = base_url_arc.clone();
                     let page_arc = page_arc.clone();
@@ -137,6 +160,7 @@ impl SharedLinkChecker {
                     async move {
                         process_single_link(
                             client,
+                            credentials,
                             semaphore,
                             base_url_arc,
                             page_arc,
@@ -308,6 +332,7 @@ pub async fn get_links_status_code(
     let checker = SharedLinkChecker::new(
         &settings,
         None,
+        None,
         Arc::new(DashMap::new()),
     );
     checker.check_links(links, base_url, page).await
@@ -322,12 +347,18 @@ pub async fn get_links_status_code_from_settings(
     let checker = SharedLinkChecker::new(
         settings,
         None,
+        None,
         Arc::new(DashMap::new()),
     );
     checker.check_links(links, base_url, page).await
 }
 
-fn build_client(config: &LinkCheckConfig, _settings: &Settings, _user_agent: Option<String>) -> Client {
+fn build_client(
+    config: &LinkCheckConfig,
+    identity: &CrawlIdentity,
+    credentials: Option<&CrawlCredentials>,
+    proxy: Option<&ProxyConfig>,
+) -> Client {
     let mut headers = HeaderMap::new();
     headers.insert(ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7".parse().unwrap());
     headers.insert(ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
@@ -336,23 +367,30 @@ fn build_client(config: &LinkCheckConfig, _settings: &Settings, _user_agent: Opt
     headers.insert(CACHE_CONTROL, "max-age=0".parse().unwrap());
     headers.insert(DNT, "1".parse().unwrap());
     headers.insert(UPGRADE_INSECURE_REQUESTS, "1".parse().unwrap());
-    headers.insert("Sec-Ch-Ua", "\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\", \"Not-A.Brand\";v=\"99\"".parse().unwrap());
-    headers.insert("Sec-Ch-Ua-Mobile", "?0".parse().unwrap());
-    headers.insert("Sec-Ch-Ua-Platform", "\"Windows\"".parse().unwrap());
-    headers.insert("Sec-Fetch-Dest", "document".parse().unwrap());
-    headers.insert("Sec-Fetch-Mode", "navigate".parse().unwrap());
-    headers.insert("Sec-Fetch-Site", "none".parse().unwrap());
-    headers.insert("Sec-Fetch-User", "?1".parse().unwrap());
+    for (name, value) in identity.browser_headers() {
+        if let Ok(value) = value.parse() {
+            headers.insert(name, value);
+        }
+    }
 
-    let spoofed_ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".to_string();
+    let mut builder = Client::builder();
+    if let Some(credentials) = credentials {
+        builder = builder
+            .cookie_provider(credentials.cookie_jar())
+            .redirect(credentials.redirect_policy(5));
+    } else {
+        builder = builder.redirect(reqwest::redirect::Policy::limited(5));
+    }
+    if let Some(proxy) = proxy {
+        builder = proxy.apply(builder);
+    }
 
-    Client::builder()
+    builder
         .timeout(Duration::from_secs(config.request_timeout_secs))
         .connect_timeout(Duration::from_secs(config.connection_timeout_secs))
         .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
         .pool_max_idle_per_host(config.pool_max_idle_per_host)
-        .user_agent(spoofed_ua)
-        .redirect(reqwest::redirect::Policy::limited(5))
+        .user_agent(&identity.user_agent)
         .default_headers(headers)
         .danger_accept_invalid_certs(false)
         .http1_only()
@@ -398,6 +436,7 @@ fn prepare_links(
 
 async fn process_single_link(
     client: Arc<Client>,
+    credentials: Option<Arc<CrawlCredentials>>,
     semaphore: Arc<Semaphore>,
     base_url: Arc<Url>,
     page: Arc<String>,
@@ -586,6 +625,7 @@ async fn process_single_link(
 
     let result = fetch_with_retry(
         &client,
+        credentials.as_deref(),
         &full_url_str,
         &domain,
         &domain_tracker,
@@ -648,6 +688,7 @@ async fn process_single_link(
 
 async fn fetch_with_retry(
     client: &Client,
+    credentials: Option<&CrawlCredentials>,
     url: &str,
     domain: &str,
     domain_tracker: &DomainTracker,
@@ -697,7 +738,7 @@ async fn fetch_with_retry(
 
         match timeout(
             Duration::from_secs(config.request_timeout_secs),
-            try_head_then_get(client, url),
+            try_head_then_get(client, credentials, url),
         )
         .await
         {
@@ -781,14 +822,17 @@ fn process_results(
 
 async fn try_head_then_get(
     client: &Client,
+    credentials: Option<&CrawlCredentials>,
     url: &str,
 ) -> Result<reqwest::Response, reqwest::Error> {
     // Try HEAD request first
-    match client.head(url).send().await {
+    rate_limit::until_ready(url).await;
+    match send_with_credentials(client, Method::HEAD, url, credentials).await {
         Ok(response) => Ok(response),
         Err(_head_err) => {
             // Fallback to GET if HEAD fails
-            match client.get(url).send().await {
+            rate_limit::until_ready(url).await;
+            match send_with_credentials(client, Method::GET, url, credentials).await {
                 Ok(response) => Ok(response),
                 Err(get_err) => Err(get_err),
             }
@@ -0,0 +1,277 @@
+//! Mobile vs desktop parity (`mobile_parity_check`). Every crawled page is
+//! fetched a second time as the counterpart device of the crawl's identity (see
+//! `CrawlIdentity::counterpart`), rendered with a phone viewport when it's the
+//! mobile side and `javascript_rendering` is on, and the two versions are
+//! compared: status and redirects, content length, internal links, headings and
+//! structured data.
+
+use reqwest::{Client, Url};
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use super::crawl_auth::CrawlCredentials;
+use super::fetch_with_exponential::fetch_with_exponential_backoff;
+use super::headings_selector::headings_selector;
+use super::links_selector::extract_links;
+use super::render_diff::{sorted_difference, structured_data_types, MAX_LISTED_LINKS};
+use super::word_count::get_word_count;
+use crate::domain_crawler::user_agents::CrawlIdentity;
+use crate::settings::settings::Settings;
+
+/// Content length may differ by this fraction of the larger page before it's
+/// reported; ads and tracking markup always vary a little.
+const CONTENT_LENGTH_TOLERANCE: f64 = 0.1;
+
+const MAX_REDIRECTS: usize = 10;
+
+/// The client and identity used for the counterpart fetch.
+pub struct ParityChecker {
+    client: Client,
+    identity: CrawlIdentity,
+}
+
+/// The counterpart page as fetched, before parsing.
+pub struct ParityFetch {
+    pub status_code: u16,
+    pub final_url: Url,
+    pub redirect_count: usize,
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+impl ParityChecker {
+    /// `client` must send `identity`'s user agent and headers, and not follow
+    /// redirects itself.
+    pub fn new(client: Client, identity: CrawlIdentity) -> Self {
+        ParityChecker { client, identity }
+    }
+
+    pub fn identity(&self) -> &CrawlIdentity {
+        &self.identity
+    }
+
+    /// Fetches `url`, following up to 10 redirects.
+    pub async fn fetch(
+        &self,
+        url: &Url,
+        settings: &Settings,
+        credentials: Option<&CrawlCredentials>,
+    ) -> Result<ParityFetch, String> {
+        let mut current = url.clone();
+        let mut visited = vec![current.to_string()];
+
+        loop {
+            let (response, _) = tokio::time::timeout(
+                Duration::from_secs(settings.client_timeout),
+                fetch_with_exponential_backoff(
+                    &self.client,
+                    current.as_str(),
+                    settings,
+                    credentials,
+                ),
+            )
+            .await
+            .map_err(|_| format!("Timeout fetching {}", current))?
+            .map_err(|e| format!("Failed to fetch {}: {}", current, e))?;
+
+            let status = response.status();
+            let next = status
+                .is_redirection()
+                .then(|| response.headers().get("location"))
+                .flatten()
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| current.join(location).ok());
+
+            match next {
+                Some(next)
+                    if visited.len() <= MAX_REDIRECTS && !visited.contains(&next.to_string()) =>
+                {
+                    visited.push(next.to_string());
+                    current = next;
+                }
+                _ => {
+                    let content_type = response
+                        .headers()
+                        .get("content-type")
+                        .and_then(|h| h.to_str().ok())
+                        .map(String::from);
+                    let body = response.text().await.unwrap_or_default();
+                    return Ok(ParityFetch {
+                        status_code: status.as_u16(),
+                        final_url: current,
+                        redirect_count: visited.len() - 1,
+                        content_type,
+                        body,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// One device's view of a page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileSnapshot {
+    pub user_agent: String,
+    pub status_code: u16,
+    pub final_url: String,
+    pub redirect_count: usize,
+    pub content_length: usize,
+    pub word_count: usize,
+    pub internal_links: usize,
+    /// Heading texts by tag (`h1`..`h6`)
+    pub headings: BTreeMap<String, Vec<String>>,
+    /// Schema types from JSON-LD and microdata
+    pub structured_data: Option<String>,
+    #[serde(skip)]
+    links: HashSet<String>,
+}
+
+impl ProfileSnapshot {
+    /// `page_url` resolves relative links; links on `base_url`'s domain are internal.
+    pub fn from_page(
+        document: &Html,
+        page_url: &Url,
+        base_url: &Url,
+        user_agent: &str,
+        status_code: u16,
+        redirect_count: usize,
+        content_length: usize,
+    ) -> Self {
+        let links: HashSet<String> = extract_links(document, page_url, base_url)
+            .into_iter()
+            .map(|u| u.to_string())
+            .collect();
+
+        ProfileSnapshot {
+            user_agent: user_agent.to_string(),
+            status_code,
+            final_url: page_url.to_string(),
+            redirect_count,
+            content_length,
+            word_count: get_word_count(document),
+            internal_links: links.len(),
+            headings: headings_selector(document).into_iter().collect(),
+            structured_data: structured_data_types(document),
+            links,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParityReport {
+    pub mobile: Option<ProfileSnapshot>,
+    pub desktop: Option<ProfileSnapshot>,
+    /// What differs: "status", "redirect", "content_length", "links", "headings"
+    /// and/or "structured_data"
+    pub mismatches: Vec<String>,
+    pub mobile_only_links: Vec<String>,
+    pub mobile_only_links_count: usize,
+    pub desktop_only_links: Vec<String>,
+    pub desktop_only_links_count: usize,
+    /// Why the counterpart fetch failed, when it did
+    pub error: Option<String>,
+}
+
+impl ParityReport {
+    pub fn compare(mobile: ProfileSnapshot, desktop: ProfileSnapshot) -> Self {
+        let mut mismatches = Vec::new();
+        if mobile.status_code != desktop.status_code {
+            mismatches.push("status");
+        }
+        if mobile.final_url != desktop.final_url || mobile.redirect_count != desktop.redirect_count
+        {
+            mismatches.push("redirect");
+        }
+        let longest = mobile.content_length.max(desktop.content_length);
+        if longest > 0
+            && mobile.content_length.abs_diff(desktop.content_length) as f64
+                > longest as f64 * CONTENT_LENGTH_TOLERANCE
+        {
+            mismatches.push("content_length");
+        }
+        if mobile.links != desktop.links {
+            mismatches.push("links");
+        }
+        if mobile.headings != desktop.headings {
+            mismatches.push("headings");
+        }
+        if mobile.structured_data != desktop.structured_data {
+            mismatches.push("structured_data");
+        }
+
+        let mobile_only = sorted_difference(&mobile.links, &desktop.links);
+        let desktop_only = sorted_difference(&desktop.links, &mobile.links);
+
+        ParityReport {
+            mismatches: mismatches.into_iter().map(String::from).collect(),
+            mobile_only_links_count: mobile_only.len(),
+            mobile_only_links: mobile_only.into_iter().take(MAX_LISTED_LINKS).collect(),
+            desktop_only_links_count: desktop_only.len(),
+            desktop_only_links: desktop_only.into_iter().take(MAX_LISTED_LINKS).collect(),
+            mobile: Some(mobile),
+            desktop: Some(desktop),
+            error: None,
+        }
+    }
+
+    /// The crawl's own profile, when the counterpart couldn't be fetched.
+    pub fn failed(primary: ProfileSnapshot, primary_is_mobile: bool, error: String) -> Self {
+        let (mobile, desktop) = if primary_is_mobile {
+            (Some(primary), None)
+        } else {
+            (None, Some(primary))
+        };
+        ParityReport {
+            mobile,
+            desktop,
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_what_differs_between_devices() {
+        let page = Url::parse("https://example.com/").unwrap();
+        let snapshot = |html: &str, status: u16, redirects: usize| {
+            ProfileSnapshot::from_page(
+                &Html::parse_document(html),
+                &page,
+                &page,
+                "test",
+                status,
+                redirects,
+                html.len(),
+            )
+        };
+
+        let desktop = snapshot(
+            r#"<h1>Shop</h1><h2>Deals</h2><a href="/deals">Deals</a><a href="/help">Help</a>
+               <script type="application/ld+json">{"@type":"Organization"}</script>"#,
+            200,
+            0,
+        );
+        let mobile = snapshot(r#"<h1>Shop</h1><a href="/deals">Deals</a>"#, 200, 1);
+
+        let report = ParityReport::compare(mobile, desktop);
+        assert_eq!(
+            report.mismatches,
+            ["redirect", "content_length", "links", "headings", "structured_data"]
+        );
+        assert_eq!(report.desktop_only_links, ["https://example.com/help"]);
+        assert!(report.mobile_only_links.is_empty());
+
+        let same = ParityReport::compare(
+            snapshot("<h1>Same</h1>", 200, 0),
+            snapshot("<h1>Same</h1>", 200, 0),
+        );
+        assert!(same.mismatches.is_empty());
+    }
+}
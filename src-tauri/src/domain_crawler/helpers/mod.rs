@@ -1,10 +1,13 @@
 pub mod alt_tags;
 pub mod anchor_links;
 pub mod blocked_robots;
+pub mod browser_pool;
 pub mod canonical_selector;
 pub mod check_html_page;
 pub mod content_signature;
 pub mod cookies;
+pub mod crawl_auth;
+pub mod crawl_budget;
 pub mod cross_origin;
 pub mod css_selector;
 pub mod domain_checker;
@@ -12,6 +15,7 @@ pub mod extract_url_pattern;
 pub mod favicon;
 pub mod fetch_with_exponential;
 pub mod flesch_reader;
+pub mod form_login;
 pub mod headings_selector;
 pub mod headless_fetch;
 pub mod hreflang_selector;
@@ -27,18 +31,29 @@ pub mod links_selector;
 pub mod links_status_code_checker;
 pub mod meta_robots_selector;
 pub mod mobile_checker;
+pub mod mobile_parity;
 pub mod ngrams;
 pub mod normalize_url;
 pub mod opengraph;
 pub mod page_description;
 pub mod pdf_checker;
+pub mod pdf_document;
 pub mod pdf_selector;
+pub mod proxy;
+pub mod rate_limit;
+pub mod render_diff;
+pub mod resources;
 pub mod robots;
 pub mod schema_selector;
+pub mod security_headers;
 pub mod screenshot;
 pub mod sitemap;
 pub mod skip_url;
+pub mod spider_trap;
 pub mod text_ratio;
 pub mod title_selector;
+pub mod tls_audit;
 pub mod url_depth;
+pub mod url_rewrite;
+pub mod url_scope;
 pub mod word_count;
@@ -0,0 +1,474 @@
+//! Best-effort PDF audit for `Settings::crawl_pdfs`: document info (title, author),
+//! page count, the text drawn with literal strings (for the word count and the
+//! language), and link annotation URIs. This is a byte scanner over the file
+//! and its Flate-compressed streams, not a full PDF object model: text drawn
+//! with CID fonts (hex strings that need the font's ToUnicode map) isn't
+//! recovered, and an encrypted document only yields its page count.
+
+use flate2::read::ZlibDecoder;
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
+
+/// Decompressed bytes kept across all of a document's streams.
+const MAX_INFLATED_BYTES: usize = 32 * 1024 * 1024;
+
+static PAGE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"/Type\s*/Page\b").unwrap());
+static STREAM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"stream\r?\n").unwrap());
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PdfDocument {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub page_count: usize,
+    pub word_count: usize,
+    /// `/Lang` of the document catalog, or guessed from the text
+    pub language: Option<String>,
+    /// URIs of the document's link annotations
+    pub links: Vec<String>,
+    /// The download stopped at `pdf_max_size_mb`, so the audit covers part of the file
+    pub truncated: bool,
+    /// Why the document could only be partly read
+    pub error: Option<String>,
+}
+
+impl PdfDocument {
+    pub fn parse(bytes: &[u8], truncated: bool) -> Self {
+        if !bytes.starts_with(b"%PDF") {
+            return PdfDocument {
+                truncated,
+                error: Some("Not a PDF document".to_string()),
+                ..Default::default()
+            };
+        }
+
+        // The file itself plus every stream that inflates, since PDF 1.5+ files
+        // keep their objects (Info, pages, annotations) in compressed object streams.
+        let mut sections: Vec<Vec<u8>> = vec![bytes.to_vec()];
+        sections.extend(flate_streams(bytes));
+
+        let mut doc = PdfDocument {
+            truncated,
+            ..Default::default()
+        };
+        let mut text = String::new();
+        let mut links = HashSet::new();
+        for (i, section) in sections.iter().enumerate() {
+            doc.page_count += PAGE_RE.find_iter(section).count();
+            if doc.title.is_none() || doc.author.is_none() {
+                if let Some(info) = info_dictionary(section) {
+                    doc.title = doc.title.or_else(|| string_value(info, b"/Title"));
+                    doc.author = doc.author.or_else(|| string_value(info, b"/Author"));
+                }
+            }
+            if doc.language.is_none() {
+                doc.language = string_value(section, b"/Lang").filter(|lang| !lang.is_empty());
+            }
+            for uri in string_values(section, b"/URI") {
+                if links.insert(uri.clone()) {
+                    doc.links.push(uri);
+                }
+            }
+            // Content streams are the sections with text objects
+            if i > 0 && contains(section, b"BT") {
+                extract_text(section, &mut text);
+            }
+        }
+
+        doc.word_count = text
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .count();
+        if doc.language.is_none() {
+            doc.language = guess_language(&text).map(str::to_string);
+        }
+        if contains(bytes, b"/Encrypt") {
+            doc.error = Some("Encrypted document: text and metadata may be unreadable".to_string());
+        } else if truncated {
+            doc.error = Some("Download stopped at the size limit".to_string());
+        }
+        doc
+    }
+}
+
+/// Reads the response body up to `max_bytes`. The flag tells whether the
+/// download stopped at the limit.
+pub async fn download(
+    mut response: reqwest::Response,
+    max_bytes: u64,
+) -> Result<(Vec<u8>, bool), reqwest::Error> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() as u64 >= max_bytes {
+            bytes.truncate(max_bytes as usize);
+            return Ok((bytes, true));
+        }
+    }
+    Ok((bytes, false))
+}
+
+/// Inflated data of every FlateDecode stream (images aside), up to
+/// `MAX_INFLATED_BYTES` in total. Decoding stops once that budget is used up.
+fn flate_streams(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut streams = Vec::new();
+    let mut budget = MAX_INFLATED_BYTES as u64;
+    for m in STREAM_RE.find_iter(bytes) {
+        if budget == 0 {
+            break;
+        }
+        // "endstream" also matches; its dictionary is the previous stream's
+        if m.start() >= 3 && &bytes[m.start() - 3..m.start()] == b"end" {
+            continue;
+        }
+        let dict_start = m.start().saturating_sub(1024);
+        let dict = &bytes[dict_start..m.start()];
+        let dict = match rfind(dict, b"obj") {
+            Some(i) => &dict[i..],
+            None => dict,
+        };
+        if !contains(dict, b"/FlateDecode") || contains(dict, b"/Image") {
+            continue;
+        }
+        let data = &bytes[m.end()..];
+        let data = match find(data, b"endstream") {
+            Some(end) => &data[..end],
+            None => data,
+        };
+        let mut out = Vec::new();
+        let mut decoder = ZlibDecoder::new(data).take(budget);
+        // A stream cut off by the budget still yields what was decoded
+        let _ = decoder.read_to_end(&mut out);
+        budget -= out.len() as u64;
+        if !out.is_empty() {
+            streams.push(out);
+        }
+    }
+    streams
+}
+
+/// The document info dictionary: the dictionary around a /Title that also has
+/// one of the info keys, which tells it apart from outline (bookmark) titles.
+fn info_dictionary(section: &[u8]) -> Option<&[u8]> {
+    const INFO_KEYS: [&[u8]; 5] = [
+        b"/Producer",
+        b"/Creator",
+        b"/CreationDate",
+        b"/ModDate",
+        b"/Author",
+    ];
+    let mut from = 0;
+    while let Some(i) = find(&section[from..], b"/Title") {
+        let at = from + i;
+        let start = rfind(&section[..at], b"<<").unwrap_or(0);
+        let end = find(&section[at..], b">>").map_or(section.len(), |e| at + e);
+        let dict = &section[start..end];
+        if INFO_KEYS.iter().any(|key| contains(dict, key)) {
+            return Some(dict);
+        }
+        from = at + 6;
+    }
+    None
+}
+
+fn string_value(section: &[u8], key: &[u8]) -> Option<String> {
+    let at = find(section, key)? + key.len();
+    read_string(&section[at..]).map(|(s, _)| s)
+}
+
+fn string_values(section: &[u8], key: &[u8]) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut from = 0;
+    while let Some(i) = find(&section[from..], key) {
+        let at = from + i + key.len();
+        if let Some((value, _)) = read_string(&section[at..]) {
+            values.push(value);
+        }
+        from = at;
+    }
+    values
+}
+
+/// Reads the literal `(...)` or hex `<...>` string at the start of `bytes`
+/// (after whitespace), returning it and the bytes consumed.
+fn read_string(bytes: &[u8]) -> Option<(String, usize)> {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace())?;
+    match bytes[start] {
+        b'(' => {
+            let (raw, len) = literal_string(&bytes[start..])?;
+            Some((decode_text(&raw), start + len))
+        }
+        b'<' if bytes.get(start + 1) != Some(&b'<') => {
+            let end = bytes[start..].iter().position(|&b| b == b'>')?;
+            let hex: Vec<u8> = bytes[start + 1..start + end]
+                .iter()
+                .copied()
+                .filter(u8::is_ascii_hexdigit)
+                .collect();
+            let raw: Vec<u8> = hex
+                .chunks(2)
+                .filter_map(|pair| {
+                    let pair = if pair.len() == 2 {
+                        pair.to_vec()
+                    } else {
+                        vec![pair[0], b'0']
+                    };
+                    u8::from_str_radix(std::str::from_utf8(&pair).ok()?, 16).ok()
+                })
+                .collect();
+            Some((decode_text(&raw), start + end + 1))
+        }
+        _ => None,
+    }
+}
+
+/// Unescapes a literal string starting at `(`, with balanced parentheses.
+fn literal_string(bytes: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'\\' => {
+                i += 1;
+                let Some(&next) = bytes.get(i) else { break };
+                match next {
+                    b'n' => out.push(b'\n'),
+                    b'r' => out.push(b'\r'),
+                    b't' => out.push(b'\t'),
+                    b'b' | b'f' => {}
+                    b'0'..=b'7' => {
+                        let digits = bytes[i..]
+                            .iter()
+                            .take(3)
+                            .take_while(|d| (b'0'..=b'7').contains(d))
+                            .count();
+                        let octal = std::str::from_utf8(&bytes[i..i + digits]).ok()?;
+                        out.push(u8::from_str_radix(octal, 8).unwrap_or(b'?'));
+                        i += digits - 1;
+                    }
+                    b'\r' | b'\n' => {}
+                    other => out.push(other),
+                }
+            }
+            b'(' => {
+                if depth > 0 {
+                    out.push(b);
+                }
+                depth += 1;
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((out, i + 1));
+                }
+                out.push(b);
+            }
+            _ => out.push(b),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Text strings are UTF-16BE with a byte order mark, or PDFDocEncoding
+/// (read as Latin-1, which it matches for printable characters).
+fn decode_text(raw: &[u8]) -> String {
+    if raw.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = raw[2..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units).trim().to_string();
+    }
+    if let Ok(utf8) = std::str::from_utf8(raw) {
+        return utf8.trim().to_string();
+    }
+    raw.iter()
+        .map(|&b| b as char)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Appends the literal strings shown between BT and ET. Kerning offsets inside
+/// TJ arrays only become spaces when they are wide enough to be a word gap.
+fn extract_text(content: &[u8], text: &mut String) {
+    let mut in_text = false;
+    let mut i = 0;
+    while i < content.len() {
+        match content[i] {
+            b'(' if in_text => {
+                if let Some((raw, len)) = literal_string(&content[i..]) {
+                    text.push_str(&decode_text(&raw));
+                    i += len;
+                    continue;
+                }
+            }
+            b'[' if in_text => {}
+            b']' if in_text => text.push(' '),
+            b'-' if in_text => {
+                let digits = content[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit() || **b == b'.')
+                    .count();
+                let number = std::str::from_utf8(&content[i + 1..i + 1 + digits])
+                    .ok()
+                    .and_then(|n| n.parse::<f32>().ok());
+                if number.is_some_and(|n| n > 200.0) {
+                    text.push(' ');
+                }
+                i += digits + 1;
+                continue;
+            }
+            b'B' if content.get(i + 1) == Some(&b'T') && is_operator_end(content, i + 2) => {
+                in_text = true;
+            }
+            b'E' if content.get(i + 1) == Some(&b'T') && is_operator_end(content, i + 2) => {
+                in_text = false;
+                text.push(' ');
+            }
+            b'T' if in_text
+                && matches!(content.get(i + 1), Some(b'j' | b'J' | b'*' | b'd' | b'D')) =>
+            {
+                text.push(' ');
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+fn is_operator_end(content: &[u8], at: usize) -> bool {
+    content
+        .get(at)
+        .is_none_or(|b| b.is_ascii_whitespace() || b"[(/<".contains(b))
+}
+
+/// Guesses the language of extracted text from its most common function words.
+fn guess_language(text: &str) -> Option<&'static str> {
+    const STOP_WORDS: [(&str, &[&str]); 7] = [
+        (
+            "en",
+            &[
+                "the", "and", "of", "to", "is", "in", "that", "for", "with", "are",
+            ],
+        ),
+        (
+            "es",
+            &[
+                "el", "la", "de", "que", "y", "los", "las", "por", "una", "para",
+            ],
+        ),
+        (
+            "fr",
+            &[
+                "le", "la", "les", "et", "des", "est", "une", "pour", "dans", "que",
+            ],
+        ),
+        (
+            "de",
+            &[
+                "der", "die", "und", "das", "ist", "nicht", "mit", "den", "ein", "für",
+            ],
+        ),
+        (
+            "pt",
+            &[
+                "o", "os", "de", "que", "e", "do", "da", "não", "uma", "para",
+            ],
+        ),
+        (
+            "it",
+            &[
+                "il", "di", "che", "e", "la", "per", "non", "una", "sono", "gli",
+            ],
+        ),
+        (
+            "nl",
+            &[
+                "de", "het", "een", "en", "van", "is", "dat", "niet", "met", "voor",
+            ],
+        ),
+    ];
+    let words: Vec<String> = text
+        .split_whitespace()
+        .take(5_000)
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .collect();
+    STOP_WORDS
+        .iter()
+        .map(|(lang, stop)| {
+            (
+                lang,
+                words.iter().filter(|w| stop.contains(&w.as_str())).count(),
+            )
+        })
+        .filter(|(_, hits)| *hits >= 5)
+        .max_by_key(|(_, hits)| *hits)
+        .map(|(lang, _)| *lang)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn reads_info_pages_text_and_links() {
+        let content = b"BT /F1 12 Tf 72 720 Td (The annual report of the company) Tj \
+            [(is)-250(ready f)10(or the)-300(board and the team)] TJ ET";
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut pdf = b"%PDF-1.4\n1 0 obj << /Type /Catalog /Pages 2 0 R /Lang (en-GB) >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >> endobj\n\
+            3 0 obj << /Type /Page /Parent 2 0 R /Contents 5 0 R /Annots [6 0 R] >> endobj\n\
+            4 0 obj << /Type/Page /Parent 2 0 R >> endobj\n\
+            6 0 obj << /Type /Annot /Subtype /Link /A << /S /URI /URI (https://example.com/pricing) >> >> endobj\n\
+            7 0 obj << /Title (Outline \\(1\\)) >> endobj\n\
+            8 0 obj << /Title <FEFF005200650070006f00720074> /Author (Jane Doe) /Producer (Writer) >> endobj\n"
+            .to_vec();
+        pdf.extend_from_slice(
+            format!(
+                "5 0 obj << /Length {} /Filter /FlateDecode >>\nstream\n",
+                compressed.len()
+            )
+            .as_bytes(),
+        );
+        pdf.extend_from_slice(&compressed);
+        pdf.extend_from_slice(b"\nendstream\nendobj\ntrailer << /Root 1 0 R /Info 8 0 R >>\n%%EOF");
+
+        let doc = PdfDocument::parse(&pdf, false);
+        assert_eq!(doc.title.as_deref(), Some("Report"));
+        assert_eq!(doc.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(doc.page_count, 2);
+        assert_eq!(doc.language.as_deref(), Some("en-GB"));
+        assert_eq!(doc.links, vec!["https://example.com/pricing"]);
+        assert_eq!(doc.word_count, 14);
+        assert!(doc.error.is_none());
+
+        assert!(PdfDocument::parse(b"<html></html>", false).error.is_some());
+    }
+}
@@ -0,0 +1,163 @@
+//! Outbound proxy for crawl traffic (`proxy_*` settings): HTTP, HTTPS or SOCKS5,
+//! with optional credentials and a list of hosts that bypass the proxy.
+//!
+//! The same proxy is applied to the page client, the link checker, PageSpeed
+//! Insights requests and headless Chrome. Chrome can't authenticate against
+//! SOCKS proxies, and only answers proxy auth challenges when the crawl itself
+//! doesn't use HTTP auth (see `headless_fetch::prepare_tab`).
+
+use headless_chrome::LaunchOptions;
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use std::ffi::OsStr;
+use url::Url;
+
+use crate::settings::settings::Settings;
+
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    proxy: Proxy,
+    /// `scheme://host:port`, without credentials
+    server: String,
+    username: String,
+    password: String,
+    /// `--proxy-bypass-list=...` for Chrome, when hosts bypass the proxy
+    chrome_bypass_arg: Option<String>,
+    key: String,
+}
+
+impl ProxyConfig {
+    /// Reads the proxy settings. `Ok(None)` when no proxy is configured, `Err` when
+    /// the proxy URL is invalid.
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>, String> {
+        let raw = settings.proxy_url.trim();
+        if raw.is_empty() {
+            return Ok(None);
+        }
+
+        let mut url = Url::parse(raw).map_err(|_| format!("Invalid proxy URL: {}", raw))?;
+        if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+            return Err(format!(
+                "Unsupported proxy scheme '{}' (use http, https, socks5 or socks5h)",
+                url.scheme()
+            ));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| format!("Proxy URL has no host: {}", raw))?
+            .to_string();
+        // SOCKS has no default port in the URL spec
+        let port = url.port_or_known_default().unwrap_or(1080);
+
+        // Credentials in the settings win over any embedded in the URL
+        let username = if settings.proxy_username.is_empty() {
+            url.username().to_string()
+        } else {
+            settings.proxy_username.clone()
+        };
+        let password = if settings.proxy_username.is_empty() {
+            url.password().unwrap_or_default().to_string()
+        } else {
+            settings.proxy_password.clone()
+        };
+        // reqwest reads proxy credentials from the URL for both HTTP and SOCKS proxies
+        let _ = url.set_username(&username);
+        let _ = url.set_password((!password.is_empty()).then_some(password.as_str()));
+
+        let no_proxy: Vec<&str> = settings
+            .proxy_no_proxy
+            .iter()
+            .map(|h| h.trim())
+            .filter(|h| !h.is_empty())
+            .collect();
+        let proxy = Proxy::all(url.as_str())
+            .map_err(|e| format!("Invalid proxy URL {}: {}", raw, e))?
+            .no_proxy(NoProxy::from_string(&no_proxy.join(",")));
+
+        // Chrome takes socks5 for both; it always resolves names through SOCKS5
+        let chrome_scheme = if url.scheme() == "socks5h" { "socks5" } else { url.scheme() };
+        let server = format!("{}://{}:{}", chrome_scheme, host, port);
+        let chrome_bypass_arg = (!no_proxy.is_empty())
+            .then(|| format!("--proxy-bypass-list={}", no_proxy.join(";")));
+        let key = format!("{}|{}", url, no_proxy.join(","));
+
+        Ok(Some(ProxyConfig {
+            proxy,
+            server,
+            username,
+            password,
+            chrome_bypass_arg,
+            key,
+        }))
+    }
+
+    /// Like `from_settings`, but logs an invalid proxy and connects directly.
+    /// Crawls reject invalid proxy settings before they start, so this only
+    /// matters for one-off requests outside a crawl.
+    pub fn configured(settings: &Settings) -> Option<Self> {
+        Self::from_settings(settings).unwrap_or_else(|e| {
+            tracing::error!("Ignoring proxy settings: {}", e);
+            None
+        })
+    }
+
+    /// Routes a client's requests through the proxy.
+    pub fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        builder.proxy(self.proxy.clone())
+    }
+
+    /// Points a headless Chrome launch at the proxy.
+    pub fn configure_launch<'a>(&'a self, options: &mut LaunchOptions<'a>) {
+        options.proxy_server = Some(&self.server);
+        if let Some(arg) = &self.chrome_bypass_arg {
+            options.args.push(OsStr::new(arg));
+        }
+    }
+
+    /// Proxy credentials Chrome can answer auth challenges with. `None` for SOCKS
+    /// proxies, which Chrome can't authenticate against.
+    pub fn chrome_credentials(&self) -> Option<(&str, &str)> {
+        (!self.username.is_empty() && !self.server.starts_with("socks"))
+            .then_some((self.username.as_str(), self.password.as_str()))
+    }
+
+    /// Identifies the proxy settings, for caching clients built with them.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_proxy_settings() {
+        let settings = Settings {
+            proxy_url: "socks5h://proxy.example.com".to_string(),
+            proxy_username: "agency".to_string(),
+            proxy_password: "secret".to_string(),
+            proxy_no_proxy: vec!["localhost".to_string(), " .internal ".to_string()],
+            ..Settings::new()
+        };
+        let proxy = ProxyConfig::from_settings(&settings).unwrap().unwrap();
+
+        assert_eq!(proxy.server, "socks5://proxy.example.com:1080");
+        assert_eq!(
+            proxy.chrome_bypass_arg.as_deref(),
+            Some("--proxy-bypass-list=localhost;.internal")
+        );
+        assert_eq!(proxy.chrome_credentials(), None);
+
+        let unset = Settings {
+            proxy_url: String::new(),
+            ..Settings::new()
+        };
+        assert!(ProxyConfig::from_settings(&unset).unwrap().is_none());
+
+        let ftp = Settings {
+            proxy_url: "ftp://proxy.example.com".to_string(),
+            ..Settings::new()
+        };
+        assert!(ProxyConfig::from_settings(&ftp).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! Hard per-host request rate limit (`rate_limit_requests_per_second`, with
+//! `rate_limit_burst`), enforced with a token bucket for every host a crawl
+//! talks to. Page fetches, link checks and image size checks all wait on the
+//! same bucket per host, so together they never exceed the limit.
+//!
+//! The limiter is global: it's set up when a crawl starts and replaced whenever
+//! the settings change, which takes effect for the requests of a running crawl.
+
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use once_cell::sync::Lazy;
+use std::num::NonZeroU32;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use url::Url;
+
+use crate::settings::settings::Settings;
+
+struct ActiveLimit {
+    requests_per_second: f64,
+    burst: u32,
+    limiter: Arc<DefaultKeyedRateLimiter<String>>,
+}
+
+static HOST_LIMIT: Lazy<RwLock<Option<ActiveLimit>>> = Lazy::new(|| RwLock::new(None));
+
+/// Applies the rate limit settings. A rate of 0 (or less) turns the limit off.
+/// The buckets are kept when the limit hasn't changed.
+pub fn configure(settings: &Settings) {
+    let requests_per_second = settings.rate_limit_requests_per_second;
+    let burst = settings.rate_limit_burst.max(1);
+
+    let mut active = HOST_LIMIT.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(current) = active.as_ref() {
+        if current.requests_per_second == requests_per_second && current.burst == burst {
+            return;
+        }
+    }
+
+    *active = quota(requests_per_second, burst).map(|quota| ActiveLimit {
+        requests_per_second,
+        burst,
+        limiter: Arc::new(RateLimiter::keyed(quota)),
+    });
+    match active.as_ref() {
+        Some(_) => tracing::info!(
+            "Per-host rate limit: {} requests/second (burst {})",
+            requests_per_second,
+            burst
+        ),
+        None => tracing::info!("Per-host rate limit off"),
+    }
+}
+
+/// Waits until a request to `url`'s host fits within the rate limit. Returns
+/// straight away when no limit is set.
+pub async fn until_ready(url: &str) {
+    let limiter = {
+        let active = HOST_LIMIT.read().unwrap_or_else(|e| e.into_inner());
+        match active.as_ref() {
+            Some(active) => active.limiter.clone(),
+            None => return,
+        }
+    };
+    let Some(host) = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_ascii_lowercase()))
+    else {
+        return;
+    };
+    limiter.until_key_ready(&host).await;
+}
+
+/// Drops the buckets of hosts that haven't been requested for long enough to
+/// have refilled; the keyed limiter would otherwise keep every host a crawl ever
+/// touched (external links included). Called periodically during a crawl and
+/// when it ends.
+pub fn prune() {
+    let active = HOST_LIMIT.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(active) = active.as_ref() {
+        active.limiter.retain_recent();
+        active.limiter.shrink_to_fit();
+    }
+}
+
+/// One token every `1 / requests_per_second` seconds, so fractional rates
+/// (e.g. one request every two seconds) work too.
+fn quota(requests_per_second: f64, burst: u32) -> Option<Quota> {
+    if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+        return None;
+    }
+    let period = Duration::try_from_secs_f64(1.0 / requests_per_second).ok()?;
+    let quota = Quota::with_period(period)?;
+    Some(quota.allow_burst(NonZeroU32::new(burst).unwrap_or(NonZeroU32::MIN)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quota_from_settings() {
+        assert!(quota(0.0, 1).is_none());
+        assert!(quota(-2.0, 1).is_none());
+        assert!(quota(f64::NAN, 1).is_none());
+        assert!(quota(1e-300, 1).is_none());
+
+        let fast = quota(4.0, 2).unwrap();
+        assert_eq!(fast.replenish_interval(), Duration::from_millis(250));
+        assert_eq!(fast.burst_size().get(), 2);
+
+        let slow = quota(0.5, 1).unwrap();
+        assert_eq!(slow.replenish_interval(), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,251 @@
+//! Raw HTML vs rendered DOM comparison for `javascript_rendering` crawls. The
+//! SEO signals are read from both versions of the page and the differences are
+//! stored per URL, so pages whose indexability or links rely on JavaScript can
+//! be found.
+
+use once_cell::sync::Lazy;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+use url::Url;
+
+use super::canonical_selector::get_canonical;
+use super::headings_selector::headings_selector;
+use super::links_selector::extract_links;
+use super::meta_robots_selector::get_meta_robots;
+use super::page_description::extract_page_description;
+use super::word_count::get_word_count;
+
+/// Links listed per direction; the counts cover the rest.
+pub(crate) const MAX_LISTED_LINKS: usize = 100;
+
+static TITLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("title").unwrap());
+static JSON_LD_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("script[type=\"application/ld+json\"]").unwrap());
+static MICRODATA_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("[itemtype]").unwrap());
+
+/// The signals compared between the raw and rendered page.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageSignals {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub canonical: Option<String>,
+    pub meta_robots: Option<String>,
+    pub h1: Option<String>,
+    pub word_count: usize,
+    pub internal_links: HashSet<String>,
+    /// Schema types from JSON-LD and microdata, sorted
+    pub structured_data: Option<String>,
+}
+
+impl PageSignals {
+    /// `page_url` resolves relative links; links on `base_url`'s domain are internal.
+    pub fn extract(document: &Html, page_url: &Url, base_url: &Url) -> Self {
+        let non_empty = |s: String| {
+            let s = s.trim().to_string();
+            (!s.is_empty()).then_some(s)
+        };
+
+        PageSignals {
+            title: document
+                .select(&TITLE_SELECTOR)
+                .next()
+                .and_then(|t| non_empty(t.text().collect())),
+            description: extract_page_description(document).and_then(non_empty),
+            canonical: get_canonical(document)
+                .and_then(|c| c.canonicals.into_iter().next())
+                .and_then(non_empty),
+            meta_robots: get_meta_robots(document)
+                .map(|m| m.meta_robots.join(", "))
+                .and_then(non_empty),
+            h1: headings_selector(document)
+                .remove("h1")
+                .map(|h1| h1.join(" | "))
+                .and_then(non_empty),
+            word_count: get_word_count(document),
+            internal_links: extract_links(document, page_url, base_url)
+                .into_iter()
+                .map(|u| u.to_string())
+                .collect(),
+            structured_data: structured_data_types(document),
+        }
+    }
+}
+
+/// A signal whose value differs between the raw HTML and the rendered DOM.
+/// `None` means the signal is missing from that version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalChange {
+    pub field: String,
+    pub raw: Option<String>,
+    pub rendered: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenderDiff {
+    /// Title, meta description, canonical, meta robots, H1 and structured data
+    /// that differ
+    pub changes: Vec<SignalChange>,
+    pub raw_word_count: usize,
+    pub rendered_word_count: usize,
+    pub raw_internal_links: usize,
+    pub rendered_internal_links: usize,
+    /// Internal links that only exist once JavaScript has run
+    pub js_only_links: Vec<String>,
+    pub js_only_links_count: usize,
+    /// Internal links in the raw HTML that are gone from the rendered DOM
+    pub raw_only_links: Vec<String>,
+    pub raw_only_links_count: usize,
+    /// The canonical or meta robots only appear, or change, after JavaScript
+    pub indexability_depends_on_js: bool,
+    /// Some internal links only appear after JavaScript
+    pub links_depend_on_js: bool,
+}
+
+impl RenderDiff {
+    pub fn between(raw: &PageSignals, rendered: &PageSignals) -> Self {
+        let mut changes = Vec::new();
+        let mut compare = |field: &str, raw: &Option<String>, rendered: &Option<String>| {
+            if raw != rendered {
+                changes.push(SignalChange {
+                    field: field.to_string(),
+                    raw: raw.clone(),
+                    rendered: rendered.clone(),
+                });
+            }
+        };
+        compare("title", &raw.title, &rendered.title);
+        compare("description", &raw.description, &rendered.description);
+        compare("canonical", &raw.canonical, &rendered.canonical);
+        compare("meta_robots", &raw.meta_robots, &rendered.meta_robots);
+        compare("h1", &raw.h1, &rendered.h1);
+        compare(
+            "structured_data",
+            &raw.structured_data,
+            &rendered.structured_data,
+        );
+
+        let indexability_depends_on_js = changes
+            .iter()
+            .any(|c| c.field == "canonical" || c.field == "meta_robots");
+
+        let js_only = sorted_difference(&rendered.internal_links, &raw.internal_links);
+        let raw_only = sorted_difference(&raw.internal_links, &rendered.internal_links);
+        let js_only_count = js_only.len();
+
+        RenderDiff {
+            changes,
+            raw_word_count: raw.word_count,
+            rendered_word_count: rendered.word_count,
+            raw_internal_links: raw.internal_links.len(),
+            rendered_internal_links: rendered.internal_links.len(),
+            js_only_links_count: js_only_count,
+            js_only_links: js_only.into_iter().take(MAX_LISTED_LINKS).collect(),
+            raw_only_links_count: raw_only.len(),
+            raw_only_links: raw_only.into_iter().take(MAX_LISTED_LINKS).collect(),
+            indexability_depends_on_js,
+            links_depend_on_js: js_only_count > 0,
+        }
+    }
+}
+
+/// Entries of `a` missing from `b`, sorted.
+pub(crate) fn sorted_difference(a: &HashSet<String>, b: &HashSet<String>) -> Vec<String> {
+    let mut only: Vec<String> = a.difference(b).cloned().collect();
+    only.sort();
+    only
+}
+
+/// `@type`s of every JSON-LD block (including `@graph` entries) and microdata
+/// `itemtype`s, deduplicated and joined. `None` when the page has none.
+pub(crate) fn structured_data_types(document: &Html) -> Option<String> {
+    let mut types = BTreeSet::new();
+
+    for script in document.select(&JSON_LD_SELECTOR) {
+        let text: String = script.text().collect();
+        match serde_json::from_str::<Value>(&text) {
+            Ok(json) => collect_types(&json, &mut types),
+            // Still a difference worth reporting, even if it doesn't parse
+            Err(_) => {
+                types.insert("invalid JSON-LD".to_string());
+            }
+        }
+    }
+
+    for element in document.select(&MICRODATA_SELECTOR) {
+        if let Some(itemtype) = element.value().attr("itemtype") {
+            for t in itemtype.split_whitespace() {
+                let name = t.rsplit('/').next().unwrap_or(t);
+                if !name.is_empty() {
+                    types.insert(name.to_string());
+                }
+            }
+        }
+    }
+
+    (!types.is_empty()).then(|| types.into_iter().collect::<Vec<_>>().join(", "))
+}
+
+fn collect_types(json: &Value, types: &mut BTreeSet<String>) {
+    match json {
+        Value::Array(items) => items.iter().for_each(|item| collect_types(item, types)),
+        Value::Object(map) => {
+            match map.get("@type") {
+                Some(Value::String(t)) => {
+                    types.insert(t.clone());
+                }
+                Some(Value::Array(list)) => {
+                    types.extend(list.iter().filter_map(|t| t.as_str().map(String::from)));
+                }
+                _ => {}
+            }
+            if let Some(graph) = map.get("@graph") {
+                collect_types(graph, types);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_signals_added_by_javascript() {
+        let page = Url::parse("https://example.com/app").unwrap();
+        let raw = Html::parse_document(
+            r#"<html><head><title>Loading</title></head>
+               <body><div id="root"></div><a href="/about">About</a></body></html>"#,
+        );
+        let rendered = Html::parse_document(
+            r#"<html><head><title>Shoes</title>
+               <link rel="canonical" href="https://example.com/shoes">
+               <meta name="robots" content="noindex">
+               <script type="application/ld+json">{"@graph":[{"@type":"Product"},{"@type":"BreadcrumbList"}]}</script>
+               </head><body><h1>Shoes</h1><p>Red running shoes</p>
+               <a href="/about">About</a><a href="/shoes/red">Red</a></body></html>"#,
+        );
+
+        let diff = RenderDiff::between(
+            &PageSignals::extract(&raw, &page, &page),
+            &PageSignals::extract(&rendered, &page, &page),
+        );
+
+        let fields: Vec<&str> = diff.changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["title", "canonical", "meta_robots", "h1", "structured_data"]
+        );
+        assert_eq!(
+            diff.changes[4].rendered.as_deref(),
+            Some("BreadcrumbList, Product")
+        );
+        assert!(diff.indexability_depends_on_js);
+        assert!(diff.links_depend_on_js);
+        assert_eq!(diff.js_only_links, ["https://example.com/shoes/red"]);
+        assert!(diff.raw_only_links.is_empty());
+        assert_eq!((diff.raw_word_count, diff.rendered_word_count), (0, 4));
+    }
+}
@@ -0,0 +1,287 @@
+//! Resource mode (`Settings::crawl_resources`): the images, stylesheets, scripts,
+//! documents and archives that `should_skip_url` keeps out of the page queue are
+//! collected with the pages referencing them, then requested once each at the end
+//! of the crawl for their status, content type, size and cache headers.
+
+use futures::stream::{self, StreamExt};
+use reqwest::header::{
+    HeaderMap, HeaderName, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, EXPIRES,
+    LAST_MODIFIED,
+};
+use reqwest::{Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+use super::crawl_auth::{send_with_credentials, CrawlCredentials};
+use super::normalize_url::normalize_url;
+use super::rate_limit;
+
+/// Unique resources kept per crawl; references to resources past the cap are dropped.
+const MAX_RESOURCES: usize = 100_000;
+/// Referring pages listed per resource (all of them are counted).
+const MAX_REFERRERS: usize = 50;
+/// Bytes read when a resource has to be downloaded to learn its size.
+const MAX_BODY_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Image,
+    Css,
+    Javascript,
+    Font,
+    Pdf,
+    Archive,
+}
+
+impl ResourceKind {
+    /// The kind of resource a linked URL points to, from its file extension.
+    /// `None` for anything that isn't a known asset type.
+    pub fn from_url(url: &Url) -> Option<Self> {
+        let last_segment = url.path().rsplit('/').next().unwrap_or("");
+        let ext = last_segment.rsplit_once('.')?.1.to_lowercase();
+        match ext.as_str() {
+            "jpg" | "jpeg" | "png" | "gif" | "svg" | "ico" | "webp" | "avif" | "bmp" => {
+                Some(Self::Image)
+            }
+            "css" => Some(Self::Css),
+            "js" | "mjs" => Some(Self::Javascript),
+            "woff" | "woff2" | "ttf" | "otf" | "eot" => Some(Self::Font),
+            "pdf" => Some(Self::Pdf),
+            "zip" | "tar" | "gz" | "tgz" | "rar" | "7z" => Some(Self::Archive),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Css => "css",
+            Self::Javascript => "javascript",
+            Self::Font => "font",
+            Self::Pdf => "pdf",
+            Self::Archive => "archive",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceRecord {
+    pub url: String,
+    pub kind: ResourceKind,
+    pub status_code: Option<u16>,
+    pub content_type: Option<String>,
+    /// Bytes, from Content-Length or, when the server doesn't send it, the body
+    pub size: Option<u64>,
+    pub cache_control: Option<String>,
+    pub expires: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Why the resource couldn't be fetched
+    pub error: Option<String>,
+    /// Pages referencing the resource, up to `MAX_REFERRERS`
+    pub referrers: Vec<String>,
+    /// Distinct pages referencing the resource
+    pub referrer_count: usize,
+}
+
+/// Resources referenced by the crawled pages, keyed by normalized URL.
+#[derive(Default)]
+pub struct ResourceCollector {
+    resources: HashMap<String, ResourceRecord>,
+}
+
+impl ResourceCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the resources a crawled page references. Called once per page;
+    /// a resource the page references several times counts once.
+    pub fn record_page(&mut self, found_on: &str, references: &[(String, ResourceKind)]) {
+        let mut seen = HashSet::new();
+        for (url, kind) in references {
+            let key = normalize_url(url);
+            if seen.insert(key.clone()) {
+                self.record(key, url, *kind, found_on);
+            }
+        }
+    }
+
+    fn record(&mut self, key: String, url: &str, kind: ResourceKind, found_on: &str) {
+        if !self.resources.contains_key(&key) && self.resources.len() >= MAX_RESOURCES {
+            return;
+        }
+        let record = self.resources.entry(key).or_insert_with(|| ResourceRecord {
+            url: url.to_string(),
+            kind,
+            status_code: None,
+            content_type: None,
+            size: None,
+            cache_control: None,
+            expires: None,
+            etag: None,
+            last_modified: None,
+            error: None,
+            referrers: Vec::new(),
+            referrer_count: 0,
+        });
+        record.referrer_count += 1;
+        if record.referrers.len() < MAX_REFERRERS {
+            record.referrers.push(found_on.to_string());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Hands over the collected resources, not yet fetched.
+    pub fn take(&mut self) -> Vec<ResourceRecord> {
+        self.resources.drain().map(|(_, record)| record).collect()
+    }
+}
+
+/// Requests every resource once, `concurrency` at a time, with the crawl's
+/// credentials for resources on its own hosts.
+pub async fn fetch_all(
+    client: &Client,
+    records: Vec<ResourceRecord>,
+    concurrency: usize,
+    credentials: Option<&CrawlCredentials>,
+) -> Vec<ResourceRecord> {
+    stream::iter(records)
+        .map(|record| fetch_resource(client, record, credentials))
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// HEAD first; servers that refuse HEAD, or don't send a Content-Length, get a
+/// GET whose body is counted instead.
+async fn fetch_resource(
+    client: &Client,
+    mut record: ResourceRecord,
+    credentials: Option<&CrawlCredentials>,
+) -> ResourceRecord {
+    rate_limit::until_ready(&record.url).await;
+    let head = send_with_credentials(client, Method::HEAD, &record.url, credentials).await;
+    let needs_get = match &head {
+        Ok(response) => {
+            matches!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+            ) || (response.status().is_success() && content_length(response.headers()).is_none())
+        }
+        Err(_) => true,
+    };
+
+    if !needs_get {
+        if let Ok(response) = head {
+            apply_headers(&mut record, response.status(), response.headers());
+            record.size = content_length(response.headers());
+        }
+        return record;
+    }
+
+    rate_limit::until_ready(&record.url).await;
+    match send_with_credentials(client, Method::GET, &record.url, credentials).await {
+        Ok(mut response) => {
+            apply_headers(&mut record, response.status(), response.headers());
+            record.size = content_length(response.headers());
+            if record.size.is_none() {
+                let mut read = 0u64;
+                while let Ok(Some(chunk)) = response.chunk().await {
+                    read += chunk.len() as u64;
+                    if read >= MAX_BODY_BYTES {
+                        break;
+                    }
+                }
+                record.size = Some(read);
+            }
+        }
+        Err(e) => record.error = Some(e.to_string()),
+    }
+    record
+}
+
+fn apply_headers(record: &mut ResourceRecord, status: StatusCode, headers: &HeaderMap) {
+    let header = |name: HeaderName| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    record.status_code = Some(status.as_u16());
+    record.content_type = header(CONTENT_TYPE);
+    record.cache_control = header(CACHE_CONTROL);
+    record.expires = header(EXPIRES);
+    record.etag = header(ETAG);
+    record.last_modified = header(LAST_MODIFIED);
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_each_resource_once() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert_eq!(
+            ResourceKind::from_url(&url("https://example.com/a/hero.JPG?v=2")),
+            Some(ResourceKind::Image)
+        );
+        assert_eq!(
+            ResourceKind::from_url(&url("https://example.com/files/report.pdf")),
+            Some(ResourceKind::Pdf)
+        );
+        assert_eq!(
+            ResourceKind::from_url(&url("https://example.com/blog/")),
+            None
+        );
+        assert_eq!(
+            ResourceKind::from_url(&url("https://example.com/v1.2/page")),
+            None
+        );
+
+        let css = |s: &str| (s.to_string(), ResourceKind::Css);
+        let mut collector = ResourceCollector::new();
+        collector.record_page(
+            "https://example.com/",
+            &[
+                css("https://example.com/app.css"),
+                (
+                    "https://cdn.example.com/app.js".to_string(),
+                    ResourceKind::Javascript,
+                ),
+                css("https://example.com/app.css"),
+            ],
+        );
+        collector.record_page(
+            "https://example.com/about",
+            &[css("https://example.com/app.css#x")],
+        );
+
+        let mut records = collector.take();
+        records.sort_by(|a, b| a.url.cmp(&b.url));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].referrer_count, 2);
+        assert_eq!(
+            records[1].referrers,
+            vec!["https://example.com/", "https://example.com/about"]
+        );
+        assert!(collector.is_empty());
+    }
+}
@@ -1,50 +1,503 @@
+//! robots.txt fetching and RFC 9309 matching for the deep crawler.
+//!
+//! The file is parsed once per crawl into `RobotsRules` (every group, rule and
+//! `Sitemap:` line, with the line number each rule came from). A `RobotsMatcher`
+//! is then selected for the crawl's user agent and consulted before any URL is
+//! queued, so the crawl only fetches what that agent would be allowed to fetch:
+//!
+//! - Group selection: the group(s) whose `User-agent` token is the most specific
+//!   match for the crawl's user agent win; otherwise the `*` group(s) apply.
+//!   Matching groups with the same token are merged, as the RFC requires.
+//! - Rule precedence: the longest matching `Allow`/`Disallow` path wins, and
+//!   `Allow` wins a tie. `*` matches any run of characters and a trailing `$`
+//!   anchors the pattern to the end of the URL path.
+//! - `Crawl-delay` of the selected group is exposed so the crawler can slow down.
+
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use url::Url;
 
+/// RFC 9309 only requires parsing the first 500 KiB of a robots.txt file.
+const MAX_ROBOTS_SIZE: usize = 500 * 1024;
+
 pub struct RobotsData {
     pub raw_text: Vec<String>,
     pub blocked_urls: Vec<String>,
+    pub rules: RobotsRules,
 }
 
-pub async fn get_robots_data(base_url: &Url) -> Option<RobotsData> {
-    let client = Client::new();
-    let robots_url = base_url.join("robots.txt").ok()?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    Allow,
+    Disallow,
+}
 
-    let response = client.get(robots_url).send().await.ok()?;
-    if response.status() != 200 {
-        return None;
+#[derive(Debug, Clone)]
+pub struct RobotsRule {
+    pub kind: RuleKind,
+    /// Path pattern, percent-encoded the same way `Url` serialises paths.
+    pub pattern: String,
+    /// 1-based line number in robots.txt, for reporting.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RobotsGroup {
+    /// Lowercased product tokens from the group's `User-agent` lines.
+    pub user_agents: Vec<String>,
+    pub rules: Vec<RobotsRule>,
+    /// Crawl-delay in seconds, if the group declares one.
+    pub crawl_delay: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    pub groups: Vec<RobotsGroup>,
+    pub sitemaps: Vec<String>,
+    /// Set when robots.txt could not be fetched because of a server error.
+    /// RFC 9309 treats an unreachable robots.txt as a complete disallow.
+    pub unreachable: bool,
+}
+
+/// Why a URL was not crawled: the robots.txt rule that disallowed it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RobotsBlock {
+    /// 1-based robots.txt line of the matching rule (0 when robots.txt was unreachable)
+    pub line: usize,
+    /// The rule as written, e.g. `Disallow: /search`
+    pub rule: String,
+    /// User-agent token of the group the rule belongs to
+    pub user_agent: String,
+}
+
+/// Rules of the group(s) selected for one user agent.
+#[derive(Debug, Clone, Default)]
+pub struct RobotsMatcher {
+    rules: Vec<RobotsRule>,
+    user_agent: String,
+    crawl_delay: Option<f64>,
+    unreachable: bool,
+}
+
+impl RobotsRules {
+    pub fn parse(body: &str) -> Self {
+        let body = &body[..body.floor_char_boundary(MAX_ROBOTS_SIZE)];
+        let mut rules = RobotsRules::default();
+        let mut current: Option<RobotsGroup> = None;
+        // A User-agent line directly after another one extends the same group;
+        // after any rule it starts a new group.
+        let mut collecting_agents = false;
+
+        for (idx, raw_line) in body.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if !collecting_agents {
+                        if let Some(group) = current.take() {
+                            rules.groups.push(group);
+                        }
+                        current = Some(RobotsGroup::default());
+                        collecting_agents = true;
+                    }
+                    let token = value
+                        .split(|c: char| c.is_whitespace() || c == '/')
+                        .next()
+                        .unwrap_or("")
+                        .to_ascii_lowercase();
+                    if !token.is_empty() {
+                        if let Some(group) = current.as_mut() {
+                            group.user_agents.push(token);
+                        }
+                    }
+                }
+                "allow" | "disallow" => {
+                    collecting_agents = false;
+                    // Rules before the first User-agent line belong to no group.
+                    let Some(group) = current.as_mut() else {
+                        continue;
+                    };
+                    // An empty path matches nothing, so it never changes the outcome.
+                    if value.is_empty() {
+                        continue;
+                    }
+                    group.rules.push(RobotsRule {
+                        kind: if key == "allow" {
+                            RuleKind::Allow
+                        } else {
+                            RuleKind::Disallow
+                        },
+                        pattern: encode_pattern(value),
+                        line: idx + 1,
+                    });
+                }
+                "crawl-delay" => {
+                    collecting_agents = false;
+                    if let Some(group) = current.as_mut() {
+                        group.crawl_delay = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|d| d.is_finite() && *d >= 0.0);
+                    }
+                }
+                // Sitemap lines are global and do not end the current group.
+                "sitemap" if !value.is_empty() => {
+                    rules.sitemaps.push(value.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(group) = current.take() {
+            rules.groups.push(group);
+        }
+
+        rules
     }
 
-    let body = response.text().await.ok()?;
-    if body.is_empty() {
-        return None;
+    /// Rules used when robots.txt answered with a server error or couldn't be
+    /// fetched at all.
+    pub fn unreachable() -> Self {
+        RobotsRules {
+            unreachable: true,
+            ..Default::default()
+        }
     }
 
-    let mut blocked_urls = Vec::new();
-    let lines = body.lines();
+    /// Selects the group(s) that apply to `user_agent` (a product token such as
+    /// `Googlebot` or a full User-Agent header) and merges their rules.
+    pub fn matcher_for(&self, user_agent: &str) -> RobotsMatcher {
+        let products = product_tokens(user_agent);
 
-    for line in lines {
-        let line = line.trim();
-        if line.to_lowercase().starts_with("disallow:") {
-            let path = line["disallow:".len()..].trim();
-            if !path.is_empty() {
-                if let Ok(full_url) = base_url.join(path) {
-                    blocked_urls.push(full_url.to_string());
+        // Group tokens are compared case-insensitively for equality with the
+        // crawler's product tokens (RFC 9309 2.2.1); the longest match wins.
+        let best_token = self
+            .groups
+            .iter()
+            .flat_map(|g| g.user_agents.iter())
+            .filter(|token| token.as_str() != "*" && products.contains(*token))
+            .max_by_key(|token| token.len())
+            .cloned()
+            .unwrap_or_else(|| "*".to_string());
+
+        let mut matcher = RobotsMatcher {
+            user_agent: best_token.clone(),
+            unreachable: self.unreachable,
+            ..Default::default()
+        };
+
+        for group in self
+            .groups
+            .iter()
+            .filter(|g| g.user_agents.contains(&best_token))
+        {
+            matcher.rules.extend(group.rules.iter().cloned());
+            if let Some(delay) = group.crawl_delay {
+                matcher.crawl_delay = Some(matcher.crawl_delay.map_or(delay, |d| d.max(delay)));
+            }
+        }
+
+        matcher
+    }
+
+    /// Every Disallow path in the file resolved against `base_url`, regardless
+    /// of group. Kept for the sidebar's "blocked by robots" overview.
+    pub fn disallowed_urls(&self, base_url: &Url) -> Vec<String> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.rules.iter())
+            .filter(|r| r.kind == RuleKind::Disallow)
+            .filter_map(|r| base_url.join(&r.pattern).ok())
+            .map(|u| u.to_string())
+            .collect()
+    }
+}
+
+impl RobotsMatcher {
+    /// A matcher that allows everything (no robots.txt, or robots.txt ignored).
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Returns the rule that blocks `url`, or `None` when the URL may be crawled.
+    pub fn check(&self, url: &Url) -> Option<RobotsBlock> {
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+
+        // The robots.txt file itself is always allowed.
+        if path == "/robots.txt" {
+            return None;
+        }
+
+        if self.unreachable {
+            return Some(RobotsBlock {
+                line: 0,
+                rule: "robots.txt unreachable (server error)".to_string(),
+                user_agent: self.user_agent.clone(),
+            });
+        }
+
+        let mut best: Option<&RobotsRule> = None;
+        for rule in &self.rules {
+            if !pattern_matches(&rule.pattern, &path) {
+                continue;
+            }
+            best = match best {
+                None => Some(rule),
+                Some(current) => {
+                    let longer = rule.pattern.len() > current.pattern.len();
+                    let tie_allow = rule.pattern.len() == current.pattern.len()
+                        && rule.kind == RuleKind::Allow;
+                    if longer || tie_allow {
+                        Some(rule)
+                    } else {
+                        Some(current)
+                    }
                 }
+            };
+        }
+
+        match best {
+            Some(rule) if rule.kind == RuleKind::Disallow => Some(RobotsBlock {
+                line: rule.line,
+                rule: format!("Disallow: {}", rule.pattern),
+                user_agent: self.user_agent.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Crawl-delay of the selected group(s).
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay.map(Duration::from_secs_f64)
+    }
+}
+
+/// Percent-encodes non-ASCII characters and uppercases existing escapes, so
+/// patterns compare byte-for-byte with `Url::path()` output.
+fn encode_pattern(pattern: &str) -> String {
+    let mut encoded = String::with_capacity(pattern.len());
+    let bytes = pattern.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            encoded.push('%');
+            encoded.push(bytes[i + 1].to_ascii_uppercase() as char);
+            encoded.push(bytes[i + 2].to_ascii_uppercase() as char);
+            i += 3;
+        } else if b.is_ascii() {
+            encoded.push(b as char);
+            i += 1;
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+            i += 1;
+        }
+    }
+    encoded
+}
+
+/// Matches a robots.txt path pattern against a URL path (plus query).
+/// Patterns match as prefixes unless they end in `$`; `*` matches any sequence.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let p = pattern.as_bytes();
+    let s = path.as_bytes();
+
+    let (mut pi, mut si) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while si < s.len() {
+        if pi < p.len() && p[pi] == b'*' {
+            star = Some((pi, si));
+            pi += 1;
+        } else if pi < p.len() && p[pi] == s[si] {
+            pi += 1;
+            si += 1;
+        } else if !anchored && pi == p.len() {
+            // Whole pattern consumed: an unanchored pattern is a prefix match.
+            return true;
+        } else if let Some((star_pi, star_si)) = star {
+            pi = star_pi + 1;
+            si = star_si + 1;
+            star = Some((star_pi, star_si + 1));
+        } else {
+            return false;
+        }
+    }
+
+    while pi < p.len() && p[pi] == b'*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+/// Lowercased product tokens of a user agent: the agent itself when it is a
+/// bare token (`Googlebot`), otherwise every `name/version` product of a
+/// User-Agent header (`Mozilla/5.0 (compatible; Googlebot/2.1)` gives
+/// `mozilla` and `googlebot`).
+fn product_tokens(user_agent: &str) -> Vec<String> {
+    let is_token_char = |c: char| c.is_ascii_alphabetic() || c == '_' || c == '-';
+    let user_agent = user_agent.trim();
+    if !user_agent.is_empty() && user_agent.chars().all(is_token_char) {
+        return vec![user_agent.to_ascii_lowercase()];
+    }
+
+    let mut tokens = Vec::new();
+    for (slash, _) in user_agent.match_indices('/') {
+        let has_version = user_agent[slash + 1..].starts_with(|c: char| c.is_ascii_digit());
+        let start = user_agent[..slash]
+            .rfind(|c: char| !is_token_char(c))
+            .map_or(0, |i| i + 1);
+        if has_version && start < slash {
+            tokens.push(user_agent[start..slash].to_ascii_lowercase());
+        }
+    }
+    tokens
+}
+
+/// Redirects followed when fetching robots.txt (RFC 9309 asks for at least five).
+const MAX_ROBOTS_REDIRECTS: usize = 5;
+
+/// Fetches and parses robots.txt with the crawl's client, so the request goes
+/// out with the crawl identity's User-Agent and headers and through its proxy.
+/// The crawl client doesn't follow redirects, so they are followed here.
+///
+/// A server error or a failed request (a proxy refusing the connection, say)
+/// is treated as unreachable: everything is disallowed rather than the rules
+/// being silently ignored (RFC 9309 2.3.1.4).
+pub async fn get_robots_data(base_url: &Url, client: &Client) -> Option<RobotsData> {
+    let mut robots_url = base_url.join("/robots.txt").ok()?;
+
+    let mut redirects = 0;
+    let response = loop {
+        let response = match client.get(robots_url.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("Failed to fetch {}: {}", robots_url, e);
+                return Some(unreachable_robots(format!("robots.txt request failed: {}", e)));
             }
+        };
+        if !response.status().is_redirection() || redirects == MAX_ROBOTS_REDIRECTS {
+            break response;
         }
+        redirects += 1;
+        robots_url = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|location| robots_url.join(location).ok())?;
+    };
+    let status = response.status();
+
+    if status.is_server_error() {
+        return Some(unreachable_robots(format!("robots.txt returned {}", status)));
+    }
+
+    // 4xx (and anything else unexpected) means there are no restrictions.
+    if status != 200 {
+        return None;
+    }
+
+    let body = response.text().await.ok()?;
+    if body.is_empty() {
+        return None;
     }
 
+    let rules = RobotsRules::parse(&body);
+    let blocked_urls = rules.disallowed_urls(base_url);
+
     Some(RobotsData {
         raw_text: vec![body],
         blocked_urls,
+        rules,
     })
 }
 
-pub async fn get_domain_robots(base_url: &Url) -> Option<Vec<String>> {
-    get_robots_data(base_url).await.map(|d| d.raw_text)
+fn unreachable_robots(reason: String) -> RobotsData {
+    RobotsData {
+        raw_text: vec![reason],
+        blocked_urls: Vec::new(),
+        rules: RobotsRules::unreachable(),
+    }
 }
 
-pub async fn get_urls_from_robots(base_url: &Url) -> Option<Vec<String>> {
-    get_robots_data(base_url).await.map(|d| d.blocked_urls)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocked(robots: &str, agent: &str, url: &str) -> Option<RobotsBlock> {
+        RobotsRules::parse(robots)
+            .matcher_for(agent)
+            .check(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn longest_match_wins_and_allow_wins_ties() {
+        let robots = "User-agent: *\nDisallow: /shop\nAllow: /shop/public\nAllow: /page\nDisallow: /page\n";
+        assert!(blocked(robots, "RustySEO", "https://a.com/shop/cart").is_some());
+        assert!(blocked(robots, "RustySEO", "https://a.com/shop/public/x").is_none());
+        assert!(blocked(robots, "RustySEO", "https://a.com/page").is_none());
+    }
+
+    #[test]
+    fn wildcards_and_end_anchor() {
+        let robots = "User-agent: *\nDisallow: /*.pdf$\nDisallow: /*?sort=\n";
+        assert!(blocked(robots, "x", "https://a.com/files/doc.pdf").is_some());
+        assert!(blocked(robots, "x", "https://a.com/files/doc.pdf?v=1").is_none());
+        assert!(blocked(robots, "x", "https://a.com/list?sort=asc").is_some());
+        assert!(blocked(robots, "x", "https://a.com/list").is_none());
+    }
+
+    #[test]
+    fn specific_group_overrides_wildcard_group() {
+        let robots = "User-agent: *\nDisallow: /\n\nUser-agent: Googlebot\nUser-agent: Bingbot\nDisallow: /private\n";
+        let ua = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        assert!(blocked(robots, ua, "https://a.com/blog").is_none());
+        let block = blocked(robots, ua, "https://a.com/private/x").unwrap();
+        assert_eq!(block.line, 6);
+        assert_eq!(block.user_agent, "googlebot");
+        assert!(blocked(robots, "SomeOtherBot", "https://a.com/blog").is_some());
+
+        // Tokens must equal a product token, not appear somewhere in the UA
+        let loose = "User-agent: bot\nUser-agent: a\nDisallow: /\n";
+        assert!(blocked(loose, ua, "https://a.com/blog").is_none());
+        assert!(blocked(loose, "MyAuditBot/1.0", "https://a.com/blog").is_none());
+        assert!(blocked(loose, "Bot", "https://a.com/blog").is_some());
+    }
+
+    #[test]
+    fn crawl_delay_and_sitemaps_are_parsed() {
+        let rules = RobotsRules::parse(
+            "Sitemap: https://a.com/sitemap.xml\nUser-agent: *\nCrawl-delay: 2.5\nDisallow:\n",
+        );
+        assert_eq!(rules.sitemaps, vec!["https://a.com/sitemap.xml".to_string()]);
+        assert_eq!(
+            rules.matcher_for("x").crawl_delay(),
+            Some(Duration::from_millis(2500))
+        );
+        assert!(rules.matcher_for("x").check(&Url::parse("https://a.com/any").unwrap()).is_none());
+    }
+
+    #[test]
+    fn unreachable_robots_disallows_everything_but_itself() {
+        let matcher = RobotsRules::unreachable().matcher_for("x");
+        assert!(matcher.check(&Url::parse("https://a.com/").unwrap()).is_some());
+        assert!(matcher.check(&Url::parse("https://a.com/robots.txt").unwrap()).is_none());
+    }
 }
@@ -0,0 +1,463 @@
+//! Grades a page's security response headers: Content-Security-Policy,
+//! Strict-Transport-Security, X-Content-Type-Options, framing protection
+//! (X-Frame-Options or CSP `frame-ancestors`), Referrer-Policy and
+//! Permissions-Policy. Complements `cross_origin`, which only looks at the HTML.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// HSTS max-age below which the policy is reported as weak (one year).
+pub const MIN_HSTS_MAX_AGE: u64 = 31_536_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityHeader {
+    ContentSecurityPolicy,
+    StrictTransportSecurity,
+    XContentTypeOptions,
+    /// X-Frame-Options, or `frame-ancestors` in an enforced CSP
+    FrameOptions,
+    ReferrerPolicy,
+    PermissionsPolicy,
+}
+
+impl SecurityHeader {
+    const ALL: [SecurityHeader; 6] = [
+        Self::ContentSecurityPolicy,
+        Self::StrictTransportSecurity,
+        Self::XContentTypeOptions,
+        Self::FrameOptions,
+        Self::ReferrerPolicy,
+        Self::PermissionsPolicy,
+    ];
+
+    /// Points out of 100; a weak header earns half.
+    fn weight(&self) -> u32 {
+        match self {
+            Self::ContentSecurityPolicy => 25,
+            Self::StrictTransportSecurity => 20,
+            Self::XContentTypeOptions | Self::FrameOptions | Self::ReferrerPolicy => 15,
+            Self::PermissionsPolicy => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    Missing,
+    Weak,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderFinding {
+    pub header: SecurityHeader,
+    pub severity: FindingSeverity,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityHeadersReport {
+    /// 0-100; HSTS only counts on HTTPS pages
+    pub score: u8,
+    /// A (90+), B (75+), C (60+), D (40+) or F
+    pub grade: String,
+    pub findings: Vec<HeaderFinding>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HeaderCounts {
+    pub missing: usize,
+    pub weak: usize,
+}
+
+/// Site-level roll-up of the per-page reports.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurityHeadersSummary {
+    pub pages: usize,
+    pub average_score: f64,
+    /// Pages per grade
+    pub grades: HashMap<String, usize>,
+    /// Pages where each header is missing or weak
+    pub headers: HashMap<SecurityHeader, HeaderCounts>,
+}
+
+impl SecurityHeadersSummary {
+    /// An empty roll-up for `pages` pages, listing every header (at zero) so
+    /// headers no page gets wrong still show up.
+    pub fn new(pages: usize, average_score: f64) -> Self {
+        let headers = if pages > 0 {
+            SecurityHeader::ALL
+                .into_iter()
+                .map(|header| (header, HeaderCounts::default()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        SecurityHeadersSummary {
+            pages,
+            average_score,
+            grades: HashMap::new(),
+            headers,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hsts {
+    pub max_age: Option<u64>,
+    pub include_subdomains: bool,
+    pub preload: bool,
+}
+
+/// Parses a Strict-Transport-Security value.
+pub fn parse_hsts(value: &str) -> Hsts {
+    let mut hsts = Hsts::default();
+    for directive in value.split(';').map(str::trim) {
+        let lower = directive.to_ascii_lowercase();
+        if let Some(age) = lower.strip_prefix("max-age=") {
+            hsts.max_age = age.trim_matches('"').parse().ok();
+        } else if lower == "includesubdomains" {
+            hsts.include_subdomains = true;
+        } else if lower == "preload" {
+            hsts.preload = true;
+        }
+    }
+    hsts
+}
+
+/// Grades the response headers of one page.
+pub fn grade(headers: &[(String, String)], https: bool) -> SecurityHeadersReport {
+    let values = |name: &str| -> Vec<&str> {
+        headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    };
+
+    let mut findings = Vec::new();
+    let mut finding = |header, severity, detail: &str| {
+        findings.push(HeaderFinding {
+            header,
+            severity,
+            detail: detail.to_string(),
+        })
+    };
+
+    // Every enforced policy applies, so their directives are read together.
+    let csp: Vec<(String, Vec<String>)> = values("content-security-policy")
+        .into_iter()
+        .flat_map(csp_directives)
+        .collect();
+    let directive = |name: &str| csp.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+
+    if csp.is_empty() {
+        if values("content-security-policy-report-only").is_empty() {
+            finding(
+                SecurityHeader::ContentSecurityPolicy,
+                FindingSeverity::Missing,
+                "No Content-Security-Policy",
+            );
+        } else {
+            finding(
+                SecurityHeader::ContentSecurityPolicy,
+                FindingSeverity::Weak,
+                "Only Content-Security-Policy-Report-Only is sent; nothing is enforced",
+            );
+        }
+    } else {
+        match directive("script-src").or_else(|| directive("default-src")) {
+            None => finding(
+                SecurityHeader::ContentSecurityPolicy,
+                FindingSeverity::Weak,
+                "No script-src or default-src; scripts are unrestricted",
+            ),
+            Some(sources) => {
+                let has = |s: &str| sources.iter().any(|v| v == s);
+                let nonce_or_hash = sources.iter().any(|v| {
+                    v.starts_with("'nonce-") || v.starts_with("'sha") || v == "'strict-dynamic'"
+                });
+                if has("'unsafe-inline'") && !nonce_or_hash {
+                    finding(
+                        SecurityHeader::ContentSecurityPolicy,
+                        FindingSeverity::Weak,
+                        "'unsafe-inline' allows inline scripts",
+                    );
+                }
+                if has("'unsafe-eval'") {
+                    finding(
+                        SecurityHeader::ContentSecurityPolicy,
+                        FindingSeverity::Weak,
+                        "'unsafe-eval' allows eval()",
+                    );
+                }
+                if ["*", "http:", "https:", "data:"].iter().any(|s| has(s)) {
+                    finding(
+                        SecurityHeader::ContentSecurityPolicy,
+                        FindingSeverity::Weak,
+                        "Scripts may load from any host",
+                    );
+                }
+            }
+        }
+    }
+
+    if https {
+        match values("strict-transport-security").first() {
+            None => finding(
+                SecurityHeader::StrictTransportSecurity,
+                FindingSeverity::Missing,
+                "No Strict-Transport-Security",
+            ),
+            Some(value) => match parse_hsts(value).max_age {
+                None => finding(
+                    SecurityHeader::StrictTransportSecurity,
+                    FindingSeverity::Weak,
+                    "No valid max-age",
+                ),
+                Some(0) => finding(
+                    SecurityHeader::StrictTransportSecurity,
+                    FindingSeverity::Weak,
+                    "max-age=0 turns HSTS off",
+                ),
+                Some(age) if age < MIN_HSTS_MAX_AGE => finding(
+                    SecurityHeader::StrictTransportSecurity,
+                    FindingSeverity::Weak,
+                    "max-age is shorter than one year",
+                ),
+                Some(_) => {}
+            },
+        }
+    }
+
+    match values("x-content-type-options").first() {
+        None => finding(
+            SecurityHeader::XContentTypeOptions,
+            FindingSeverity::Missing,
+            "No X-Content-Type-Options",
+        ),
+        Some(value) if !value.trim().eq_ignore_ascii_case("nosniff") => finding(
+            SecurityHeader::XContentTypeOptions,
+            FindingSeverity::Weak,
+            "Value is not nosniff",
+        ),
+        Some(_) => {}
+    }
+
+    // An enforced frame-ancestors supersedes X-Frame-Options.
+    match directive("frame-ancestors") {
+        Some(sources) if sources.iter().any(|s| s == "*") => finding(
+            SecurityHeader::FrameOptions,
+            FindingSeverity::Weak,
+            "frame-ancestors allows any site to frame the page",
+        ),
+        Some(_) => {}
+        None => match values("x-frame-options")
+            .first()
+            .map(|v| v.trim().to_ascii_uppercase())
+        {
+            None => finding(
+                SecurityHeader::FrameOptions,
+                FindingSeverity::Missing,
+                "No X-Frame-Options or CSP frame-ancestors",
+            ),
+            Some(value) if value == "DENY" || value == "SAMEORIGIN" => {}
+            Some(value) if value.starts_with("ALLOW-FROM") => finding(
+                SecurityHeader::FrameOptions,
+                FindingSeverity::Weak,
+                "ALLOW-FROM is ignored by current browsers",
+            ),
+            Some(_) => finding(
+                SecurityHeader::FrameOptions,
+                FindingSeverity::Weak,
+                "X-Frame-Options value is not DENY or SAMEORIGIN",
+            ),
+        },
+    }
+
+    // Browsers use the last policy they recognise.
+    let referrer = values("referrer-policy")
+        .into_iter()
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim().to_ascii_lowercase())
+        .rfind(|v| {
+            matches!(
+                v.as_str(),
+                "no-referrer"
+                    | "no-referrer-when-downgrade"
+                    | "origin"
+                    | "origin-when-cross-origin"
+                    | "same-origin"
+                    | "strict-origin"
+                    | "strict-origin-when-cross-origin"
+                    | "unsafe-url"
+            )
+        });
+    match referrer.as_deref() {
+        None if values("referrer-policy").is_empty() => finding(
+            SecurityHeader::ReferrerPolicy,
+            FindingSeverity::Missing,
+            "No Referrer-Policy",
+        ),
+        None => finding(
+            SecurityHeader::ReferrerPolicy,
+            FindingSeverity::Weak,
+            "No recognised policy value",
+        ),
+        Some("unsafe-url") | Some("no-referrer-when-downgrade") => finding(
+            SecurityHeader::ReferrerPolicy,
+            FindingSeverity::Weak,
+            "Full URLs are sent to other sites",
+        ),
+        Some(_) => {}
+    }
+
+    if values("permissions-policy")
+        .iter()
+        .all(|v| v.trim().is_empty())
+    {
+        if values("feature-policy").is_empty() {
+            finding(
+                SecurityHeader::PermissionsPolicy,
+                FindingSeverity::Missing,
+                "No Permissions-Policy",
+            );
+        } else {
+            finding(
+                SecurityHeader::PermissionsPolicy,
+                FindingSeverity::Weak,
+                "Only the deprecated Feature-Policy is sent",
+            );
+        }
+    }
+
+    let (earned, possible) = SecurityHeader::ALL
+        .iter()
+        .filter(|h| https || **h != SecurityHeader::StrictTransportSecurity)
+        .fold((0, 0), |(earned, possible), header| {
+            let points = match worst(&findings, *header) {
+                None => header.weight(),
+                Some(FindingSeverity::Weak) => header.weight() / 2,
+                Some(FindingSeverity::Missing) => 0,
+            };
+            (earned + points, possible + header.weight())
+        });
+    let score = (earned * 100 / possible.max(1)) as u8;
+
+    SecurityHeadersReport {
+        score,
+        grade: letter_grade(score).to_string(),
+        findings,
+    }
+}
+
+/// Missing beats weak when a header has several findings.
+fn worst(findings: &[HeaderFinding], header: SecurityHeader) -> Option<FindingSeverity> {
+    findings
+        .iter()
+        .filter(|f| f.header == header)
+        .map(|f| f.severity)
+        .max_by_key(|s| *s == FindingSeverity::Missing)
+}
+
+fn letter_grade(score: u8) -> &'static str {
+    match score {
+        90.. => "A",
+        75..=89 => "B",
+        60..=74 => "C",
+        40..=59 => "D",
+        _ => "F",
+    }
+}
+
+/// `(directive, sources)` pairs of one CSP header value, lowercased.
+fn csp_directives(value: &str) -> Vec<(String, Vec<String>)> {
+    value
+        .split(';')
+        .filter_map(|directive| {
+            let mut parts = directive.split_whitespace();
+            let name = parts.next()?.to_ascii_lowercase();
+            Some((name, parts.map(str::to_ascii_lowercase).collect()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn grades_missing_and_weak_headers() {
+        let strong = grade(
+            &headers(&[
+                (
+                    "content-security-policy",
+                    "default-src 'self'; frame-ancestors 'none'",
+                ),
+                (
+                    "strict-transport-security",
+                    "max-age=63072000; includeSubDomains",
+                ),
+                ("x-content-type-options", "nosniff"),
+                (
+                    "referrer-policy",
+                    "no-referrer, strict-origin-when-cross-origin",
+                ),
+                ("permissions-policy", "camera=()"),
+            ]),
+            true,
+        );
+        assert_eq!(strong.score, 100);
+        assert_eq!(strong.grade, "A");
+        assert!(strong.findings.is_empty());
+
+        let weak = grade(
+            &headers(&[
+                (
+                    "Content-Security-Policy",
+                    "script-src 'self' 'unsafe-inline'",
+                ),
+                ("Strict-Transport-Security", "max-age=86400"),
+                ("X-Frame-Options", "ALLOW-FROM https://example.com"),
+                ("Referrer-Policy", "unsafe-url"),
+            ]),
+            true,
+        );
+        // CSP, HSTS, framing and Referrer-Policy weak; XCTO and Permissions-Policy missing
+        assert_eq!(weak.score, 36);
+        assert_eq!(weak.grade, "F");
+        let missing: Vec<_> = weak
+            .findings
+            .iter()
+            .filter(|f| f.severity == FindingSeverity::Missing)
+            .map(|f| f.header)
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                SecurityHeader::XContentTypeOptions,
+                SecurityHeader::PermissionsPolicy
+            ]
+        );
+
+        // HSTS isn't expected over plain HTTP
+        let http = grade(&headers(&[("x-content-type-options", "nosniff")]), false);
+        assert!(http
+            .findings
+            .iter()
+            .all(|f| f.header != SecurityHeader::StrictTransportSecurity));
+        assert_eq!(http.score, 18);
+
+        let summary = SecurityHeadersSummary::new(3, 48.0);
+        assert_eq!(summary.headers.len(), SecurityHeader::ALL.len());
+        assert!(SecurityHeadersSummary::new(0, 0.0).headers.is_empty());
+    }
+}
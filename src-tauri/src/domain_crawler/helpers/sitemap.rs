@@ -5,21 +5,13 @@ use scraper::{Html, Selector};
 use crate::domain_crawler::helpers::robots::get_robots_data;
 
 pub async fn extract_urls_from_sitemaps(base_url: &Url, client: &Client) -> HashSet<String> {
-    let mut discovered_urls = HashSet::new();
     let mut sitemap_queue = HashSet::new();
-    let mut processed_sitemaps = HashSet::new();
 
     // 1. Try to find sitemaps in robots.txt
-    if let Some(robots_data) = get_robots_data(base_url).await {
-        for text in &robots_data.raw_text {
-            for line in text.lines() {
-                let line = line.trim();
-                if line.to_lowercase().starts_with("sitemap:") {
-                    let sitemap_url = line["sitemap:".len()..].trim();
-                    if let Ok(url) = Url::parse(sitemap_url) {
-                        sitemap_queue.insert(url);
-                    }
-                }
+    if let Some(robots_data) = get_robots_data(base_url, client).await {
+        for sitemap_url in &robots_data.rules.sitemaps {
+            if let Ok(url) = Url::parse(sitemap_url) {
+                sitemap_queue.insert(url);
             }
         }
     }
@@ -32,9 +24,20 @@ pub async fn extract_urls_from_sitemaps(base_url: &Url, client: &Client) -> Hash
         }
     }
 
-    let mut queue_vec: Vec<Url> = sitemap_queue.into_iter().collect();
-
     // 3. Process sitemaps recursively (to handle sitemap indexes)
+    collect_sitemap_urls(sitemap_queue.into_iter().collect(), client).await
+}
+
+/// Collects page URLs from a single sitemap (or sitemap index, followed
+/// recursively). Used by list mode, where the user supplies the sitemap directly.
+pub async fn extract_urls_from_sitemap(sitemap_url: &Url, client: &Client) -> HashSet<String> {
+    collect_sitemap_urls(vec![sitemap_url.clone()], client).await
+}
+
+async fn collect_sitemap_urls(mut queue_vec: Vec<Url>, client: &Client) -> HashSet<String> {
+    let mut discovered_urls = HashSet::new();
+    let mut processed_sitemaps = HashSet::new();
+
     while let Some(current_sitemap) = queue_vec.pop() {
         let sitemap_str = current_sitemap.to_string();
         if processed_sitemaps.contains(&sitemap_str) {
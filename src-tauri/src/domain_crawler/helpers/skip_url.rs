@@ -1,3 +1,10 @@
+/// Whether `url` points to a PDF (by its path), which `crawl_pdfs` lets through
+/// `should_skip_url`.
+pub fn is_pdf_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.to_lowercase().ends_with(".pdf")
+}
+
 pub fn should_skip_url(url: &str) -> bool {
     // Skip fragments
     if url.contains('#') {
@@ -0,0 +1,507 @@
+//! Spider trap and faceted-navigation detection. Every discovered URL is
+//! classified into trap signatures (calendar loops, ever-growing paths,
+//! repeating path segments, parameter permutations and session IDs); URLs that
+//! share a signature are counted together with a few examples and the pages
+//! linking into them. With `spider_trap_auto_stop` on, a signature is no longer
+//! queued once `spider_trap_threshold` URLs matched it, and that decision is
+//! logged.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+/// Examples and linking pages kept per trap.
+const MAX_EXAMPLES: usize = 5;
+const MAX_LINKED_FROM: usize = 10;
+
+/// Maximum number of signatures tracked; new ones past this are ignored.
+const MAX_TRAPS: usize = 20_000;
+
+/// Paths with at least this many segments count as ever-growing.
+const GROWING_PATH_SEGMENTS: usize = 10;
+
+const SESSION_PARAMS: &[&str] = &[
+    "sid",
+    "sessionid",
+    "session_id",
+    "session",
+    "phpsessid",
+    "jsessionid",
+    "aspsessionid",
+    "sess",
+    "cfid",
+    "cftoken",
+    "zenid",
+    "oscsid",
+];
+
+const DATE_PARAMS: &[&str] = &["date", "day", "month", "year", "week", "cal", "calendar"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrapKind {
+    Calendar,
+    GrowingPath,
+    RepeatingSegments,
+    ParameterPermutations,
+    SessionId,
+    /// URLs that only differ by long numeric IDs, stopped by the crawler's
+    /// built-in cap of 500 per pattern
+    SimilarUrls,
+}
+
+impl TrapKind {
+    /// URLs needed before a signature is reported. A single session ID or
+    /// repeating path is already a problem; dates and parameters only are in bulk.
+    fn min_count(self) -> usize {
+        match self {
+            TrapKind::Calendar => 24,
+            TrapKind::ParameterPermutations => 50,
+            TrapKind::GrowingPath
+            | TrapKind::RepeatingSegments
+            | TrapKind::SessionId
+            | TrapKind::SimilarUrls => 1,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrapKind::Calendar => "calendar",
+            TrapKind::GrowingPath => "growing_path",
+            TrapKind::RepeatingSegments => "repeating_segments",
+            TrapKind::ParameterPermutations => "parameter_permutations",
+            TrapKind::SessionId => "session_id",
+            TrapKind::SimilarUrls => "similar_urls",
+        }
+    }
+}
+
+/// A trap a URL may belong to: URLs with the same kind and pattern are counted
+/// together.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrapSignature {
+    pub kind: TrapKind,
+    pub pattern: String,
+}
+
+/// Why and when a trap stopped being crawled.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrapStop {
+    pub at: String,
+    pub reason: String,
+    /// The first URL that was not queued, and the page linking to it
+    pub url: String,
+    pub found_on: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspectedTrap {
+    pub kind: TrapKind,
+    pub pattern: String,
+    /// URLs queued for this pattern
+    pub count: usize,
+    pub examples: Vec<String>,
+    /// Pages linking into the trap
+    pub linked_from: Vec<String>,
+    /// Links not queued once the pattern was stopped
+    pub skipped_links: usize,
+    pub stopped: Option<TrapStop>,
+}
+
+#[derive(Default)]
+struct TrapEntry {
+    count: usize,
+    examples: Vec<String>,
+    linked_from: Vec<String>,
+    skipped_links: usize,
+    stopped: Option<TrapStop>,
+}
+
+impl TrapEntry {
+    fn note(&mut self, url: &str, found_on: &str) {
+        if self.examples.len() < MAX_EXAMPLES && !self.examples.iter().any(|e| e == url) {
+            self.examples.push(url.to_string());
+        }
+        if self.linked_from.len() < MAX_LINKED_FROM
+            && !self.linked_from.iter().any(|l| l == found_on)
+        {
+            self.linked_from.push(found_on.to_string());
+        }
+    }
+}
+
+pub struct TrapDetector {
+    auto_stop: bool,
+    threshold: usize,
+    entries: HashMap<TrapSignature, TrapEntry>,
+}
+
+impl TrapDetector {
+    pub fn new(auto_stop: bool, threshold: usize) -> Self {
+        TrapDetector {
+            auto_stop,
+            threshold: threshold.max(1),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns `false` when auto-stop has stopped one of `signatures`. The first
+    /// refusal of each pattern is logged and recorded as its stop decision.
+    pub fn allows(&mut self, signatures: &[TrapSignature], url: &str, found_on: &str) -> bool {
+        if !self.auto_stop {
+            return true;
+        }
+        let mut allowed = true;
+        for signature in signatures {
+            let Some(entry) = self.entries.get_mut(signature) else {
+                continue;
+            };
+            if entry.count < self.threshold {
+                continue;
+            }
+            allowed = false;
+            entry.skipped_links += 1;
+            entry.note(url, found_on);
+            if entry.stopped.is_none() {
+                let reason = format!(
+                    "Auto-stop: {} URLs matched (threshold {})",
+                    entry.count, self.threshold
+                );
+                tracing::warn!(
+                    "Spider trap stopped ({}): {}. {}, next was {} linked from {}",
+                    signature.kind.as_str(),
+                    signature.pattern,
+                    reason,
+                    url,
+                    found_on
+                );
+                entry.stopped = Some(stop(reason, url, found_on));
+            }
+        }
+        allowed
+    }
+
+    /// Counts a queued URL against its signatures.
+    pub fn record(&mut self, signatures: Vec<TrapSignature>, url: &str, found_on: &str) {
+        for signature in signatures {
+            if !self.entries.contains_key(&signature) && self.entries.len() >= MAX_TRAPS {
+                continue;
+            }
+            let entry = self.entries.entry(signature).or_default();
+            entry.count += 1;
+            entry.note(url, found_on);
+        }
+    }
+
+    /// Records a link skipped by the crawler's cap on similar URLs per
+    /// `extract_url_pattern` pattern.
+    pub fn record_similar_url_cap(
+        &mut self,
+        pattern: &str,
+        count: usize,
+        url: &str,
+        found_on: &str,
+    ) {
+        let signature = TrapSignature {
+            kind: TrapKind::SimilarUrls,
+            pattern: pattern.to_string(),
+        };
+        if !self.entries.contains_key(&signature) && self.entries.len() >= MAX_TRAPS {
+            return;
+        }
+        let entry = self.entries.entry(signature).or_default();
+        entry.count = entry.count.max(count);
+        entry.skipped_links += 1;
+        entry.note(url, found_on);
+        if entry.stopped.is_none() {
+            entry.stopped = Some(stop(
+                format!("Built-in limit on similar URLs ({} seen)", count),
+                url,
+                found_on,
+            ));
+        }
+    }
+
+    /// Signatures with enough URLs to be reported, most URLs first.
+    pub fn report(&self) -> Vec<SuspectedTrap> {
+        let mut traps: Vec<SuspectedTrap> = self
+            .entries
+            .iter()
+            .filter(|(signature, entry)| {
+                entry.stopped.is_some() || entry.count >= signature.kind.min_count()
+            })
+            .map(|(signature, entry)| SuspectedTrap {
+                kind: signature.kind,
+                pattern: signature.pattern.clone(),
+                count: entry.count,
+                examples: entry.examples.clone(),
+                linked_from: entry.linked_from.clone(),
+                skipped_links: entry.skipped_links,
+                stopped: entry.stopped.clone(),
+            })
+            .collect();
+        traps.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.pattern.cmp(&b.pattern))
+        });
+        traps
+    }
+}
+
+fn stop(reason: String, url: &str, found_on: &str) -> TrapStop {
+    TrapStop {
+        at: chrono::Local::now().to_rfc3339(),
+        reason,
+        url: url.to_string(),
+        found_on: found_on.to_string(),
+    }
+}
+
+/// The trap signatures `url` matches, if any.
+pub fn signatures(url: &Url) -> Vec<TrapSignature> {
+    let mut found = Vec::new();
+    let origin = url.origin().ascii_serialization();
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+
+    let mut push = |kind: TrapKind, pattern: String| found.push(TrapSignature { kind, pattern });
+
+    if let Some(pattern) = calendar_pattern(&origin, &segments, &params) {
+        push(TrapKind::Calendar, pattern);
+    }
+
+    if segments.len() >= GROWING_PATH_SEGMENTS {
+        push(
+            TrapKind::GrowingPath,
+            format!("{}/{}/…", origin, segments[..2].join("/")),
+        );
+    }
+
+    if let Some(pattern) = repeating_pattern(&origin, &segments) {
+        push(TrapKind::RepeatingSegments, pattern);
+    }
+
+    let path = url.path().to_ascii_lowercase();
+    let has_session = params
+        .iter()
+        .any(|(name, _)| SESSION_PARAMS.contains(&name.to_ascii_lowercase().as_str()))
+        || path.contains(";jsessionid=")
+        || path.contains(";sid=");
+    if has_session {
+        let path = url.path().split(';').next().unwrap_or_default();
+        let query: Vec<String> = params
+            .iter()
+            .map(|(name, value)| {
+                if SESSION_PARAMS.contains(&name.to_ascii_lowercase().as_str()) {
+                    format!("{}={{session}}", name)
+                } else {
+                    format!("{}={}", name, value)
+                }
+            })
+            .collect();
+        push(TrapKind::SessionId, with_query(&origin, path, &query));
+    }
+
+    if params.len() >= 2 {
+        let mut names: Vec<String> = params
+            .iter()
+            .map(|(name, _)| format!("{}=*", name))
+            .collect();
+        names.sort();
+        names.dedup();
+        push(
+            TrapKind::ParameterPermutations,
+            with_query(&origin, url.path(), &names),
+        );
+    }
+
+    found
+}
+
+fn with_query(origin: &str, path: &str, query: &[String]) -> String {
+    if query.is_empty() {
+        format!("{}{}", origin, path)
+    } else {
+        format!("{}{}?{}", origin, path, query.join("&"))
+    }
+}
+
+/// The URL with its dates replaced by placeholders: year/month/day path
+/// segments, ISO dates, and date-named or date-valued parameters.
+fn calendar_pattern(
+    origin: &str,
+    segments: &[&str],
+    params: &[(String, String)],
+) -> Option<String> {
+    let mut changed = false;
+    let mut previous = "";
+    let path: Vec<String> = segments
+        .iter()
+        .map(|segment| {
+            let placeholder = if is_iso_date(segment) {
+                Some("{date}")
+            } else if is_year(segment) {
+                Some("{yyyy}")
+            } else if previous == "{yyyy}" && number_in(segment, 1..=12) {
+                Some("{mm}")
+            } else if previous == "{mm}" && number_in(segment, 1..=31) {
+                Some("{dd}")
+            } else {
+                None
+            };
+            previous = placeholder.unwrap_or("");
+            match placeholder {
+                Some(placeholder) => {
+                    changed = true;
+                    placeholder.to_string()
+                }
+                None => segment.to_string(),
+            }
+        })
+        .collect();
+
+    let query: Vec<String> = params
+        .iter()
+        .map(|(name, value)| {
+            if DATE_PARAMS.contains(&name.to_ascii_lowercase().as_str()) || is_iso_date(value) {
+                changed = true;
+                format!("{}={{date}}", name)
+            } else {
+                format!("{}={}", name, value)
+            }
+        })
+        .collect();
+
+    changed.then(|| with_query(origin, &format!("/{}", path.join("/")), &query))
+}
+
+fn is_year(s: &str) -> bool {
+    s.len() == 4 && number_in(s, 1900..=2099)
+}
+
+fn number_in(s: &str, range: std::ops::RangeInclusive<u32>) -> bool {
+    s.len() <= 4
+        && s.chars().all(|c| c.is_ascii_digit())
+        && s.parse::<u32>().is_ok_and(|n| range.contains(&n))
+}
+
+/// `2024-05` or `2024-05-17`
+fn is_iso_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    match parts.as_slice() {
+        [year, month] => is_year(year) && month.len() == 2 && number_in(month, 1..=12),
+        [year, month, day] => {
+            is_year(year)
+                && month.len() == 2
+                && number_in(month, 1..=12)
+                && day.len() == 2
+                && number_in(day, 1..=31)
+        }
+        _ => false,
+    }
+}
+
+/// A block of segments repeated back to back (`/a/b/a/b`), or one segment
+/// showing up three times or more.
+fn repeating_pattern(origin: &str, segments: &[&str]) -> Option<String> {
+    for start in 0..segments.len() {
+        for len in 1..=(segments.len() - start) / 2 {
+            if segments[start..start + len] == segments[start + len..start + 2 * len] {
+                return Some(format!(
+                    "{}/{}({})+",
+                    origin,
+                    segments[..start]
+                        .iter()
+                        .map(|s| format!("{}/", s))
+                        .collect::<String>(),
+                    segments[start..start + len].join("/")
+                ));
+            }
+        }
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for segment in segments {
+        *counts.entry(segment).or_default() += 1;
+    }
+    segments.iter().find_map(|segment| {
+        let count = counts[segment];
+        (count >= 3).then(|| format!("{}/…/{}/… (×{})", origin, segment, count))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(url: &str) -> Vec<(TrapKind, String)> {
+        signatures(&Url::parse(url).unwrap())
+            .into_iter()
+            .map(|s| (s.kind, s.pattern))
+            .collect()
+    }
+
+    #[test]
+    fn classifies_trap_urls() {
+        assert_eq!(
+            kinds("https://example.com/events/2031/07/14"),
+            [(
+                TrapKind::Calendar,
+                "https://example.com/events/{yyyy}/{mm}/{dd}".to_string()
+            )]
+        );
+        assert_eq!(
+            kinds("https://example.com/docs/api/docs/api/intro"),
+            [(
+                TrapKind::RepeatingSegments,
+                "https://example.com/(docs/api)+".to_string()
+            )]
+        );
+        assert_eq!(
+            kinds("https://example.com/cart;jsessionid=ABC123"),
+            [(TrapKind::SessionId, "https://example.com/cart".to_string())]
+        );
+        assert_eq!(
+            kinds("https://example.com/shoes?size=9&color=red"),
+            [(
+                TrapKind::ParameterPermutations,
+                "https://example.com/shoes?color=*&size=*".to_string()
+            )]
+        );
+        assert!(kinds("https://example.com/blog/hello-world").is_empty());
+        assert!(kinds("https://example.com/a/b/c/d/e/f/g/h/i/j")
+            .iter()
+            .any(|(kind, pattern)| *kind == TrapKind::GrowingPath
+                && pattern == "https://example.com/a/b/…"));
+    }
+
+    #[test]
+    fn auto_stop_past_threshold() {
+        let mut detector = TrapDetector::new(true, 2);
+        let page = "https://example.com/shop";
+        for n in 0..3 {
+            let url = Url::parse(&format!("https://example.com/shoes?sid={}&page=1", n)).unwrap();
+            let found = signatures(&url);
+            if detector.allows(&found, url.as_str(), page) {
+                detector.record(found, url.as_str(), page);
+            }
+        }
+
+        let report = detector.report();
+        let session = report
+            .iter()
+            .find(|t| t.kind == TrapKind::SessionId)
+            .unwrap();
+        assert_eq!(
+            session.pattern,
+            "https://example.com/shoes?sid={session}&page=1"
+        );
+        assert_eq!((session.count, session.skipped_links), (2, 1));
+        assert_eq!(session.linked_from, [page]);
+        assert!(session.stopped.is_some());
+    }
+}
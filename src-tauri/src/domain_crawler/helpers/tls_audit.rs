@@ -0,0 +1,478 @@
+//! Per-host TLS audit (`Settings::tls_audit`), run once for every host the crawl
+//! visited: the leaf certificate's expiry, issuer and SAN coverage, whether the
+//! served chain verifies against the web PKI roots, the homepage's HTTP→HTTPS
+//! redirect and its Strict-Transport-Security header.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures::stream::{self, StreamExt};
+use reqwest::header::{LOCATION, STRICT_TRANSPORT_SECURITY};
+use reqwest::Client;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore,
+    SignatureScheme,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+use x509_parser::prelude::{ASN1Time, FromDer, GeneralName, X509Certificate};
+use x509_parser::x509::AttributeTypeAndValue;
+
+use super::rate_limit;
+use super::security_headers::{parse_hsts, MIN_HSTS_MAX_AGE};
+
+/// Hosts audited per crawl; list crawls spanning more hosts audit the first ones.
+const MAX_TLS_HOSTS: usize = 50;
+/// Redirect hops followed from `http://host/` looking for HTTPS.
+const MAX_REDIRECT_HOPS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsIssue {
+    /// No TLS handshake could be completed on port 443
+    NoHttps,
+    CertificateExpired,
+    /// Expires within `Settings::tls_expiry_warning_days`
+    CertificateExpiring,
+    /// The certificate's SANs don't cover the host
+    NameMismatch,
+    /// The served chain doesn't lead to a trusted root
+    IncompleteChain,
+    /// The chain failed verification for another reason
+    InvalidChain,
+    NoHttpsRedirect,
+    MissingHsts,
+    ShortHstsMaxAge,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    /// Subject common name
+    pub subject: Option<String>,
+    /// Issuer common name, or organization when there is none
+    pub issuer: Option<String>,
+    pub not_before: Option<String>,
+    pub not_after: Option<String>,
+    /// dNSName entries of the subjectAltName extension
+    pub dns_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsHostReport {
+    pub host: String,
+    pub tls_version: Option<String>,
+    pub certificate: Option<CertificateInfo>,
+    pub days_until_expiry: Option<i64>,
+    /// Certificates served, leaf first
+    pub chain_length: usize,
+    /// `Some(false)` when the chain can't be built to a trusted root; `None`
+    /// when verification failed before getting that far
+    pub chain_complete: Option<bool>,
+    pub chain_error: Option<String>,
+    pub covers_host: bool,
+    /// Status of `http://host/`
+    pub http_status: Option<u16>,
+    /// Where the `http://host/` redirects end (up to `MAX_REDIRECT_HOPS`)
+    pub http_final_url: Option<String>,
+    pub redirects_to_https: bool,
+    /// Raw Strict-Transport-Security header of `https://host/`
+    pub hsts: Option<String>,
+    pub hsts_max_age: Option<u64>,
+    pub hsts_include_subdomains: bool,
+    pub hsts_preload: bool,
+    /// Why the TLS handshake failed or wasn't attempted
+    pub error: Option<String>,
+    pub issues: Vec<TlsIssue>,
+}
+
+/// Distinct hosts of the visited URLs, in a stable order.
+pub fn crawled_hosts<'a>(urls: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut hosts: Vec<String> = urls
+        .into_iter()
+        .filter_map(|u| Url::parse(u).ok())
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .filter_map(|u| u.host_str().map(str::to_lowercase))
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    hosts
+}
+
+/// Audits the first `MAX_TLS_HOSTS` hosts, `concurrency` at a time.
+pub async fn audit_all(
+    client: &Client,
+    hosts: &[String],
+    direct: bool,
+    timeout: Duration,
+    expiry_warning_days: i64,
+    concurrency: usize,
+) -> Vec<TlsHostReport> {
+    stream::iter(hosts.iter().take(MAX_TLS_HOSTS).cloned())
+        .map(|host| audit_host(client, host, direct, timeout, expiry_warning_days))
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Audits one host. The handshake is made directly to port 443, so it is
+/// skipped when `direct` is false (the crawl goes through a proxy); the HTTP
+/// checks use the crawl's client, which must not follow redirects.
+async fn audit_host(
+    client: &Client,
+    host: String,
+    direct: bool,
+    timeout: Duration,
+    expiry_warning_days: i64,
+) -> TlsHostReport {
+    let mut report = TlsHostReport {
+        host: host.clone(),
+        ..Default::default()
+    };
+
+    if direct {
+        let target = host;
+        match tokio::task::spawn_blocking(move || handshake(&target, timeout)).await {
+            Ok(Ok(handshake)) => apply_handshake(&mut report, handshake),
+            Ok(Err(e)) => report.error = Some(e),
+            Err(e) => report.error = Some(e.to_string()),
+        }
+    } else {
+        report.error = Some("not checked: the crawl goes through a proxy".to_string());
+    }
+
+    check_http_redirect(client, &mut report).await;
+    check_hsts(client, &mut report).await;
+    report.issues = issues(&report, direct, expiry_warning_days);
+    report
+}
+
+struct Handshake {
+    tls_version: Option<String>,
+    certificates: Vec<CertificateDer<'static>>,
+    verification: Option<Result<(), rustls::Error>>,
+}
+
+fn apply_handshake(report: &mut TlsHostReport, handshake: Handshake) {
+    report.tls_version = handshake.tls_version;
+    report.chain_length = handshake.certificates.len();
+    if let Some(leaf) = handshake.certificates.first() {
+        let certificate = parse_certificate(leaf.as_ref()).unwrap_or_default();
+        report.days_until_expiry = certificate
+            .not_after
+            .as_deref()
+            .and_then(|t| NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%SZ").ok())
+            .map(|t| (t.and_utc() - Utc::now()).num_days());
+        report.covers_host = certificate
+            .dns_names
+            .iter()
+            .any(|name| name_matches(name, &report.host));
+        report.certificate = Some(certificate);
+    }
+    match handshake.verification {
+        Some(Ok(())) => report.chain_complete = Some(true),
+        Some(Err(e)) => {
+            // webpki builds the path before checking the name, so a name
+            // mismatch still means the chain itself is fine.
+            report.chain_complete = match &e {
+                rustls::Error::InvalidCertificate(
+                    CertificateError::NotValidForName
+                    | CertificateError::NotValidForNameContext { .. },
+                ) => Some(true),
+                rustls::Error::InvalidCertificate(CertificateError::UnknownIssuer) => Some(false),
+                _ => None,
+            };
+            report.chain_error = Some(e.to_string());
+        }
+        None => {}
+    }
+}
+
+/// Completes a handshake with `host:443`, accepting whatever certificate is
+/// served and recording what web PKI verification made of it.
+fn handshake(host: &str, timeout: Duration) -> Result<Handshake, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let verifier = Arc::new(RecordingVerifier {
+        inner,
+        outcome: Mutex::new(None),
+    });
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+
+    let server_name = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+    let mut conn =
+        ClientConnection::new(Arc::new(config), server_name).map_err(|e| e.to_string())?;
+
+    let addr = (host, 443)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("{} did not resolve", host))?;
+    let mut sock = TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    sock.set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+    sock.set_write_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+
+    while conn.is_handshaking() {
+        conn.complete_io(&mut sock).map_err(|e| e.to_string())?;
+    }
+    conn.send_close_notify();
+    let _ = conn.complete_io(&mut sock);
+    let _ = sock.flush();
+
+    let certificates = conn
+        .peer_certificates()
+        .map(|certs| certs.iter().map(|c| c.clone().into_owned()).collect())
+        .unwrap_or_default();
+    let verification = verifier.outcome.lock().ok().and_then(|mut o| o.take());
+    Ok(Handshake {
+        tls_version: conn.protocol_version().map(|v| format!("{:?}", v)),
+        certificates,
+        verification,
+    })
+}
+
+/// Lets every handshake through so an invalid certificate can still be
+/// inspected, keeping the web PKI verdict for the report.
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    outcome: Mutex<Option<Result<(), rustls::Error>>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let outcome = self
+            .inner
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+            .map(|_| ());
+        if let Ok(mut slot) = self.outcome.lock() {
+            *slot = Some(outcome);
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Follows `http://host/` redirects by hand and records where they end.
+async fn check_http_redirect(client: &Client, report: &mut TlsHostReport) {
+    let Ok(mut url) = Url::parse(&format!("http://{}/", report.host)) else {
+        return;
+    };
+    for _ in 0..=MAX_REDIRECT_HOPS {
+        rate_limit::until_ready(url.as_str()).await;
+        let Ok(response) = client.get(url.clone()).send().await else {
+            break;
+        };
+        if report.http_status.is_none() {
+            report.http_status = Some(response.status().as_u16());
+        }
+        let next = response
+            .status()
+            .is_redirection()
+            .then(|| response.headers().get(LOCATION))
+            .flatten()
+            .and_then(|v| v.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        match next {
+            Some(next) => {
+                url = next;
+                if url.scheme() == "https" {
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+    report.redirects_to_https = url.scheme() == "https";
+    report.http_final_url = Some(url.to_string());
+}
+
+async fn check_hsts(client: &Client, report: &mut TlsHostReport) {
+    let url = format!("https://{}/", report.host);
+    rate_limit::until_ready(&url).await;
+    let Ok(response) = client.get(&url).send().await else {
+        return;
+    };
+    let Some(value) = response
+        .headers()
+        .get(STRICT_TRANSPORT_SECURITY)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return;
+    };
+    let hsts = parse_hsts(value);
+    report.hsts_max_age = hsts.max_age;
+    report.hsts_include_subdomains = hsts.include_subdomains;
+    report.hsts_preload = hsts.preload;
+    report.hsts = Some(value.to_string());
+}
+
+fn issues(report: &TlsHostReport, direct: bool, expiry_warning_days: i64) -> Vec<TlsIssue> {
+    let mut issues = Vec::new();
+    if direct && report.certificate.is_none() {
+        issues.push(TlsIssue::NoHttps);
+    }
+    if report.certificate.is_some() {
+        match report.days_until_expiry {
+            Some(days) if days < 0 => issues.push(TlsIssue::CertificateExpired),
+            Some(days) if days <= expiry_warning_days => issues.push(TlsIssue::CertificateExpiring),
+            _ => {}
+        }
+        if !report.covers_host {
+            issues.push(TlsIssue::NameMismatch);
+        }
+        match report.chain_complete {
+            Some(false) => issues.push(TlsIssue::IncompleteChain),
+            None if report.chain_error.is_some()
+                && !issues.contains(&TlsIssue::CertificateExpired) =>
+            {
+                issues.push(TlsIssue::InvalidChain)
+            }
+            _ => {}
+        }
+    }
+    if report.http_status.is_some() && !report.redirects_to_https {
+        issues.push(TlsIssue::NoHttpsRedirect);
+    }
+    match (report.hsts.is_some(), report.hsts_max_age) {
+        (false, _) => issues.push(TlsIssue::MissingHsts),
+        (true, Some(age)) if age >= MIN_HSTS_MAX_AGE => {}
+        (true, _) => issues.push(TlsIssue::ShortHstsMaxAge),
+    }
+    issues
+}
+
+/// Matches a SAN dNSName against a host; a wildcard covers exactly one label.
+fn name_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim_end_matches('.').to_ascii_lowercase();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => host
+            .split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+        None => pattern == host,
+    }
+}
+
+/// Subject, issuer, validity and DNS names of a DER certificate.
+fn parse_certificate(der: &[u8]) -> Option<CertificateInfo> {
+    let (_, certificate) = X509Certificate::from_der(der).ok()?;
+    let time = |time: ASN1Time| {
+        DateTime::from_timestamp(time.timestamp(), 0)
+            .map(|time| time.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    };
+    let dns_names = certificate
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|san| {
+            san.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(name) => Some(name.to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let info = CertificateInfo {
+        subject: text(certificate.subject().iter_common_name().next()),
+        issuer: text(certificate.issuer().iter_common_name().next())
+            .or_else(|| text(certificate.issuer().iter_organization().next())),
+        not_before: time(certificate.validity().not_before),
+        not_after: time(certificate.validity().not_after),
+        dns_names,
+    };
+    Some(info)
+}
+
+/// A name attribute as text, when it is a string type.
+fn text(value: Option<&AttributeTypeAndValue>) -> Option<String> {
+    value
+        .and_then(|value| value.as_str().ok())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x509_parser::pem::parse_x509_pem;
+
+    /// Leaf for example.com and *.example.com, issued by "Test CA" (O=Test Org),
+    /// valid 2025-01-01 to 2099-01-01.
+    const LEAF_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBljCCATugAwIBAgIBATAKBggqhkjOPQQDAjAlMREwDwYDVQQKDAhUZXN0IE9y
+ZzEQMA4GA1UEAwwHVGVzdCBDQTAgFw0yNTAxMDEwMDAwMDBaGA8yMDk5MDEwMTAw
+MDAwMFowFjEUMBIGA1UEAwwLZXhhbXBsZS5jb20wWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAATE2uCBdjLzk2Mzq/bPfPW3iBjOjp50+2jbjJhyYdF0jRg6EBGXxLMq
+vtQa5IYpDKyhJxxZAt8OYR1xO9h4R1Rko2kwZzAlBgNVHREEHjAcggtleGFtcGxl
+LmNvbYINKi5leGFtcGxlLmNvbTAdBgNVHQ4EFgQUgqexLkd8HGULhOs/EJ+lORsY
+Q9EwHwYDVR0jBBgwFoAU+QoiVoYbUK71/HAOsAl63sqd8eIwCgYIKoZIzj0EAwID
+SQAwRgIhALQJrhhLaB/qGPu/ywcwO+dt9PoUGaM5ZDUxplzauwZ7AiEAhLdcDa6o
+VWKCEDFoH3o6QH8LjxKHTs/H+BXozGCMbqI=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn reads_expiry_issuer_and_sans() {
+        let (_, pem) = parse_x509_pem(LEAF_PEM.as_bytes()).unwrap();
+        let info = parse_certificate(&pem.contents).unwrap();
+        assert_eq!(info.issuer.as_deref(), Some("Test CA"));
+        assert_eq!(info.subject.as_deref(), Some("example.com"));
+        assert_eq!(info.not_before.as_deref(), Some("2025-01-01T00:00:00Z"));
+        assert_eq!(info.not_after.as_deref(), Some("2099-01-01T00:00:00Z"));
+        assert_eq!(info.dns_names, vec!["example.com", "*.example.com"]);
+        assert!(parse_certificate(b"not a certificate").is_none());
+
+        assert!(name_matches("*.example.com", "www.example.com"));
+        assert!(!name_matches("*.example.com", "example.com"));
+        assert!(!name_matches("*.example.com", "a.b.example.com"));
+        assert!(name_matches("Example.com", "example.com."));
+    }
+}
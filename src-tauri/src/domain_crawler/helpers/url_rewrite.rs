@@ -0,0 +1,234 @@
+//! User-defined URL rewrite rules (`Settings::url_rewrite_rules`), applied in
+//! order to every URL discovered during a crawl (page links and sitemaps) before
+//! it is normalized and queued. They collapse session IDs, sort/filter params
+//! and mixed-case paths into one URL each. Start URLs and list-mode URLs are
+//! fetched as given.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::normalize_url::normalize_url;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RewriteRule {
+    /// Regex replace on the full URL; `replacement` may use `$1` / `${name}`
+    RegexReplace { pattern: String, replacement: String },
+    /// Drop the named query parameters (case-insensitive)
+    DropParams { params: Vec<String> },
+    /// Drop the whole query string
+    DropAllParams,
+    /// Keep only the named query parameters (case-insensitive)
+    KeepParams { params: Vec<String> },
+    /// Sort query parameters by name, then value
+    SortParams,
+    /// Lowercase the path and query
+    Lowercase,
+}
+
+impl RewriteRule {
+    fn describe(&self) -> String {
+        match self {
+            RewriteRule::RegexReplace {
+                pattern,
+                replacement,
+            } => format!("Replace /{}/ with \"{}\"", pattern, replacement),
+            RewriteRule::DropParams { params } => format!("Drop params: {}", params.join(", ")),
+            RewriteRule::DropAllParams => "Drop all params".to_string(),
+            RewriteRule::KeepParams { params } => format!("Keep only params: {}", params.join(", ")),
+            RewriteRule::SortParams => "Sort params".to_string(),
+            RewriteRule::Lowercase => "Lowercase".to_string(),
+        }
+    }
+}
+
+/// One rule's effect on the previewed URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewriteStep {
+    pub rule: String,
+    pub url: String,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewritePreview {
+    pub original: String,
+    pub steps: Vec<RewriteStep>,
+    /// After all rules
+    pub rewritten: String,
+    /// What the crawler queues: the rewritten URL after `normalize_url`
+    pub normalized: String,
+}
+
+/// Rewrite rules with their regexes compiled.
+#[derive(Debug, Clone)]
+pub struct UrlRewriter {
+    rules: Vec<(RewriteRule, Option<Regex>)>,
+}
+
+impl UrlRewriter {
+    /// Compiles the rules, failing on the first invalid regex.
+    pub fn from_rules(rules: &[RewriteRule]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let regex = match rule {
+                    RewriteRule::RegexReplace { pattern, .. } => Some(
+                        Regex::new(pattern)
+                            .map_err(|e| format!("Invalid rewrite pattern '{}': {}", pattern, e))?,
+                    ),
+                    _ => None,
+                };
+                Ok((rule.clone(), regex))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(UrlRewriter { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Applies every rule in order. A rule whose result isn't a valid URL is skipped.
+    pub fn rewrite(&self, url: &str) -> String {
+        self.rules
+            .iter()
+            .fold(url.to_string(), |current, (rule, regex)| {
+                apply_rule(rule, regex.as_ref(), &current).unwrap_or(current)
+            })
+    }
+
+    /// Shows how `url` changes rule by rule.
+    pub fn preview(&self, url: &str) -> Result<RewritePreview, String> {
+        Url::parse(url).map_err(|_| format!("Invalid URL: {}", url))?;
+
+        let mut current = url.to_string();
+        let mut steps = Vec::with_capacity(self.rules.len());
+        for (rule, regex) in &self.rules {
+            let next = apply_rule(rule, regex.as_ref(), &current).unwrap_or_else(|| current.clone());
+            steps.push(RewriteStep {
+                rule: rule.describe(),
+                changed: next != current,
+                url: next.clone(),
+            });
+            current = next;
+        }
+
+        Ok(RewritePreview {
+            original: url.to_string(),
+            steps,
+            normalized: normalize_url(&current),
+            rewritten: current,
+        })
+    }
+}
+
+fn apply_rule(rule: &RewriteRule, regex: Option<&Regex>, url: &str) -> Option<String> {
+    let mut parsed = Url::parse(url).ok()?;
+    match rule {
+        RewriteRule::RegexReplace { replacement, .. } => {
+            let replaced = regex?.replace_all(url, replacement.as_str());
+            return Url::parse(&replaced).ok().map(|u| u.to_string());
+        }
+        RewriteRule::DropParams { params } => {
+            retain_params(&mut parsed, |name| {
+                !params.iter().any(|p| p.eq_ignore_ascii_case(name))
+            });
+        }
+        RewriteRule::DropAllParams => parsed.set_query(None),
+        RewriteRule::KeepParams { params } => {
+            retain_params(&mut parsed, |name| {
+                params.iter().any(|p| p.eq_ignore_ascii_case(name))
+            });
+        }
+        RewriteRule::SortParams => {
+            if let Some(query) = parsed.query() {
+                let mut pairs: Vec<&str> = query.split('&').filter(|p| !p.is_empty()).collect();
+                pairs.sort_by(|a, b| param_name(a).cmp(param_name(b)).then(a.cmp(b)));
+                let sorted = pairs.join("&");
+                parsed.set_query(Some(&sorted));
+            }
+        }
+        RewriteRule::Lowercase => {
+            let path = parsed.path().to_lowercase();
+            parsed.set_path(&path);
+            if let Some(query) = parsed.query().map(str::to_lowercase) {
+                parsed.set_query(Some(&query));
+            }
+        }
+    }
+    Some(parsed.to_string())
+}
+
+/// Keeps the query parameters whose (raw) name passes `keep`, preserving their
+/// order and encoding. Removes the query string when nothing is left.
+fn retain_params(url: &mut Url, keep: impl Fn(&str) -> bool) {
+    let Some(query) = url.query() else {
+        return;
+    };
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|p| !p.is_empty() && keep(param_name(p)))
+        .collect();
+    let kept = kept.join("&");
+    url.set_query((!kept.is_empty()).then_some(kept.as_str()));
+}
+
+fn param_name(pair: &str) -> &str {
+    pair.split_once('=').map_or(pair, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_rules_in_order() {
+        let rewriter = UrlRewriter::from_rules(&[
+            RewriteRule::RegexReplace {
+                pattern: r";jsessionid=[^?#]*".to_string(),
+                replacement: String::new(),
+            },
+            RewriteRule::DropParams {
+                params: vec!["SID".to_string()],
+            },
+            RewriteRule::SortParams,
+            RewriteRule::Lowercase,
+        ])
+        .unwrap();
+
+        assert_eq!(
+            rewriter.rewrite("https://example.com/Shop/Shoes;jsessionid=AB12?sort=price&sid=9&color=Red"),
+            "https://example.com/shop/shoes?color=red&sort=price"
+        );
+
+        let keep = UrlRewriter::from_rules(&[RewriteRule::KeepParams {
+            params: vec!["page".to_string()],
+        }])
+        .unwrap();
+        assert_eq!(
+            keep.rewrite("https://example.com/blog?page=2&ref=nav"),
+            "https://example.com/blog?page=2"
+        );
+        assert_eq!(keep.rewrite("https://example.com/blog?ref=nav"), "https://example.com/blog");
+    }
+
+    #[test]
+    fn preview_lists_each_step() {
+        let rewriter =
+            UrlRewriter::from_rules(&[RewriteRule::DropAllParams, RewriteRule::Lowercase]).unwrap();
+        let preview = rewriter.preview("https://example.com/About/?utm_source=x").unwrap();
+
+        assert_eq!(preview.steps.len(), 2);
+        assert!(preview.steps.iter().all(|s| s.changed));
+        assert_eq!(preview.rewritten, "https://example.com/about/");
+        assert_eq!(preview.normalized, "https://example.com/about");
+
+        assert!(UrlRewriter::from_rules(&[RewriteRule::RegexReplace {
+            pattern: "(".to_string(),
+            replacement: String::new(),
+        }])
+        .is_err());
+    }
+}
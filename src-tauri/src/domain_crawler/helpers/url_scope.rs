@@ -0,0 +1,85 @@
+use regex::Regex;
+
+/// User-defined include/exclude rules that decide which discovered URLs are in
+/// scope for a deep crawl. Patterns are matched against the full normalized URL.
+#[derive(Debug, Clone, Default)]
+pub struct UrlScope {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl UrlScope {
+    /// Compiles the include/exclude lists. Blank patterns are ignored; an invalid
+    /// regex is reported back with the offending pattern so the crawl can refuse
+    /// to start instead of silently crawling the wrong set of URLs.
+    pub fn from_patterns(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            include: compile(include, "include")?,
+            exclude: compile(exclude, "exclude")?,
+        })
+    }
+
+    /// `true` when no rules are configured, so callers can skip scope checks entirely.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Returns the rule that excludes `url`, or `None` when it is in scope.
+    /// An exclude match always wins; when include patterns are configured the
+    /// URL must match at least one of them.
+    pub fn check(&self, url: &str) -> Option<String> {
+        if let Some(re) = self.exclude.iter().find(|re| re.is_match(url)) {
+            return Some(format!("exclude: {}", re.as_str()));
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|re| re.is_match(url)) {
+            return Some("include: no pattern matched".to_string());
+        }
+        None
+    }
+}
+
+fn compile(patterns: &[String], kind: &str) -> Result<Vec<Regex>, String> {
+    patterns
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| Regex::new(p).map_err(|e| format!("Invalid {} pattern '{}': {}", kind, p, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(include: &[&str], exclude: &[&str]) -> UrlScope {
+        let to_vec = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        UrlScope::from_patterns(&to_vec(include), &to_vec(exclude)).unwrap()
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let s = scope(&["/blog/"], &[r"\?page=\d+"]);
+        assert_eq!(s.check("https://example.com/blog/post"), None);
+        assert_eq!(
+            s.check("https://example.com/blog/?page=2"),
+            Some(r"exclude: \?page=\d+".to_string())
+        );
+        assert_eq!(
+            s.check("https://example.com/shop/item"),
+            Some("include: no pattern matched".to_string())
+        );
+    }
+
+    #[test]
+    fn empty_rules_allow_everything() {
+        let s = scope(&[], &["  "]);
+        assert!(s.is_empty());
+        assert_eq!(s.check("https://example.com/anything"), None);
+    }
+
+    #[test]
+    fn invalid_pattern_is_reported() {
+        let err = UrlScope::from_patterns(&[], &["(unclosed".to_string()]).unwrap_err();
+        assert!(err.contains("(unclosed"));
+    }
+}
@@ -0,0 +1,186 @@
+//! List mode: crawl an exact set of URLs through the regular `process_url`
+//! pipeline without following links or discovering sitemaps.
+//! This module only turns the user's input (pasted text, a CSV/XLSX file or a
+//! single sitemap) into the list of URLs to fetch; the crawl itself is driven by
+//! `domain_crawler::crawl_url_list`.
+
+use calamine::{open_workbook_auto, Data, Reader};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use url::Url;
+
+use super::helpers::domain_checker::url_check;
+use super::helpers::normalize_url::normalize_url;
+use super::helpers::sitemap;
+
+/// Where the list of URLs comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ListSource {
+    /// URLs pasted into the UI, separated by new lines, commas, tabs or spaces
+    Paste { text: String },
+    /// Path to a .csv/.txt or spreadsheet (.xlsx/.xls/.ods) file. Every cell that
+    /// holds an http(s) URL is used, whatever column it is in.
+    File { path: String },
+    /// A single sitemap (or sitemap index) whose `<loc>` URLs make up the list
+    Sitemap { url: String },
+}
+
+/// Resolves a `ListSource` into a de-duplicated list of URLs, keeping the order
+/// they were supplied in.
+pub async fn resolve_list_source(source: &ListSource, client: &Client) -> Result<Vec<Url>, String> {
+    let urls = match source {
+        ListSource::Paste { text } => parse_url_list(text),
+        ListSource::File { path } => read_file(path).await?,
+        ListSource::Sitemap { url } => {
+            let mut urls: Vec<Url> = sitemap::extract_urls_from_sitemap(&sitemap_url(url)?, client)
+                .await
+                .into_iter()
+                .filter_map(|u| Url::parse(&u).ok())
+                .collect();
+            // HashSet order is random; sort so repeated runs queue URLs identically.
+            urls.sort();
+            urls
+        }
+    };
+    non_empty(urls)
+}
+
+/// Checks a source before a crawl project is created for it. Pasted text and
+/// files must hold at least one URL; a sitemap can only be checked for a valid
+/// URL, since its contents are fetched with the crawl's client.
+pub async fn validate_list_source(source: &ListSource) -> Result<(), String> {
+    match source {
+        ListSource::Paste { text } => non_empty(parse_url_list(text)).map(|_| ()),
+        ListSource::File { path } => non_empty(read_file(path).await?).map(|_| ()),
+        ListSource::Sitemap { url } => sitemap_url(url).map(|_| ()),
+    }
+}
+
+async fn read_file(path: &str) -> Result<Vec<Url>, String> {
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || read_urls_from_file(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Failed to read URL list: {}", e))?
+}
+
+fn sitemap_url(url: &str) -> Result<Url, String> {
+    Url::parse(&url_check(url)).map_err(|e| format!("Invalid sitemap URL: {}", e))
+}
+
+fn non_empty(urls: Vec<Url>) -> Result<Vec<Url>, String> {
+    if urls.is_empty() {
+        return Err("No valid URLs found in the supplied list".to_string());
+    }
+    Ok(urls)
+}
+
+/// Extracts URLs from free text. Tokens without a scheme are accepted when they
+/// look like a host name (e.g. `example.com/page`) and default to https.
+pub fn parse_url_list(text: &str) -> Vec<Url> {
+    let mut seen = HashSet::new();
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter_map(|token| to_crawl_url(token, true))
+        .filter(|url| seen.insert(normalize_url(url.as_str())))
+        .collect()
+}
+
+fn read_urls_from_file(path: &Path) -> Result<Vec<Url>, String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let cells: Vec<String> = match extension.as_str() {
+        "xlsx" | "xlsm" | "xls" | "xlsb" | "ods" => {
+            let mut workbook = open_workbook_auto(path)
+                .map_err(|e| format!("Failed to open spreadsheet: {}", e))?;
+            workbook
+                .worksheets()
+                .into_iter()
+                .flat_map(|(_, range)| {
+                    range
+                        .cells()
+                        .filter_map(|(_, _, cell)| match cell {
+                            Data::String(s) => Some(s.clone()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        }
+        "csv" | "tsv" | "txt" => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .delimiter(if extension == "tsv" { b'\t' } else { b',' })
+                .from_path(path)
+                .map_err(|e| format!("Failed to open file: {}", e))?;
+            reader
+                .records()
+                .filter_map(Result::ok)
+                .flat_map(|record| record.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+                .collect()
+        }
+        other => return Err(format!("Unsupported file type: .{}", other)),
+    };
+
+    // Files carry headers and arbitrary columns, so only explicit http(s) URLs count.
+    let mut seen = HashSet::new();
+    Ok(cells
+        .iter()
+        .flat_map(|cell| cell.split_whitespace())
+        .filter_map(|token| to_crawl_url(token, false))
+        .filter(|url| seen.insert(normalize_url(url.as_str())))
+        .collect())
+}
+
+fn to_crawl_url(token: &str, allow_schemeless: bool) -> Option<Url> {
+    let token = token.trim_matches(|c: char| matches!(c, '"' | '\'' | '<' | '>' | '(' | ')'));
+    if token.is_empty() {
+        return None;
+    }
+
+    let lower = token.to_lowercase();
+    let candidate = if lower.starts_with("http://") || lower.starts_with("https://") {
+        token.to_string()
+    } else if allow_schemeless && !lower.contains("://") && token.contains('.') {
+        url_check(token)
+    } else {
+        return None;
+    };
+
+    let url = Url::parse(&candidate).ok()?;
+    url.host_str()?;
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mixed_pasted_list() {
+        let text = "https://example.com/a\nexample.com/b, \"https://example.com/a\"\tnot-a-url\nftp://example.com/c";
+        let urls: Vec<String> = parse_url_list(text).iter().map(|u| u.to_string()).collect();
+        assert_eq!(urls, vec!["https://example.com/a", "https://example.com/b"]);
+    }
+
+    #[test]
+    fn reads_urls_from_any_csv_column() {
+        // Unique per process so concurrent test runs don't share the file
+        let path = std::env::temp_dir().join(format!(
+            "rustyseo_list_mode_test_{}.csv",
+            std::process::id()
+        ));
+        std::fs::write(&path, "Title,Address\nHome,https://example.com/\nAbout,https://example.com/about\n")
+            .unwrap();
+        let urls = read_urls_from_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[1].as_str(), "https://example.com/about");
+    }
+}
@@ -1,4 +1,8 @@
+pub mod checkpoint;
 pub mod constants;
+pub mod crawl_compare;
+pub mod crawl_overrides;
+pub mod crawl_projects;
 pub mod database;
 pub mod db_deep;
 pub mod domain_commands;
@@ -9,8 +13,11 @@ pub mod extractors;
 pub mod helpers;
 pub mod issues_report;
 pub mod link_score;
+pub mod list_mode;
 pub mod models;
+pub mod orphans;
 pub mod page_speed;
+pub mod scheduler;
 pub mod state;
 pub mod url_processor;
 pub mod user_agents;
@@ -7,9 +7,9 @@ use url::Url;
 
 use super::helpers::{
         alt_tags::AltTags, anchor_links::InternalExternalLinks, cross_origin::SecuritySummary,
-        css_selector::CSS, hreflang_selector::HreflangObject, html_size_calculator::Sizes,
+        css_selector::CSS, headless_fetch::RenderTiming, hreflang_selector::HreflangObject, html_size_calculator::Sizes,
         iframe_selector::Iframe, indexability::Indexability, javascript_selector::JavaScript,
-        links_status_code_checker::LinkCheckResults, meta_robots_selector::MetaRobots, text_ratio::TextRatio, title_selector::TitleDetails,
+        links_status_code_checker::LinkCheckResults, meta_robots_selector::MetaRobots, mobile_parity::ParityReport, pdf_document::PdfDocument, render_diff::RenderDiff, robots::RobotsBlock, security_headers::SecurityHeadersReport, text_ratio::TextRatio, title_selector::TitleDetails,
     };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +18,34 @@ pub struct RedirectHop {
     pub status_code: u16,
 }
 
+/// Where the crawler first found a URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoverySource {
+    /// The crawl's start URL
+    Start,
+    /// A URL supplied in list mode
+    List,
+    Link,
+    Sitemap,
+    /// The target of a redirect from a URL found another way
+    Redirect,
+    Canonical,
+    Hreflang,
+}
+
+/// How a crawled URL was discovered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Discovery {
+    pub source: DiscoverySource,
+    /// The first page linking to the URL (or to the URL that redirected to it).
+    /// `None` for start, list and sitemap URLs
+    pub referrer: Option<String>,
+    /// Hops from the start URL; sitemap and list URLs start at 0
+    #[serde(default)]
+    pub crawl_depth: usize,
+}
+
 // Result of evaluating one Custom Search rule (see db_deep::db::CustomSearchRule)
 // against a single page. One of these per *enabled* rule, per crawled page.
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -65,6 +93,10 @@ pub struct DomainCrawlResults {
     pub pdf_files: Vec<String>,
     pub https: bool,
     pub cross_origin: SecuritySummary,
+    /// Grade of the security response headers. `None` for results stored before
+    /// the check existed.
+    #[serde(default)]
+    pub security_headers: Option<SecurityHeadersReport>,
     pub psi_results: Result<Vec<Value>, String>,
     pub original_url: String,                     // The URL we requested
     pub redirect_url: Option<String>,             // The redirect URL (if any)
@@ -81,6 +113,28 @@ pub struct DomainCrawlResults {
     /// Exact hash of normalized H1-H3 text, for flagging pages that share the same
     /// heading structure. `None` when the check is disabled, or the page has no headings.
     pub heading_hash: Option<u64>,
+    /// Set when the URL was not fetched because robots.txt disallows it for the
+    /// crawl's user agent; records the rule and its line number.
+    #[serde(default)]
+    pub blocked_by_robots: Option<RobotsBlock>,
+    /// Headless Chrome render timings. `None` unless the page was rendered with
+    /// `javascript_rendering` on.
+    #[serde(default)]
+    pub js_render: Option<RenderTiming>,
+    /// What changed between the raw HTML and the rendered DOM. `None` unless the
+    /// page was rendered with `javascript_rendering` on.
+    #[serde(default)]
+    pub render_diff: Option<RenderDiff>,
+    /// Mobile vs desktop comparison. `None` unless `mobile_parity_check` is on.
+    #[serde(default)]
+    pub mobile_parity: Option<ParityReport>,
+    /// How the URL was found. `None` for URLs restored from a checkpoint.
+    #[serde(default)]
+    pub discovery: Option<Discovery>,
+    /// Document audit of a PDF. `None` unless the URL is a PDF crawled with
+    /// `crawl_pdfs` on.
+    #[serde(default)]
+    pub pdf: Option<PdfDocument>,
 }
 
 // Implement Default for DomainCrawlResults
@@ -132,6 +186,7 @@ impl Default for DomainCrawlResults {
                 total_missing_cors: 0,
                 total_inline_scripts: 0,
             },
+            security_headers: None,
             psi_results: Ok(Vec::new()),
             original_url: String::new(),
             redirect_url: None,
@@ -144,6 +199,12 @@ impl Default for DomainCrawlResults {
             cookies: Ok(Vec::new()),
             content_simhash: None,
             heading_hash: None,
+            blocked_by_robots: None,
+            js_render: None,
+            render_diff: None,
+            mobile_parity: None,
+            discovery: None,
+            pdf: None,
         }
     }
 }
@@ -188,6 +249,24 @@ pub struct LightCrawlResult {
     pub seo_score: Option<f64>,
     pub https: bool,
     pub security: SecuritySummary,
+    /// 0-100 grade of the security response headers
+    #[serde(default)]
+    pub security_headers_score: Option<u8>,
+    #[serde(default)]
+    pub blocked_by_robots: Option<RobotsBlock>,
+    /// Total headless Chrome render time, when the page was rendered
+    #[serde(default)]
+    pub js_render_ms: Option<u64>,
+    /// Indexability signals or internal links only appear after JavaScript
+    #[serde(default)]
+    pub js_dependent: bool,
+    /// What differs between the mobile and desktop versions (see `ParityReport`)
+    #[serde(default)]
+    pub parity_mismatches: Vec<String>,
+    #[serde(default)]
+    pub discovery_source: Option<DiscoverySource>,
+    #[serde(default)]
+    pub first_referrer: Option<String>,
 }
 
 impl LightCrawlResult {
@@ -289,6 +368,20 @@ impl LightCrawlResult {
             seo_score: Self::get_psi_score(full, "seo"),
             https: full.https,
             security: full.cross_origin.clone(),
+            security_headers_score: full.security_headers.as_ref().map(|r| r.score),
+            blocked_by_robots: full.blocked_by_robots.clone(),
+            js_render_ms: full.js_render.as_ref().map(|t| t.total_ms),
+            js_dependent: full
+                .render_diff
+                .as_ref()
+                .is_some_and(|d| d.indexability_depends_on_js || d.links_depend_on_js),
+            parity_mismatches: full
+                .mobile_parity
+                .as_ref()
+                .map(|p| p.mismatches.clone())
+                .unwrap_or_default(),
+            discovery_source: full.discovery.as_ref().map(|d| d.source),
+            first_referrer: full.discovery.as_ref().and_then(|d| d.referrer.clone()),
         }
     }
 
@@ -0,0 +1,439 @@
+//! Orphan pages: URLs the site is known to have (listed in its sitemaps, reported
+//! by Search Console or GA4, or requested in the server logs) that no crawled page
+//! links to. Each orphan is labelled with the sources that know about it. Its status
+//! and indexability come from the crawl when it was crawled anyway (e.g. queued from
+//! the sitemap), and can otherwise be fetched on demand.
+
+use futures::stream::{self, StreamExt};
+use reqwest::cookie::Jar;
+use reqwest::Client;
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+use crate::settings::settings::Settings;
+
+use super::helpers::crawl_auth::{self, CrawlAuth, CrawlCredentials};
+use super::helpers::fetch_with_exponential::fetch_with_exponential_backoff;
+use super::helpers::form_login::LoginSession;
+use super::helpers::indexability::extract_indexability;
+use super::helpers::normalize_url::normalize_url;
+use super::helpers::proxy::ProxyConfig;
+use super::helpers::robots;
+use super::user_agents::CrawlIdentity;
+
+/// Upper bound on the orphans fetched by one report, so a sitemap full of
+/// unlinked URLs doesn't turn into a second crawl.
+const MAX_ORPHAN_FETCHES: usize = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanSource {
+    Sitemap,
+    Gsc,
+    Ga4,
+    Logs,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanPage {
+    pub url: String,
+    pub sources: Vec<OrphanSource>,
+    /// Whether the status/indexability come from the crawl itself rather than
+    /// an on-demand fetch
+    pub crawled: bool,
+    pub status_code: Option<u16>,
+    pub indexability: Option<f32>,
+    pub indexability_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrphanReport {
+    pub total: usize,
+    /// Orphans per source; an orphan known from several sources counts in each
+    pub by_source: HashMap<OrphanSource, usize>,
+    pub orphans: Vec<OrphanPage>,
+}
+
+/// The crawl's start URL origin, which log paths and GA4 page paths are
+/// resolved against. Falls back to the first crawled page for crawls stored
+/// before discovery sources were recorded.
+pub fn site_origin(pages: &[Value]) -> Option<Url> {
+    let url_of = |page: &Value| {
+        page.get("url")
+            .and_then(|v| v.as_str())
+            .and_then(|u| Url::parse(u).ok())
+    };
+    pages
+        .iter()
+        .find(|page| page.pointer("/discovery/source").and_then(|v| v.as_str()) == Some("start"))
+        .and_then(url_of)
+        .or_else(|| pages.iter().find_map(url_of))
+        .and_then(|url| url.join("/").ok())
+}
+
+/// Resolves a path (or full URL) against the site origin. Paths that look like
+/// assets rather than pages are dropped, since logs and analytics report both.
+pub fn resolve_path(origin: &Url, path: &str) -> Option<String> {
+    let path = path.trim();
+    if path.is_empty() || !looks_like_page(path) {
+        return None;
+    }
+    origin.join(path).ok().map(String::from)
+}
+
+fn looks_like_page(path: &str) -> bool {
+    const PAGE_EXTENSIONS: &[&str] = &["html", "htm", "php", "asp", "aspx", "jsp", "shtml"];
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let last_segment = path.rsplit('/').next().unwrap_or(path);
+    match last_segment.rfind('.') {
+        Some(idx) => PAGE_EXTENSIONS.contains(&last_segment[idx + 1..].to_lowercase().as_str()),
+        None => true,
+    }
+}
+
+/// Cross-references the URLs known from each source with the crawl: a known URL
+/// on one of the crawled hosts is an orphan when no crawled page links to it (or
+/// to the page it redirects to), self-links aside.
+pub fn find_orphans(pages: &[Value], known: Vec<(String, OrphanSource)>) -> Vec<OrphanPage> {
+    let mut hosts = HashSet::new();
+    let mut linked = HashSet::new();
+    // Normalized URL (requested or final) -> index of the crawled page
+    let mut crawled: HashMap<String, usize> = HashMap::new();
+
+    for (i, page) in pages.iter().enumerate() {
+        let Some(url) = page.get("url").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+        {
+            hosts.insert(host);
+        }
+        let own = normalize_url(url);
+        crawled.entry(own.clone()).or_insert(i);
+        if let Some(original) = page.get("original_url").and_then(|v| v.as_str()) {
+            crawled.entry(normalize_url(original)).or_insert(i);
+        }
+
+        if let Some(internal) = page
+            .pointer("/inoutlinks_status_codes/internal")
+            .and_then(|v| v.as_array())
+        {
+            for link in internal {
+                if let Some(target) = link.get("url").and_then(|v| v.as_str()) {
+                    let target = normalize_url(target);
+                    if target != own {
+                        linked.insert(target);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut candidates: HashMap<String, (String, BTreeSet<OrphanSource>)> = HashMap::new();
+    for (url, source) in known {
+        let Ok(parsed) = Url::parse(&url) else {
+            continue;
+        };
+        if !parsed.host_str().is_some_and(|host| hosts.contains(host)) {
+            continue;
+        }
+        candidates
+            .entry(normalize_url(&url))
+            .or_insert_with(|| (url, BTreeSet::new()))
+            .1
+            .insert(source);
+    }
+
+    let mut orphans: Vec<OrphanPage> = candidates
+        .into_iter()
+        .filter_map(|(normalized, (url, sources))| {
+            let page = crawled.get(&normalized).map(|&i| &pages[i]);
+            let final_url = page
+                .and_then(|p| p.get("url"))
+                .and_then(|v| v.as_str())
+                .map(normalize_url);
+            if linked.contains(&normalized) || final_url.is_some_and(|u| linked.contains(&u)) {
+                return None;
+            }
+            Some(OrphanPage {
+                url,
+                sources: sources.into_iter().collect(),
+                crawled: page.is_some(),
+                status_code: page
+                    .and_then(|p| p.get("status_code"))
+                    .and_then(|v| v.as_u64())
+                    .map(|code| code as u16),
+                indexability: page
+                    .and_then(|p| p.pointer("/indexability/indexability"))
+                    .and_then(|v| v.as_f64())
+                    .map(|v| v as f32),
+                indexability_reason: page
+                    .and_then(|p| p.pointer("/indexability/indexability_reason"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+            })
+        })
+        .collect();
+    orphans.sort_by(|a, b| a.url.cmp(&b.url));
+    orphans
+}
+
+/// Fetches the orphans the crawl never reached to fill in their status code and
+/// indexability. Requests go out the way the crawl's did: through the proxy,
+/// with `auth` for the site's own hosts, and only for URLs robots.txt allows
+/// (when `obey_robots_txt` is on). Redirects aren't followed: the orphan URL
+/// itself is reported.
+pub async fn fetch_uncrawled(
+    orphans: &mut [OrphanPage],
+    origin: &Url,
+    settings: &Settings,
+    auth: Option<CrawlAuth>,
+) -> Result<(), String> {
+    let identity = CrawlIdentity::from_settings(settings);
+    let proxy = ProxyConfig::from_settings(settings)?;
+    let cookie_jar = Arc::new(Jar::default());
+    let mut client_builder = Client::builder();
+    if let Some(proxy) = &proxy {
+        client_builder = proxy.apply(client_builder);
+    }
+    let client = client_builder
+        .cookie_provider(cookie_jar.clone())
+        .user_agent(&identity.user_agent)
+        .default_headers(identity.default_headers())
+        .timeout(Duration::from_secs(settings.client_timeout))
+        .connect_timeout(Duration::from_secs(settings.client_connect_timeout))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let credentials = match auth {
+        Some(auth) if !auth.is_empty() => {
+            let form_login = auth.form_login.clone();
+            let credentials = Arc::new(CrawlCredentials::new(
+                auth,
+                crawl_auth::crawl_hosts(origin),
+                cookie_jar,
+            )?);
+            if let Some(config) = form_login {
+                LoginSession::new(config, identity.clone(), proxy.clone())?
+                    .login(&client, &credentials)
+                    .await?;
+            }
+            Some(credentials)
+        }
+        _ => None,
+    };
+
+    let robots = if settings.obey_robots_txt {
+        robots::get_robots_data(origin, &client)
+            .await
+            .map(|data| data.rules.matcher_for(&identity.robots_token))
+    } else {
+        None
+    };
+
+    let mut pending = Vec::new();
+    for (i, orphan) in orphans.iter_mut().enumerate() {
+        if orphan.crawled {
+            continue;
+        }
+        let block = robots.as_ref().and_then(|robots| {
+            Url::parse(&orphan.url)
+                .ok()
+                .and_then(|url| robots.check(&url))
+        });
+        if let Some(block) = block {
+            orphan.indexability = Some(0.0);
+            orphan.indexability_reason = Some(format!(
+                "Blocked by robots.txt (line {}: {})",
+                block.line, block.rule
+            ));
+            continue;
+        }
+        if pending.len() < MAX_ORPHAN_FETCHES {
+            pending.push((i, orphan.url.clone()));
+        }
+    }
+
+    let results: Vec<_> = stream::iter(pending)
+        .map(|(i, url)| {
+            let client = &client;
+            let credentials = credentials.as_deref();
+            let agent = identity.indexing_agent();
+            async move {
+                let status = fetch_status(client, &url, agent, settings, credentials).await;
+                (i, status)
+            }
+        })
+        .buffer_unordered(settings.concurrent_requests.max(1))
+        .collect()
+        .await;
+
+    for (i, (status_code, indexability, reason)) in results {
+        let orphan = &mut orphans[i];
+        orphan.status_code = status_code;
+        orphan.indexability = Some(indexability);
+        orphan.indexability_reason = Some(reason);
+    }
+    Ok(())
+}
+
+async fn fetch_status(
+    client: &Client,
+    url: &str,
+    agent: &str,
+    settings: &Settings,
+    credentials: Option<&CrawlCredentials>,
+) -> (Option<u16>, f32, String) {
+    let response = match fetch_with_exponential_backoff(client, url, settings, credentials).await {
+        Ok((response, _)) => response,
+        Err(e) => return (None, 0.0, format!("Fetch failed: {}", e)),
+    };
+    let status = response.status();
+    if !status.is_success() {
+        return (
+            Some(status.as_u16()),
+            0.0,
+            format!("Status code {}", status.as_u16()),
+        );
+    }
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    match response.text().await {
+        Ok(body) => {
+            let document = Html::parse_document(&body);
+            let indexability = extract_indexability(&document, &headers, agent);
+            (
+                Some(status.as_u16()),
+                indexability.indexability,
+                indexability.indexability_reason,
+            )
+        }
+        Err(e) => (Some(status.as_u16()), 0.0, format!("Fetch failed: {}", e)),
+    }
+}
+
+/// URLs with Search Console data for the site, if any has been fetched.
+pub fn gsc_urls() -> Vec<String> {
+    let Ok(conn) = crate::crawler::db::open_db_connection("crawl_results.db") else {
+        return Vec::new();
+    };
+    let urls = conn
+        .prepare("SELECT DISTINCT url FROM gsc_data")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, String>(0))
+                .map(|rows| rows.filter_map(Result::ok).collect::<Vec<_>>())
+        });
+    urls.unwrap_or_default()
+}
+
+/// Paths requested in the active log analysis, by any crawler or visitor.
+pub fn log_paths() -> Vec<String> {
+    use crate::loganalyser::active_db::{init_active_db, DB_CONN};
+
+    if init_active_db().is_err() {
+        return Vec::new();
+    }
+    let Ok(lock) = DB_CONN.lock() else {
+        return Vec::new();
+    };
+    let Some(conn) = lock.as_ref() else {
+        return Vec::new();
+    };
+    let paths = conn
+        .prepare("SELECT DISTINCT path FROM active_path_aggregations")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, String>(0))
+                .map(|rows| rows.filter_map(Result::ok).collect::<Vec<_>>())
+        });
+    paths.unwrap_or_default()
+}
+
+/// Tallies the orphans per source.
+pub fn report(orphans: Vec<OrphanPage>) -> OrphanReport {
+    let mut by_source = HashMap::new();
+    for orphan in &orphans {
+        for source in &orphan.sources {
+            *by_source.entry(*source).or_insert(0) += 1;
+        }
+    }
+    OrphanReport {
+        total: orphans.len(),
+        by_source,
+        orphans,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn lists_known_urls_without_inlinks() {
+        let pages = vec![
+            json!({
+                "url": "https://example.com/",
+                "original_url": "https://example.com/",
+                "status_code": 200,
+                "discovery": { "source": "start" },
+                "inoutlinks_status_codes": { "internal": [
+                    { "url": "https://example.com/linked" },
+                    { "url": "https://example.com/" }
+                ] }
+            }),
+            json!({
+                "url": "https://example.com/from-sitemap",
+                "original_url": "https://example.com/from-sitemap",
+                "status_code": 200,
+                "indexability": { "indexability": 1.0, "indexability_reason": "Indexable" },
+                "inoutlinks_status_codes": { "internal": [
+                    { "url": "https://example.com/from-sitemap" }
+                ] }
+            }),
+        ];
+        let origin = site_origin(&pages).unwrap();
+        let known = vec![
+            (
+                "https://example.com/from-sitemap".to_string(),
+                OrphanSource::Sitemap,
+            ),
+            (
+                "https://example.com/linked/".to_string(),
+                OrphanSource::Sitemap,
+            ),
+            ("https://other.example/page".to_string(), OrphanSource::Gsc),
+            (
+                "https://example.com/old-page".to_string(),
+                OrphanSource::Gsc,
+            ),
+            (
+                resolve_path(&origin, "/old-page").unwrap(),
+                OrphanSource::Logs,
+            ),
+        ];
+        assert!(resolve_path(&origin, "/assets/app.js").is_none());
+
+        let orphans = find_orphans(&pages, known);
+        assert_eq!(orphans.len(), 2);
+        assert_eq!(orphans[0].url, "https://example.com/from-sitemap");
+        assert!(orphans[0].crawled);
+        assert_eq!(orphans[0].status_code, Some(200));
+        assert_eq!(orphans[1].url, "https://example.com/old-page");
+        assert_eq!(
+            orphans[1].sources,
+            vec![OrphanSource::Gsc, OrphanSource::Logs]
+        );
+        assert!(!orphans[1].crawled);
+    }
+}
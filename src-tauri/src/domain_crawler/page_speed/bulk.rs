@@ -1,13 +1,16 @@
 use crate::Settings;
 use reqwest::Client;
 use serde_json::Value;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
 use tokio::sync::Mutex;
 use tokio::time::{sleep, timeout, Duration, Instant};
 use url::Url;
 
 use lazy_static::lazy_static;
 
+use crate::domain_crawler::helpers::proxy::ProxyConfig;
+
 pub enum PageSpeedStrategy {
     Mobile,
     Desktop,
@@ -100,16 +103,35 @@ impl CircuitBreaker {
 }
 
 lazy_static! {
-    static ref HTTP_CLIENT: Client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
-        .pool_idle_timeout(Duration::from_secs(30))
-        .build()
-        .expect("Failed to create HTTP client");
+    // One client per proxy setting (see `ProxyConfig::key`, empty for a direct
+    // connection), so connections are reused across pages.
+    static ref HTTP_CLIENTS: StdMutex<HashMap<String, Client>> = StdMutex::new(HashMap::new());
     static ref CIRCUIT_BREAKER: Arc<Mutex<CircuitBreaker>> =
         Arc::new(Mutex::new(CircuitBreaker::new()));
 }
 
+fn http_client(settings: &Settings) -> Client {
+    let proxy = ProxyConfig::configured(settings);
+    let key = proxy.as_ref().map(|p| p.key().to_string()).unwrap_or_default();
+
+    let mut clients = HTTP_CLIENTS.lock().unwrap();
+    clients
+        .entry(key)
+        .or_insert_with(|| {
+            let mut builder = Client::builder();
+            if let Some(proxy) = &proxy {
+                builder = proxy.apply(builder);
+            }
+            builder
+                .timeout(Duration::from_secs(30))
+                .connect_timeout(Duration::from_secs(10))
+                .pool_idle_timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to create HTTP client")
+        })
+        .clone()
+}
+
 // Validation function to check if a URL should be analyzed with PSI
 fn should_analyze_with_psi(url: &Url) -> bool {
     // Skip non-HTTP/HTTPS URLs
@@ -261,6 +283,7 @@ fn should_analyze_with_psi(url: &Url) -> bool {
 }
 
 async fn fetch_psi_with_retry(
+    client: &Client,
     url: &str,
     strategy_str: &str,
     api_key: &str,
@@ -327,7 +350,7 @@ async fn fetch_psi_with_retry(
             sleep(delay).await;
         }
 
-        let response = match HTTP_CLIENT.get(&api_url).send().await {
+        let response = match client.get(&api_url).send().await {
             Ok(resp) => resp,
             Err(e) => {
                 last_error = format!("Request failed: {}", e);
@@ -513,6 +536,7 @@ pub async fn fetch_psi_bulk(url: Url, settings: &Settings) -> Result<Vec<Value>,
         }
     }
 
+    let client = http_client(settings);
     let max_retries = 3; // Retry a few times to handle API flakesg API
     let mut results = Vec::new();
     let mut errors = Vec::new();
@@ -531,7 +555,7 @@ pub async fn fetch_psi_bulk(url: Url, settings: &Settings) -> Result<Vec<Value>,
 
         let result = timeout(
             Duration::from_secs(45),
-            fetch_psi_with_retry(&url.to_string(), strategy_str, api_key, max_retries),
+            fetch_psi_with_retry(&client, &url.to_string(), strategy_str, api_key, max_retries),
         )
         .await;
 
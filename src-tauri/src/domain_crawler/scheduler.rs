@@ -0,0 +1,516 @@
+//! Recurring scheduled crawls. Job definitions (domain, per-crawl overrides and a
+//! cron schedule) live in `deep_crawl.db` next to the `crawl_projects` registry.
+//! While the app is open a background task starts due jobs one at a time; every
+//! run is stored as its own crawl project and logged in the job's run history.
+//!
+//! Schedules use standard 5-field cron syntax (`minute hour day month weekday`,
+//! weekday 0-7 with 0 and 7 = Sunday, or names like `MON-FRI`), or one of
+//! `@hourly`, `@daily`, `@weekly`, `@monthly`, `@yearly`. Jobs that came due while
+//! the app was closed run once when it starts.
+//!
+//! Jobs can't carry crawl credentials: they would have to be stored in plain text
+//! and nobody is around to enter them when the job runs.
+
+use chrono::{DateTime, Local};
+use cron::Schedule;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use super::crawl_overrides::CrawlOverrides;
+use super::crawl_projects;
+use super::db_deep::db::open_domain_db_connection;
+use super::domain_commands;
+use super::domain_crawler;
+use crate::AppState;
+
+/// How often the background task looks for due jobs.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlJob {
+    pub id: String,
+    pub name: String,
+    pub domain: String,
+    pub schedule: String,
+    pub overrides: Option<CrawlOverrides>,
+    pub enabled: bool,
+    pub created_at: String,
+    pub last_run_at: Option<String>,
+    /// `None` when the job is disabled
+    pub next_run_at: Option<String>,
+}
+
+/// A new job (no `id`) or changes to an existing one.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CrawlJobInput {
+    pub id: Option<String>,
+    pub name: String,
+    pub domain: String,
+    pub schedule: String,
+    pub overrides: Option<CrawlOverrides>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlJobRun {
+    pub id: i64,
+    pub job_id: String,
+    /// The stored crawl (see `crawl_projects`), when one was created
+    pub crawl_id: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// running, completed, stopped or failed
+    pub status: String,
+    pub pages: i64,
+    pub error: Option<String>,
+}
+
+fn open_jobs_db() -> Result<Connection, String> {
+    let conn = open_domain_db_connection("deep_crawl.db").map_err(|e| e.to_string())?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS crawl_jobs (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            domain TEXT NOT NULL,
+            schedule TEXT NOT NULL,
+            overrides TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL,
+            last_run_at TEXT,
+            next_run_at TEXT
+        );
+        CREATE TABLE IF NOT EXISTS crawl_job_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            job_id TEXT NOT NULL,
+            crawl_id TEXT,
+            started_at TEXT NOT NULL,
+            finished_at TEXT,
+            status TEXT NOT NULL,
+            pages INTEGER NOT NULL DEFAULT 0,
+            error TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_crawl_job_runs_job ON crawl_job_runs (job_id, started_at);",
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+const JOB_COLUMNS: &str =
+    "id, name, domain, schedule, overrides, enabled, created_at, last_run_at, next_run_at";
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<CrawlJob> {
+    // A job whose overrides don't parse must not run with the defaults instead
+    // (no scope, auth or proxy)
+    let overrides = row
+        .get::<_, Option<String>>(4)?
+        .map(|o| serde_json::from_str(&o))
+        .transpose()
+        .map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+    Ok(CrawlJob {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        domain: row.get(2)?,
+        schedule: row.get(3)?,
+        overrides,
+        enabled: row.get(5)?,
+        created_at: row.get(6)?,
+        last_run_at: row.get(7)?,
+        next_run_at: row.get(8)?,
+    })
+}
+
+/// Parses a 5-field cron expression (or `@daily` etc.) for the `cron` crate,
+/// which wants a seconds field and numbers weekdays from Sunday = 1.
+fn parse_schedule(expr: &str) -> Result<Schedule, String> {
+    let expr = expr.trim();
+    let invalid = |e: &dyn std::fmt::Display| format!("Invalid schedule '{}': {}", expr, e);
+
+    if expr.starts_with('@') {
+        return Schedule::from_str(expr).map_err(|e| invalid(&e));
+    }
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(invalid(
+            &"expected 5 fields (minute hour day month weekday)",
+        ));
+    }
+
+    let mut weekday = String::new();
+    let mut digits = String::new();
+    for c in fields[4].chars().chain(std::iter::once('\0')) {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if !digits.is_empty() {
+            // Digits after `/` are a step, not a weekday
+            if weekday.ends_with('/') {
+                weekday.push_str(&digits);
+            } else {
+                let day: usize = digits.parse().map_err(|_| invalid(&"bad weekday"))?;
+                if day > 7 {
+                    return Err(invalid(&"weekday must be 0-7"));
+                }
+                weekday.push_str(WEEKDAYS[day % 7]);
+            }
+            digits.clear();
+        }
+        if c != '\0' {
+            weekday.push(c);
+        }
+    }
+
+    let full = format!(
+        "0 {} {} {} {} {}",
+        fields[0], fields[1], fields[2], fields[3], weekday
+    );
+    Schedule::from_str(&full).map_err(|e| invalid(&e))
+}
+
+fn next_run_after(schedule: &str, after: &DateTime<Local>) -> Result<Option<String>, String> {
+    Ok(parse_schedule(schedule)?
+        .after(after)
+        .next()
+        .map(|t| t.to_rfc3339()))
+}
+
+#[tauri::command]
+pub fn list_crawl_jobs_command() -> Result<Vec<CrawlJob>, String> {
+    let conn = open_jobs_db()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM crawl_jobs ORDER BY name",
+            JOB_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_job).map_err(|e| e.to_string())?;
+
+    let mut jobs = Vec::new();
+    for row in rows {
+        jobs.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(jobs)
+}
+
+/// Creates a job, or updates the one with `job.id`. The next run is computed from
+/// now, so editing a job's schedule takes effect immediately.
+#[tauri::command]
+pub fn save_crawl_job_command(job: CrawlJobInput) -> Result<CrawlJob, String> {
+    let domain = job.domain.trim();
+    if domain.is_empty() {
+        return Err("A scheduled crawl needs a domain".to_string());
+    }
+    let now = Local::now();
+    let next_run_at = next_run_after(&job.schedule, &now)?.filter(|_| job.enabled);
+    let name = match job.name.trim() {
+        "" => domain.to_string(),
+        name => name.to_string(),
+    };
+    if job
+        .overrides
+        .as_ref()
+        .and_then(|o| o.auth.as_ref())
+        .is_some_and(|auth| !auth.is_empty())
+    {
+        return Err(
+            "Scheduled crawls can't use login credentials, as they would have to be stored"
+                .to_string(),
+        );
+    }
+    let overrides = job
+        .overrides
+        .as_ref()
+        .map(|o| serde_json::to_string(&o.without_secrets()).map_err(|e| e.to_string()))
+        .transpose()?;
+
+    let conn = open_jobs_db()?;
+    let id = match job.id {
+        Some(id) => {
+            let updated = conn
+                .execute(
+                    "UPDATE crawl_jobs SET name = ?1, domain = ?2, schedule = ?3, overrides = ?4,
+                     enabled = ?5, next_run_at = ?6 WHERE id = ?7",
+                    params![
+                        name,
+                        domain,
+                        job.schedule.trim(),
+                        overrides,
+                        job.enabled,
+                        next_run_at,
+                        id
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+            if updated == 0 {
+                return Err(format!("Scheduled crawl {} not found", id));
+            }
+            id
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().simple().to_string();
+            conn.execute(
+                "INSERT INTO crawl_jobs (id, name, domain, schedule, overrides, enabled, created_at, next_run_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![id, name, domain, job.schedule.trim(), overrides, job.enabled, now.to_rfc3339(), next_run_at],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+    };
+
+    get_job(&conn, &id)?.ok_or_else(|| format!("Scheduled crawl {} not found", id))
+}
+
+/// Deletes a job and its run history. Crawls it already ran are kept.
+#[tauri::command]
+pub fn delete_crawl_job_command(job_id: String) -> Result<(), String> {
+    let conn = open_jobs_db()?;
+    conn.execute(
+        "DELETE FROM crawl_job_runs WHERE job_id = ?1",
+        params![job_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM crawl_jobs WHERE id = ?1", params![job_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// A job's runs, most recent first.
+#[tauri::command]
+pub fn get_crawl_job_runs_command(
+    job_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<CrawlJobRun>, String> {
+    let conn = open_jobs_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, job_id, crawl_id, started_at, finished_at, status, pages, error
+             FROM crawl_job_runs WHERE job_id = ?1 ORDER BY started_at DESC LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![job_id, limit.unwrap_or(50)], |row| {
+            Ok(CrawlJobRun {
+                id: row.get(0)?,
+                job_id: row.get(1)?,
+                crawl_id: row.get(2)?,
+                started_at: row.get(3)?,
+                finished_at: row.get(4)?,
+                status: row.get(5)?,
+                pages: row.get(6)?,
+                error: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut runs = Vec::new();
+    for row in rows {
+        runs.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(runs)
+}
+
+fn get_job(conn: &Connection, id: &str) -> Result<Option<CrawlJob>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM crawl_jobs WHERE id = ?1", JOB_COLUMNS),
+        params![id],
+        row_to_job,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn due_jobs(now: &DateTime<Local>) -> Result<Vec<CrawlJob>, String> {
+    // RFC 3339 strings from different offsets don't compare as text, so filter here
+    Ok(list_crawl_jobs_command()?
+        .into_iter()
+        .filter(|job| job.enabled)
+        .filter(|job| {
+            job.next_run_at
+                .as_deref()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| t <= *now)
+        })
+        .collect())
+}
+
+/// Starts the background task that runs due jobs. Called once from app setup.
+pub fn start(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            // Never start on top of a crawl (scheduled or manual); the job stays
+            // due and runs once that crawl is done.
+            if crawl_projects::crawl_in_progress() {
+                continue;
+            }
+            let jobs = match due_jobs(&Local::now()) {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    tracing::error!("Failed to load scheduled crawls: {}", e);
+                    continue;
+                }
+            };
+            if let Some(job) = jobs.into_iter().next() {
+                run_job(&app_handle, job).await;
+            }
+        }
+    });
+}
+
+async fn run_job(app_handle: &tauri::AppHandle, job: CrawlJob) {
+    let started = Local::now();
+    let conn = match open_jobs_db() {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::error!("Failed to open scheduled crawls: {}", e);
+            return;
+        }
+    };
+
+    // Schedule the next run up front so a crash mid-crawl doesn't re-run the job
+    // in a loop on every start.
+    let next_run_at = next_run_after(&job.schedule, &started).unwrap_or_else(|e| {
+        tracing::error!("Scheduled crawl {}: {}", job.name, e);
+        None
+    });
+    let run_id = conn
+        .execute(
+            "UPDATE crawl_jobs SET last_run_at = ?1, next_run_at = ?2 WHERE id = ?3",
+            params![started.to_rfc3339(), next_run_at, job.id],
+        )
+        .and_then(|_| {
+            conn.execute(
+                "INSERT INTO crawl_job_runs (job_id, started_at, status) VALUES (?1, ?2, 'running')",
+                params![job.id, started.to_rfc3339()],
+            )
+        })
+        .map(|_| conn.last_insert_rowid());
+    let run_id = match run_id {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to record scheduled crawl {}: {}", job.name, e);
+            return;
+        }
+    };
+
+    tracing::info!("Starting scheduled crawl {} ({})", job.name, job.domain);
+    let _ = app_handle.emit("scheduled_crawl_started", (&job.id, &job.domain));
+
+    let settings_state = app_handle.state::<AppState>();
+    let (crawl_id, result) = match domain_commands::prepare_crawl_db(
+        &job.domain,
+        &settings_state,
+        job.overrides.as_ref(),
+    )
+    .await
+    {
+        Ok((project, db)) => {
+            let result = domain_crawler::crawl_domain(
+                &job.domain,
+                app_handle.clone(),
+                Ok(db.clone()),
+                app_handle.state::<AppState>(),
+                job.overrides.clone(),
+            )
+            .await;
+            let result = domain_commands::finish_crawl(&project.id, &db, result).await;
+            (Some(project.id), result)
+        }
+        Err(e) => (None, Err(e)),
+    };
+
+    let project = crawl_id
+        .as_deref()
+        .and_then(|id| crawl_projects::get_project(id).ok().flatten());
+    let (status, pages) = match (&result, &project) {
+        (Err(_), _) => (
+            "failed".to_string(),
+            project.as_ref().map_or(0, |p| p.pages),
+        ),
+        (Ok(_), Some(project)) => (project.status.clone(), project.pages),
+        (Ok(_), None) => ("completed".to_string(), 0),
+    };
+    if let Err(e) = conn.execute(
+        "UPDATE crawl_job_runs SET crawl_id = ?1, finished_at = ?2, status = ?3, pages = ?4, error = ?5
+         WHERE id = ?6",
+        params![
+            crawl_id,
+            Local::now().to_rfc3339(),
+            status,
+            pages,
+            result.as_ref().err(),
+            run_id
+        ],
+    ) {
+        tracing::error!("Failed to record scheduled crawl {}: {}", job.name, e);
+    }
+
+    tracing::info!("Scheduled crawl {} finished: {}", job.name, status);
+    let _ = app_handle.emit("scheduled_crawl_finished", (&job.id, &status));
+}
+
+/// Marks runs left `running` by an app that quit mid-crawl as failed.
+pub fn close_interrupted_runs() -> Result<(), String> {
+    let conn = open_jobs_db()?;
+    conn.execute(
+        "UPDATE crawl_job_runs SET status = 'failed', error = 'The app closed during the crawl'
+         WHERE status = 'running'",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, TimeZone, Timelike, Weekday};
+
+    #[test]
+    fn standard_cron_weekdays() {
+        // Sunday 2026-10-18 08:00
+        let now = Local.with_ymd_and_hms(2026, 10, 18, 8, 0, 0).unwrap();
+        let next = |expr: &str| parse_schedule(expr).unwrap().after(&now).next().unwrap();
+
+        let monday = next("0 9 * * 1");
+        assert_eq!(monday.weekday(), Weekday::Mon);
+        assert_eq!(monday.hour(), 9);
+        assert_eq!(next("30 6 * * MON-FRI").weekday(), Weekday::Mon);
+        assert_eq!(next("0 9 * * 0").weekday(), Weekday::Sun);
+        assert_eq!(next("0 9 * * 7").weekday(), Weekday::Sun);
+        assert_eq!(next("0 9 * * */2").weekday(), Weekday::Sun);
+        assert_eq!(next("@weekly").weekday(), Weekday::Sun);
+
+        assert!(parse_schedule("0 9 * *").is_err());
+        assert!(parse_schedule("0 9 * * 8").is_err());
+    }
+
+    #[test]
+    fn unreadable_overrides_fail_the_job() {
+        let conn = Connection::open_in_memory().unwrap();
+        let job = |overrides: &str| {
+            conn.query_row(
+                "SELECT 'id', 'name', 'example.com', '@daily', ?1, 1, 'now', NULL, NULL",
+                params![overrides],
+                row_to_job,
+            )
+        };
+
+        assert!(job("{}").unwrap().overrides.is_some());
+        assert!(job("not json").is_err());
+    }
+}
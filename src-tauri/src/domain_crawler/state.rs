@@ -8,11 +8,22 @@ use std::time::Instant;
 use tokio::sync::Mutex;
 use url::Url;
 
+use super::checkpoint::FrontierSnapshot;
 use super::constants::MAX_PENDING_TIME;
 use super::database::{Database, DatabaseResults};
+use super::helpers::browser_pool::BrowserPool;
+use super::helpers::crawl_auth::CrawlCredentials;
+use super::helpers::crawl_budget::CrawlBudgets;
+use super::helpers::form_login::LoginSession;
+use super::helpers::url_rewrite::UrlRewriter;
 use super::helpers::links_status_code_checker::SharedLinkChecker;
-use super::models::DomainCrawlResults;
+use super::helpers::mobile_parity::ParityChecker;
+use super::models::{Discovery, DiscoverySource, DomainCrawlResults};
 use super::helpers::normalize_url::normalize_url;
+use super::helpers::resources::ResourceCollector;
+use super::helpers::robots::{RobotsBlock, RobotsMatcher};
+use super::helpers::spider_trap::TrapDetector;
+use super::helpers::url_scope::UrlScope;
 
 /// Maximum number of failed URLs to retain. Once this cap is hit the oldest
 /// failures are silently discarded to prevent the set from consuming memory
@@ -23,6 +34,17 @@ const MAX_FAILED_URLS: usize = 10_000;
 /// infinite URL traps; beyond this cap new patterns are simply not tracked.
 const MAX_URL_PATTERNS: usize = 20_000;
 
+/// Maximum number of robots.txt-blocked URLs to retain for the end-of-crawl report.
+const MAX_ROBOTS_BLOCKED: usize = 50_000;
+
+/// Maximum number of scope-excluded URLs to retain for the end-of-crawl report.
+/// `scope_excluded_count` keeps counting past this cap.
+const MAX_SCOPE_EXCLUDED: usize = 50_000;
+
+/// Maximum number of budget-skipped URLs remembered for deduplication. Past
+/// this cap a URL seen on several pages is counted as skipped more than once.
+const MAX_BUDGET_SKIPPED: usize = 50_000;
+
 /// Track failed URLs and retries
 #[derive(Clone, Hash, Eq, PartialEq)]
 pub struct FailedUrl {
@@ -82,6 +104,57 @@ pub struct CrawlerState {
     /// Uses DashMap (lock-free concurrent hashmap) to avoid blocking the async executor
     /// under high concurrency (many simultaneous inserts from 50+ tasks).
     pub url_status_registry: Arc<DashMap<String, u16>>,
+    /// robots.txt rules selected for the crawl's user agent. `None` when robots.txt
+    /// is missing or `obey_robots_txt` is off, in which case every URL is allowed.
+    pub robots: Option<Arc<RobotsMatcher>>,
+    /// URLs that were discovered but not queued because robots.txt disallows them,
+    /// with the rule responsible. Reported as result rows once the crawl finishes.
+    pub robots_blocked: HashMap<String, RobotsBlock>,
+    /// Include/exclude rules from Settings (or the per-crawl overrides). `None` when
+    /// no rules are configured.
+    pub scope: Option<Arc<UrlScope>>,
+    /// URLs kept out of the crawl by `scope`, keyed by normalized URL, with the rule
+    /// that excluded them and the page they were first found on.
+    pub scope_excluded: HashMap<String, (String, String)>,
+    /// Number of unique URLs excluded by `scope`, including those past the retention cap.
+    pub scope_excluded_count: usize,
+    /// Per path prefix / URL pattern caps (see `crawl_budget`). `None` when no
+    /// budgets are configured.
+    pub budgets: Option<CrawlBudgets>,
+    /// URLs not queued because their budget was used up, so each is counted once.
+    pub budget_skipped: HashSet<String>,
+    /// How each queued URL was found and the page it was first found on, keyed
+    /// by normalized URL. Taken once the URL has been fetched.
+    pub discovered: HashMap<String, (DiscoverySource, Option<String>)>,
+    /// Suspected spider traps among the queued URLs (see `spider_trap`)
+    pub spider_traps: TrapDetector,
+    /// Non-HTML resources referenced by the crawled pages (see `resources`).
+    /// `None` unless `crawl_resources` is on.
+    pub resources: Option<ResourceCollector>,
+    /// Whether links found on crawled pages are queued. Off in list mode, where only
+    /// the supplied URLs are fetched.
+    pub spidering: bool,
+    /// URLs handed to a fetch task, with their depth. Written to checkpoints
+    /// alongside `queue` so in-flight URLs are fetched again after a resume.
+    pub in_flight: HashMap<String, usize>,
+    /// Whether visited URLs are journaled for checkpointing.
+    pub checkpointing: bool,
+    /// URLs added to `visited` since the last checkpoint (only when `checkpointing`).
+    pub visited_journal: Vec<String>,
+    /// Auth for the crawl's own hosts (see `crawl_auth`), used by page fetches and
+    /// headless rendering. `None` when the crawl has no credentials.
+    pub credentials: Option<Arc<CrawlCredentials>>,
+    /// Form login (see `form_login`), used to log in again when the session
+    /// expires mid-crawl.
+    pub login_session: Option<Arc<LoginSession>>,
+    /// Rewrite rules for discovered URLs (see `url_rewrite`). `None` when there are none.
+    pub url_rewriter: Option<Arc<UrlRewriter>>,
+    /// Headless Chrome browsers reused across JS renders. `None` unless
+    /// `javascript_rendering` is on.
+    pub browser_pool: Option<Arc<BrowserPool>>,
+    /// Fetches pages as the other device for `mobile_parity_check`. `None` when
+    /// the check is off.
+    pub parity_checker: Option<Arc<ParityChecker>>,
 }
 
 impl CrawlerState {
@@ -106,6 +179,25 @@ impl CrawlerState {
             pending_results: Vec::with_capacity(64),
             last_cleanup: Instant::now(),
             url_status_registry: Arc::new(DashMap::with_capacity(4096)),
+            robots: None,
+            robots_blocked: HashMap::new(),
+            scope: None,
+            scope_excluded: HashMap::new(),
+            scope_excluded_count: 0,
+            budgets: None,
+            budget_skipped: HashSet::new(),
+            discovered: HashMap::new(),
+            spider_traps: TrapDetector::new(false, usize::MAX),
+            resources: None,
+            spidering: true,
+            in_flight: HashMap::new(),
+            checkpointing: false,
+            visited_journal: Vec::new(),
+            credentials: None,
+            login_session: None,
+            url_rewriter: None,
+            browser_pool: None,
+            parity_checker: None,
         }
     }
 
@@ -119,6 +211,232 @@ impl CrawlerState {
         self
     }
 
+    pub fn with_robots(mut self, robots: Option<Arc<RobotsMatcher>>) -> Self {
+        self.robots = robots;
+        self
+    }
+
+    pub fn with_credentials(mut self, credentials: Option<Arc<CrawlCredentials>>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    pub fn with_login_session(mut self, login_session: Option<Arc<LoginSession>>) -> Self {
+        self.login_session = login_session;
+        self
+    }
+
+    pub fn with_url_rewriter(mut self, url_rewriter: Option<Arc<UrlRewriter>>) -> Self {
+        self.url_rewriter = url_rewriter;
+        self
+    }
+
+    pub fn with_browser_pool(mut self, browser_pool: Option<Arc<BrowserPool>>) -> Self {
+        self.browser_pool = browser_pool;
+        self
+    }
+
+    pub fn with_parity_checker(mut self, parity_checker: Option<Arc<ParityChecker>>) -> Self {
+        self.parity_checker = parity_checker;
+        self
+    }
+
+    pub fn with_scope(mut self, scope: Option<Arc<UrlScope>>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn with_budgets(mut self, budgets: Option<CrawlBudgets>) -> Self {
+        self.budgets = budgets;
+        self
+    }
+
+    pub fn with_spider_traps(mut self, spider_traps: TrapDetector) -> Self {
+        self.spider_traps = spider_traps;
+        self
+    }
+
+    pub fn with_resources(mut self, resources: Option<ResourceCollector>) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    pub fn with_spidering(mut self, spidering: bool) -> Self {
+        self.spidering = spidering;
+        self
+    }
+
+    pub fn with_checkpointing(mut self, checkpointing: bool) -> Self {
+        self.checkpointing = checkpointing;
+        self
+    }
+
+    /// Marks a normalized URL as visited, journaling it for the next checkpoint.
+    /// Returns `true` when the URL had not been visited before.
+    pub fn mark_visited(&mut self, normalized_url: &str) -> bool {
+        let is_new = self.visited.insert(normalized_url.to_string());
+        if is_new && self.checkpointing {
+            self.visited_journal.push(normalized_url.to_string());
+        }
+        is_new
+    }
+
+    /// Captures the frontier (queued + in-flight URLs) and the visited URLs added
+    /// since the previous checkpoint.
+    pub fn checkpoint_snapshot(&mut self) -> FrontierSnapshot {
+        let mut frontier: Vec<(String, usize)> = self
+            .queue
+            .iter()
+            .map(|(url, depth)| (url.to_string(), *depth))
+            .collect();
+        frontier.extend(self.in_flight.iter().map(|(url, depth)| (url.clone(), *depth)));
+
+        FrontierSnapshot {
+            frontier,
+            new_visited: std::mem::take(&mut self.visited_journal),
+            total_urls: self.total_urls,
+            crawled_urls: self.crawled_urls,
+            failed_urls: self.total_failed_count,
+        }
+    }
+
+    /// Reloads a checkpointed frontier and visited set into a fresh state.
+    pub fn restore_checkpoint(
+        &mut self,
+        frontier: Vec<(String, usize)>,
+        visited: Vec<String>,
+        crawled_urls: usize,
+        failed_urls: usize,
+    ) {
+        if let Some(budgets) = self.budgets.as_mut() {
+            for url in visited.iter().filter_map(|v| Url::parse(v).ok()) {
+                budgets.charge(&url);
+            }
+        }
+        self.visited.extend(visited);
+        for (url_str, depth) in frontier {
+            let normalized = normalize_url(&url_str);
+            if self.visited.contains(&normalized) || self.queued_url_set.contains(&normalized) {
+                continue;
+            }
+            if let Ok(url) = Url::parse(&normalized) {
+                if let Some(budgets) = self.budgets.as_mut() {
+                    budgets.charge(&url);
+                }
+                self.queue.push_back((url, depth));
+                self.queued_url_set.insert(normalized);
+            }
+        }
+        self.crawled_urls = crawled_urls;
+        self.total_failed_count = failed_urls;
+        self.total_urls = crawled_urls + failed_urls + self.queue.len();
+    }
+
+    /// Remembers how a queued URL was found. The first source wins.
+    pub fn note_discovery(
+        &mut self,
+        normalized_url: &str,
+        source: DiscoverySource,
+        referrer: Option<&str>,
+    ) {
+        self.discovered
+            .entry(normalized_url.to_string())
+            .or_insert_with(|| (source, referrer.map(String::from)));
+    }
+
+    /// How a fetched URL was discovered. A redirect target that was queued on
+    /// its own keeps its own source; otherwise it inherits the referrer of the
+    /// URL that redirected to it.
+    pub fn take_discovery(
+        &mut self,
+        requested_url: &str,
+        final_url: &str,
+        crawl_depth: usize,
+    ) -> Option<Discovery> {
+        let requested = self.discovered.remove(requested_url);
+        let (source, referrer) = if requested_url == final_url {
+            requested?
+        } else if let Some((source, referrer)) = self.discovered.get(final_url) {
+            (*source, referrer.clone())
+        } else {
+            (DiscoverySource::Redirect, requested?.1)
+        };
+        Some(Discovery {
+            source,
+            referrer,
+            crawl_depth,
+        })
+    }
+
+    /// Drops the discovery noted for a URL whose fetch failed, so it isn't kept
+    /// for the rest of the crawl.
+    pub fn forget_discovery(&mut self, normalized_url: &str) {
+        self.discovered.remove(normalized_url);
+    }
+
+    /// Returns `true` when the include/exclude rules allow crawling `normalized_url`.
+    /// Excluded URLs are counted once each and remembered with the rule and the
+    /// page they were found on, for the `excluded_urls` report.
+    pub fn scope_allows(&mut self, normalized_url: &str, found_on: &str) -> bool {
+        let Some(scope) = self.scope.as_ref() else {
+            return true;
+        };
+        match scope.check(normalized_url) {
+            None => true,
+            Some(rule) => {
+                if !self.scope_excluded.contains_key(normalized_url) {
+                    self.scope_excluded_count += 1;
+                    if self.scope_excluded.len() < MAX_SCOPE_EXCLUDED {
+                        self.scope_excluded
+                            .insert(normalized_url.to_string(), (rule, found_on.to_string()));
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    /// Returns `true` when the crawl budgets have room for `url`, charging it to
+    /// them. Must be the last check before the URL is queued. URLs over budget
+    /// are counted once each against the bucket that was full.
+    pub fn budget_allows(&mut self, url: &Url, normalized_url: &str) -> bool {
+        let Some(budgets) = self.budgets.as_mut() else {
+            return true;
+        };
+        match budgets.take(url) {
+            None => true,
+            Some(bucket) => {
+                if !self.budget_skipped.contains(normalized_url) {
+                    budgets.skip(&bucket);
+                    if self.budget_skipped.len() < MAX_BUDGET_SKIPPED {
+                        self.budget_skipped.insert(normalized_url.to_string());
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    /// Returns `true` when robots.txt allows crawling `url`. Disallowed URLs are
+    /// remembered in `robots_blocked` (keyed by their normalized form) so they
+    /// show up in the results instead of silently disappearing.
+    pub fn robots_allows(&mut self, url: &Url, normalized_url: &str) -> bool {
+        let Some(robots) = self.robots.as_ref() else {
+            return true;
+        };
+        match robots.check(url) {
+            None => true,
+            Some(block) => {
+                if self.robots_blocked.len() < MAX_ROBOTS_BLOCKED {
+                    self.robots_blocked
+                        .entry(normalized_url.to_string())
+                        .or_insert(block);
+                }
+                false
+            }
+        }
+    }
+
     /// Record a failed URL. Always increments `total_failed_count` even though
     /// the `failed_urls` set is periodically truncated to cap memory usage.
     /// Use this instead of inserting into `failed_urls` directly.
@@ -200,8 +518,11 @@ impl CrawlerState {
     /// Add multiple discovered URLs to the queue if they are new
     pub fn add_discovered_urls(&mut self, urls: HashSet<String>, base_url: &Url, _max_depth: usize, max_urls: usize) {
         for url_str in urls {
-            // Normalize before any checks or queueing
-            let normalized_url = normalize_url(&url_str);
+            // Rewrite and normalize before any checks or queueing
+            let normalized_url = match &self.url_rewriter {
+                Some(rewriter) => normalize_url(&rewriter.rewrite(&url_str)),
+                None => normalize_url(&url_str),
+            };
 
             if let Ok(url) = Url::parse(&normalized_url) {
                 // Basic validation: same domain check
@@ -215,7 +536,11 @@ impl CrawlerState {
                     && !self.queued_url_set.contains(&normalized_url)
                     && !self.pending_urls.contains_key(&normalized_url)
                     && self.total_urls < max_urls
+                    && self.scope_allows(&normalized_url, "sitemap")
+                    && self.robots_allows(&url, &normalized_url)
+                    && self.budget_allows(&url, &normalized_url)
                 {
+                    self.note_discovery(&normalized_url, DiscoverySource::Sitemap, None);
                     self.queue.push_back((url.clone(), 0)); // Sitemaps seed at depth 0
                     self.queued_url_set.insert(normalized_url.clone());
                     self.total_urls += 1;
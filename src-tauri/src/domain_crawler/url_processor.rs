@@ -3,6 +3,7 @@
 use reqwest::Client;
 use scraper::Html;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 use tauri::Emitter;
@@ -17,20 +18,34 @@ use crate::domain_crawler::helpers::extract_url_pattern::extract_url_pattern;
 use crate::domain_crawler::helpers::fetch_with_exponential::fetch_with_exponential_backoff;
 use crate::domain_crawler::helpers::https_checker::valid_https;
 use crate::domain_crawler::helpers::normalize_url::normalize_url;
-use crate::domain_crawler::helpers::skip_url::should_skip_url;
+use crate::domain_crawler::helpers::browser_pool::BrowserPool;
+use crate::domain_crawler::helpers::crawl_auth::CrawlCredentials;
+use crate::domain_crawler::helpers::headless_fetch::{JsWait, RenderTiming};
+use crate::domain_crawler::helpers::mobile_parity::{ParityChecker, ParityReport, ProfileSnapshot};
+use crate::domain_crawler::helpers::pdf_document::{self, PdfDocument};
+use crate::domain_crawler::helpers::render_diff::{PageSignals, RenderDiff};
+use crate::domain_crawler::helpers::resources::ResourceKind;
+use crate::domain_crawler::helpers::skip_url::{is_pdf_url, should_skip_url};
+use crate::domain_crawler::helpers::spider_trap::{self, TrapSignature};
+use crate::domain_crawler::helpers::url_rewrite::UrlRewriter;
 use crate::domain_crawler::helpers::{headless_fetch, opengraph, url_depth};
 use crate::settings::settings::Settings;
 
 use super::helpers::canonical_selector::get_canonical;
 use super::helpers::cross_origin::analyze_cross_origin_security;
+use super::helpers::security_headers;
 use super::helpers::flesch_reader::get_flesch_score;
 use super::helpers::hreflang_selector::select_hreflang;
 use super::helpers::html_size_calculator::calculate_html_size;
 use super::helpers::keyword_selector::extract_keywords;
 use super::helpers::language_selector::detect_language;
-use super::helpers::links_status_code_checker::get_links_status_code_from_settings;
+use super::helpers::anchor_links::InternalExternalLinks;
+use super::helpers::links_status_code_checker::{
+    get_links_status_code_from_settings, LinkCheckResults, SharedLinkChecker,
+};
 use super::helpers::meta_robots_selector::{get_meta_robots, MetaRobots};
 use super::helpers::text_ratio::{get_text_ratio, TextRatio};
+use super::helpers::title_selector::TitleDetails;
 use super::helpers::{
     alt_tags, anchor_links, check_html_page,
     content_signature::compute_content_signature, css_selector, headings_selector,
@@ -38,9 +53,10 @@ use super::helpers::{
     mobile_checker, ngrams, page_description, schema_selector, title_selector,
     word_count::get_word_count,
 };
-use super::models::DomainCrawlResults;
+use super::models::{DiscoverySource, DomainCrawlResults};
 use super::page_speed::bulk::fetch_psi_bulk;
 use super::state::{CrawlResultData, CrawlerState, FailedUrl, ProgressData};
+use super::user_agents::CrawlIdentity;
 
 /// Process a single URL and extract all relevant data
 pub async fn process_url(
@@ -55,10 +71,17 @@ pub async fn process_url(
     image_semaphore: Arc<Semaphore>,
 ) -> Result<DomainCrawlResults, String> {
     // Grab the global URL status registry early so we can record our results later.
-    // This brief lock just clones the Arc, then drops the state lock immediately.
-    let url_status_registry = {
+    // This brief lock just clones the Arcs, then drops the state lock immediately.
+    let (url_status_registry, credentials, login_session, url_rewriter, browser_pool, parity_checker) = {
         let state_guard = state.lock().await;
-        state_guard.url_status_registry.clone()
+        (
+            state_guard.url_status_registry.clone(),
+            state_guard.credentials.clone(),
+            state_guard.login_session.clone(),
+            state_guard.url_rewriter.clone(),
+            state_guard.browser_pool.clone(),
+            state_guard.parity_checker.clone(),
+        )
     };
 
     let mut current_url = url.clone();
@@ -68,12 +91,19 @@ pub async fn process_url(
     let mut redirection_type = None;
     let mut final_response = None;
     let mut total_time = 0.0;
+    let mut relogged_in = false;
 
     // Follow redirects manually to track the chain
     while redirect_count < 10 {
+        let login_generation = login_session.as_ref().map_or(0, |s| s.generation());
         let response_result = tokio::time::timeout(
             Duration::from_secs(settings.client_timeout),
-            fetch_with_exponential_backoff(client, current_url.as_str(), settings),
+            fetch_with_exponential_backoff(
+                client,
+                current_url.as_str(),
+                settings,
+                credentials.as_deref(),
+            ),
         )
         .await;
 
@@ -98,6 +128,26 @@ pub async fn process_url(
                         if let Ok(location_str) = location.to_str() {
                             match current_url.join(location_str) {
                                 Ok(next_url) => {
+                                    // A redirect to the login page means the session
+                                    // expired: log in again and refetch this hop once.
+                                    if let (Some(session), Some(credentials)) =
+                                        (&login_session, &credentials)
+                                    {
+                                        if session.is_login_redirect(&current_url, &next_url) {
+                                            if !relogged_in
+                                                && session
+                                                    .relogin(login_generation, client, credentials)
+                                                    .await
+                                            {
+                                                relogged_in = true;
+                                                redirect_chain.pop();
+                                                continue;
+                                            }
+                                            let _ = app_handle
+                                                .emit("crawl_session_expired", url.as_str());
+                                        }
+                                    }
+
                                     // Check for infinite loops
                                     if redirect_chain
                                         .iter()
@@ -186,9 +236,129 @@ pub async fn process_url(
         .iter()
         .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
         .collect::<Vec<_>>();
+    let security_headers = security_headers::grade(&headers, https);
 
     let mut cookies_data = cookies::extract_cookies(&response);
 
+    // With `crawl_pdfs`, a PDF is downloaded (up to `pdf_max_size_mb`) and audited
+    // as a document rather than skipped with the other binary content below.
+    let is_pdf = content_type
+        .as_deref()
+        .is_some_and(|ct| ct.to_lowercase().contains("application/pdf"));
+    if settings.crawl_pdfs && is_pdf && response.status().is_success() {
+        let max_bytes = settings.pdf_max_size_mb * 1024 * 1024;
+        let (bytes, truncated) = match tokio::time::timeout(
+            Duration::from_secs(60),
+            pdf_document::download(response, max_bytes),
+        )
+        .await
+        {
+            Ok(Ok(download)) => download,
+            Ok(Err(e)) => {
+                let mut state = state.lock().await;
+                state.record_failure(FailedUrl {
+                    url: url.to_string(),
+                    error: e.to_string(),
+                    retries: 0,
+                    depth,
+                    timestamp: Instant::now(),
+                });
+                state.pending_urls.remove(url.as_str());
+                return Err(format!("Failed to download PDF {}: {}", url, e));
+            }
+            Err(_) => {
+                let mut state = state.lock().await;
+                state.record_failure(FailedUrl {
+                    url: url.to_string(),
+                    error: "Timeout downloading PDF".to_string(),
+                    retries: 0,
+                    depth,
+                    timestamp: Instant::now(),
+                });
+                state.pending_urls.remove(url.as_str());
+                return Err(format!("Timeout downloading PDF from {}", url));
+            }
+        };
+
+        // Parsing inflates and scans every stream, so it runs off the async workers
+        let pdf_size = bytes.len();
+        let pdf = task::spawn_blocking(move || PdfDocument::parse(&bytes, truncated))
+            .await
+            .map_err(|e| format!("Failed to parse PDF {}: {}", url, e))?;
+        let links_for_crawler: HashMap<Url, DiscoverySource> = pdf
+            .links
+            .iter()
+            .filter_map(|href| links_selector::resolve_link(&final_url, base_url, href))
+            .map(|link| (link, DiscoverySource::Link))
+            .collect();
+        let anchor_links = Some(anchor_links::links_from_urls(&pdf.links, &final_url, base_url));
+        let link_checker = state.lock().await.link_checker.clone();
+        let inoutlinks_status_codes = check_link_statuses(
+            link_checker,
+            anchor_links.clone(),
+            base_url,
+            &final_url,
+            settings,
+        )
+        .await;
+        let discovery = state.lock().await.take_discovery(
+            &normalize_url(url.as_str()),
+            &normalize_url(final_url.as_str()),
+            depth,
+        );
+
+        let result = DomainCrawlResults {
+            url: final_url.to_string(),
+            original_url: url.to_string(),
+            redirect_url,
+            had_redirect,
+            redirection_type,
+            redirect_chain: Some(redirect_chain),
+            redirect_count,
+            title: pdf
+                .title
+                .as_ref()
+                .map(|title| vec![TitleDetails::new(title, title.chars().count())]),
+            status_code,
+            anchor_links,
+            inoutlinks_status_codes,
+            indexability: indexability::header_indexability(
+                &headers,
+                CrawlIdentity::from_settings(settings).indexing_agent(),
+            ),
+            word_count: pdf.word_count,
+            response_time: Some(response_time),
+            content_type: content_type.unwrap_or_default(),
+            content_length: pdf_size,
+            page_size: calculate_html_size(Some(pdf_size)),
+            language: pdf.language.clone(),
+            headers,
+            pdf_files: vec![final_url.to_string()],
+            https,
+            security_headers: Some(security_headers),
+            status: Some(status_code),
+            url_depth: Some(url_depth::calculate_url_depth(&url)),
+            cookies: Ok(cookies_data),
+            discovery,
+            pdf: Some(pdf),
+            ..Default::default()
+        };
+
+        update_state_and_emit_progress(
+            &state,
+            &url,
+            depth,
+            &result,
+            links_for_crawler,
+            url_rewriter.as_deref(),
+            app_handle,
+            settings,
+        )
+        .await;
+
+        return Ok(result);
+    }
+
 
     // Skip binary content types early to avoid downloading large non-HTML payloads.
     if let Some(ref ct) = content_type {
@@ -303,35 +473,36 @@ pub async fn process_url(
         }
     }
 
+    // Crawls set up a pool whenever rendering is on
+    let js_pool = match (settings.javascript_rendering, browser_pool) {
+        (true, None) => {
+            return Err("JavaScript rendering is on but no browser pool was set up".to_string())
+        }
+        (true, pool) => pool,
+        (false, _) => None,
+    };
+
     // If Javascript Rendering is enabled and content is HTML, re-fetch via Headless Chrome
-    if settings.javascript_rendering
-        && check_html_page::is_html_page(&body, content_type.as_deref()).await
-    {
-        let js_url = final_url.to_string();
-        let js_semaphore_clone = js_semaphore.clone();
-
-        // Use a separate task for blocking IO of headless chrome, protected by semaphore
-        let js_fetch_future = async move {
-            // Acquire permit asynchronously
-            let _permit = js_semaphore_clone
-                .acquire()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            // Run blocking Chrome operation
-            // Run blocking Chrome operation with a timeout
-            tokio::time::timeout(
-                Duration::from_secs(45), // 45s timeout for JS rendering
-                task::spawn_blocking(move || headless_fetch::fetch_js_body(&js_url))
-            ).await
-                .map_err(|e| e.to_string())? // Outer timeout error
-                .map_err(|e| e.to_string())? // Inner spawn_blocking error
-        };
+    let mut js_render = None;
+    // The response as served, kept for the raw vs rendered comparison
+    let mut raw_body: Option<String> = None;
+    let renders_html = settings.javascript_rendering
+        && check_html_page::is_html_page(&body, content_type.as_deref()).await;
+    if let Some(js_pool) = js_pool.as_ref().filter(|_| renders_html) {
+        let js_fetch_future = render_js(
+            js_pool.clone(),
+            final_url.to_string(),
+            CrawlIdentity::from_settings(settings),
+            credentials.clone(),
+            JsWait::from_settings(settings),
+            js_semaphore.clone(),
+        );
 
         match js_fetch_future.await {
-            Ok((js_body, js_cookies)) => {
+            Ok((js_body, js_cookies, timing)) => {
+                js_render = Some(timing);
                 if js_body.len() > 200 {
-                    body = js_body;
+                    raw_body = Some(std::mem::replace(&mut body, js_body));
                 }
                 // Merge JS cookies with existing cookies
                 // We use a HashSet (implicitly by iterating) or just append and dedup?
@@ -397,6 +568,8 @@ pub async fn process_url(
         opengraph_data,
         body_len,
         content_signature_val,
+        render_diff_val,
+        parity_snapshot_val,
     ) = {
         // Parse ngrams before moving `body` into the document parse (ngrams borrows body as &str).
         let ngrams_data_pre = if settings.extract_ngrams {
@@ -410,6 +583,27 @@ pub async fn process_url(
 
         let document = Html::parse_document(&body);
 
+        // Compare what JavaScript changed, when the page was rendered
+        let render_diff_val = raw_body.map(|raw| {
+            let raw_signals =
+                PageSignals::extract(&Html::parse_document(&raw), &final_url, base_url);
+            let rendered_signals = PageSignals::extract(&document, &final_url, base_url);
+            RenderDiff::between(&raw_signals, &rendered_signals)
+        });
+
+        // This device's side of the mobile/desktop parity check
+        let parity_snapshot_val = parity_checker.as_ref().map(|_| {
+            ProfileSnapshot::from_page(
+                &document,
+                &final_url,
+                base_url,
+                &CrawlIdentity::from_settings(settings).user_agent,
+                status_code,
+                redirect_count,
+                body_len_pre,
+            )
+        });
+
         // Explicitly drop `body` here so it's freed from memory before all the
         // async link-checking and image-fetching tasks that follow.
         // At 40K pages, keeping body alive until the end of process_url wastes GBs.
@@ -424,6 +618,34 @@ pub async fn process_url(
             None
         };
 
+        // Everything the crawler follows from this page: links, then (when
+        // `follow_canonical_hreflang` is on) canonical and hreflang URLs, each
+        // with how it was found
+        let canonicals = get_canonical(&document).map(|c| c.canonicals);
+        let hreflangs = select_hreflang(&document);
+        let mut links_for_crawler: HashMap<Url, DiscoverySource> =
+            links_selector::extract_links(&document, &final_url, base_url)
+                .into_iter()
+                .map(|link| (link, DiscoverySource::Link))
+                .collect();
+        if settings.follow_canonical_hreflang {
+            let alternates = canonicals
+                .iter()
+                .flatten()
+                .map(|href| (href, DiscoverySource::Canonical))
+                .chain(
+                    hreflangs
+                        .iter()
+                        .flatten()
+                        .map(|h| (&h.url, DiscoverySource::Hreflang)),
+                );
+            for (href, source) in alternates {
+                if let Some(link) = links_selector::resolve_link(&final_url, base_url, href) {
+                    links_for_crawler.entry(link).or_insert(source);
+                }
+            }
+        }
+
         (
             title_selector::extract_title(&document),
             page_description::extract_page_description(&document).unwrap_or_default(),
@@ -435,29 +657,35 @@ pub async fn process_url(
             },
             images_selector::extract_image_urls_and_alts(&document, &final_url),
             anchor_links::extract_internal_external_links(&document, &final_url, base_url),
-            indexability::extract_indexability(&document),
+            indexability::extract_indexability(
+                &document,
+                &headers,
+                CrawlIdentity::from_settings(settings).indexing_agent(),
+            ),
             alt_tags::get_alt_tags(&document),
             schema_selector::get_schema(&document),
             css_selector::extract_css(&document, &final_url),
             iframe_selector::extract_iframe(&document),
             get_word_count(&document),
             mobile_checker::is_mobile(&document),
-            get_canonical(&document).map(|c| c.canonicals),
+            canonicals,
             get_meta_robots(&document).unwrap_or(MetaRobots {
                 meta_robots: Vec::new(),
             }),
             get_text_ratio(&document),
             extract_keywords(&document, &settings.stop_words),
-            select_hreflang(&document),
+            hreflangs,
             detect_language(&document),
             get_flesch_score(&document),
             perform_extraction(&document),
             analyze_cross_origin_security(&document, &final_url),
-            links_selector::extract_links(&document, &final_url, base_url),
+            links_for_crawler,
             ngrams_data_pre,
             opengraph_data_pre,
             body_len_pre,
             content_signature_val,
+            render_diff_val,
+            parity_snapshot_val,
         )
     }; // `document` is dropped here
 
@@ -469,7 +697,6 @@ pub async fn process_url(
     let settings_clone = settings.clone();
     let internal_external_links_clone = internal_external_links.clone();
     let base_url_clone = base_url.clone();
-    let final_url_str = final_url.to_string();
     let image_urls_for_fetch_clone = image_urls_for_fetch.clone();
     let psi_settings_clone = settings.clone();
 
@@ -479,25 +706,13 @@ pub async fn process_url(
         Result<Vec<(String, String, u64, String, u16, bool)>, String>,
         Result<Vec<Value>, String>,
     ) = tokio::join!(
-        async {
-            if let Some(checker) = link_checker {
-                checker
-                    .check_links(
-                        internal_external_links_clone,
-                        &base_url_clone,
-                        final_url_str,
-                    )
-                    .await
-            } else {
-                get_links_status_code_from_settings(
-                    internal_external_links_clone,
-                    &base_url_clone,
-                    final_url_str,
-                    &settings_clone,
-                )
-                .await
-            }
-        },
+        check_link_statuses(
+            link_checker,
+            internal_external_links_clone,
+            &base_url_clone,
+            &final_url,
+            &settings_clone,
+        ),
         images_selector::fetch_image_details(client, image_urls_for_fetch_clone, image_semaphore.clone()),
         async {
             if psi_settings_clone.page_speed_bulk {
@@ -516,6 +731,29 @@ pub async fn process_url(
         }
     );
 
+    let mobile_parity = match (parity_checker, parity_snapshot_val) {
+        (Some(checker), Some(primary)) => Some(
+            check_parity(
+                &checker,
+                primary,
+                &url,
+                base_url,
+                settings,
+                credentials.clone(),
+                js_pool.as_ref(),
+                js_semaphore.clone(),
+            )
+            .await,
+        ),
+        _ => None,
+    };
+
+    let discovery = state.lock().await.take_discovery(
+        &normalize_url(url.as_str()),
+        &normalize_url(final_url.as_str()),
+        depth,
+    );
+
     // GETS THE SPECIFIC URL DEPTH
     let url_depth = url_depth::calculate_url_depth(&url);
 
@@ -569,11 +807,18 @@ pub async fn process_url(
         pdf_files,
         https,
         cross_origin: cross_origin_data,
+        security_headers: Some(security_headers),
         status: Some(status_code),
         url_depth: Some(url_depth),
         cookies: Ok(cookies_data),
         content_simhash: content_signature_val.map(|s| s.content_simhash),
         heading_hash: content_signature_val.and_then(|s| s.heading_hash),
+        blocked_by_robots: None,
+        js_render,
+        render_diff: render_diff_val,
+        mobile_parity,
+        discovery,
+        pdf: None,
     };
 
     // Update state and emit progress
@@ -583,6 +828,7 @@ pub async fn process_url(
         depth,
         &result,
         links_for_crawler,
+        url_rewriter.as_deref(),
         app_handle,
         settings,
     )
@@ -591,13 +837,110 @@ pub async fn process_url(
     Ok(result)
 }
 
+/// Status codes of a page's links, through the crawl's shared checker when it has one.
+async fn check_link_statuses(
+    link_checker: Option<Arc<SharedLinkChecker>>,
+    links: Option<InternalExternalLinks>,
+    base_url: &Url,
+    page: &Url,
+    settings: &Settings,
+) -> LinkCheckResults {
+    match link_checker {
+        Some(checker) => checker.check_links(links, base_url, page.to_string()).await,
+        None => get_links_status_code_from_settings(links, base_url, page.to_string(), settings).await,
+    }
+}
+
+/// Fetches `url` as the parity checker's device (rendering it too when the crawl
+/// renders JavaScript) and compares it with `primary`, this crawl's own view.
+#[allow(clippy::too_many_arguments)]
+async fn check_parity(
+    checker: &ParityChecker,
+    primary: ProfileSnapshot,
+    url: &Url,
+    base_url: &Url,
+    settings: &Settings,
+    credentials: Option<Arc<CrawlCredentials>>,
+    js_pool: Option<&Arc<BrowserPool>>,
+    js_semaphore: Arc<Semaphore>,
+) -> ParityReport {
+    let identity = checker.identity();
+    let fetched = match checker.fetch(url, settings, credentials.as_deref()).await {
+        Ok(fetched) => fetched,
+        Err(e) => {
+            tracing::warn!("Parity fetch failed for {}: {}", url, e);
+            return ParityReport::failed(primary, !identity.is_mobile(), e);
+        }
+    };
+
+    let mut body = fetched.body;
+    if let Some(pool) = js_pool {
+        if check_html_page::is_html_page(&body, fetched.content_type.as_deref()).await {
+            match render_js(
+                pool.clone(),
+                fetched.final_url.to_string(),
+                identity.clone(),
+                credentials,
+                JsWait::from_settings(settings),
+                js_semaphore,
+            )
+            .await
+            {
+                Ok((js_body, _, _)) if js_body.len() > 200 => body = js_body,
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to render {} for parity: {}", url, e),
+            }
+        }
+    }
+
+    let counterpart = ProfileSnapshot::from_page(
+        &Html::parse_document(&body),
+        &fetched.final_url,
+        base_url,
+        &identity.user_agent,
+        fetched.status_code,
+        fetched.redirect_count,
+        body.len(),
+    );
+
+    if identity.is_mobile() {
+        ParityReport::compare(counterpart, primary)
+    } else {
+        ParityReport::compare(primary, counterpart)
+    }
+}
+
+/// Renders `url` in headless Chrome as `identity`, once a JS permit is free.
+async fn render_js(
+    pool: Arc<BrowserPool>,
+    url: String,
+    identity: CrawlIdentity,
+    credentials: Option<Arc<CrawlCredentials>>,
+    wait: JsWait,
+    js_semaphore: Arc<Semaphore>,
+) -> Result<(String, Vec<String>, RenderTiming), String> {
+    // Acquire permit asynchronously
+    let permit = js_semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+
+    // The permit stays with the blocking render until it ends, so the number of
+    // live browsers never exceeds `javascript_concurrency`. The render enforces
+    // `RENDER_TIMEOUT` itself.
+    task::spawn_blocking(move || {
+        let _permit = permit;
+        headless_fetch::fetch_js_body(&pool, &url, &identity, credentials.as_deref(), &wait)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 /// Update crawler state and emit progress after processing a URL
 async fn update_state_and_emit_progress(
     state: &Arc<Mutex<CrawlerState>>,
     url: &Url,
     depth: usize,
     result: &DomainCrawlResults,
-    links_for_crawler: std::collections::HashSet<Url>,
+    links_for_crawler: HashMap<Url, DiscoverySource>,
+    url_rewriter: Option<&UrlRewriter>,
     app_handle: &tauri::AppHandle,
     settings: &Settings,
 ) {
@@ -607,30 +950,60 @@ async fn update_state_and_emit_progress(
     let normalized_current_url = normalize_url(url.as_str());
     let normalized_final_url = normalize_url(&result.url);
 
+    // Resource mode: the page's images, stylesheets and scripts, plus linked
+    // assets that are kept out of the page queue below.
+    let mut resource_refs: Vec<(String, ResourceKind)> = Vec::new();
+    if settings.crawl_resources {
+        if let Ok(images) = &result.images {
+            resource_refs.extend(images.iter().map(|image| (image.0.clone(), ResourceKind::Image)));
+        }
+        resource_refs.extend(result.css.external.iter().map(|css| (css.clone(), ResourceKind::Css)));
+        resource_refs.extend(
+            result
+                .javascript
+                .external
+                .iter()
+                .map(|js| (js.clone(), ResourceKind::Javascript)),
+        );
+        // Crawled PDFs are audited as documents instead
+        resource_refs.extend(links_for_crawler.keys().filter_map(|link| {
+            ResourceKind::from_url(link)
+                .filter(|kind| !(settings.crawl_pdfs && *kind == ResourceKind::Pdf))
+                .map(|kind| (link.to_string(), kind))
+        }));
+    }
+
     // Pre-filter and normalize links before touching shared state.
-    // Each entry: (normalized_url_string, pattern_string, parsed_Url)
-    let prepared_links: Vec<(String, String, Url)> = links_for_crawler
-        .into_iter()
-        .filter_map(|link| {
-            let link_str = link.as_str();
-            if should_skip_url(link_str) {
-                return None;
-            }
-            let normalized = normalize_url(link_str);
-            let pattern = extract_url_pattern(&normalized);
-            let url_obj = Url::parse(&normalized).ok()?;
-            Some((normalized, pattern, url_obj))
-        })
-        .collect();
+    // Each entry: (normalized_url_string, pattern_string, parsed_Url, trap_signatures, source)
+    #[allow(clippy::type_complexity)]
+    let prepared_links: Vec<(String, String, Url, Vec<TrapSignature>, DiscoverySource)> =
+        links_for_crawler
+            .into_iter()
+            .filter_map(|(link, source)| {
+                let link_str = link.as_str();
+                let crawled_pdf = settings.crawl_pdfs && is_pdf_url(link_str);
+                if !crawled_pdf && should_skip_url(link_str) {
+                    return None;
+                }
+                let normalized = match url_rewriter {
+                    Some(rewriter) => normalize_url(&rewriter.rewrite(link_str)),
+                    None => normalize_url(link_str),
+                };
+                let pattern = extract_url_pattern(&normalized);
+                let url_obj = Url::parse(&normalized).ok()?;
+                let traps = spider_trap::signatures(&url_obj);
+                Some((normalized, pattern, url_obj, traps, source))
+            })
+            .collect();
 
     let mut state = state.lock().await;
 
     // Insert final_url first to see if it's new
-    let is_new_final = state.visited.insert(normalized_final_url.clone());
+    let is_new_final = state.mark_visited(&normalized_final_url);
 
     // Also mark original requested URL as visited
     if normalized_current_url != normalized_final_url {
-        state.visited.insert(normalized_current_url.clone());
+        state.mark_visited(&normalized_current_url);
     }
 
     state.pending_urls.remove(&normalized_current_url);
@@ -638,6 +1011,9 @@ async fn update_state_and_emit_progress(
 
     if is_new_final {
         state.crawled_urls += 1;
+        if let Some(resources) = state.resources.as_mut() {
+            resources.record_page(&result.url, &resource_refs);
+        }
     } else {
         // We already processed this final URL via another path (e.g. redirect).
         // Discount it from total_urls so completion percentage remains accurate.
@@ -648,6 +1024,7 @@ async fn update_state_and_emit_progress(
     // AND this was a newly discovered final page (skip duplicate link extraction).
     const MAX_QUEUE_SIZE: usize = 50_000;
     if is_new_final
+        && state.spidering
         && depth < settings.max_depth
         && state.total_urls < settings.max_urls_per_domain
         && state.queue.len() < MAX_QUEUE_SIZE
@@ -656,7 +1033,7 @@ async fn update_state_and_emit_progress(
         if links_found > 0 && state.crawled_urls % 100 == 0 {
             tracing::info!("Found {} links on {} at depth {}", links_found, url, depth);
         }
-        for (normalized_url, url_pattern, normalized_url_obj) in prepared_links {
+        for (normalized_url, url_pattern, normalized_url_obj, traps, source) in prepared_links {
             // Pattern checking to avoid infinite URL traps
             let pattern_count = *state.url_patterns.get(&url_pattern).unwrap_or(&0);
 
@@ -669,6 +1046,12 @@ async fn update_state_and_emit_progress(
                         url_pattern
                     );
                 }
+                state.spider_traps.record_similar_url_cap(
+                    &url_pattern,
+                    pattern_count,
+                    &normalized_url,
+                    &normalized_final_url,
+                );
                 *state.url_patterns.entry(url_pattern).or_insert(0) += 1;
                 continue;
             }
@@ -680,7 +1063,17 @@ async fn update_state_and_emit_progress(
                 && !state.queued_url_set.contains(&normalized_url)
                 && !state.pending_urls.contains_key(&normalized_url)
                 && state.total_urls < settings.max_urls_per_domain
+                && state.scope_allows(&normalized_url, &normalized_final_url)
+                && state.robots_allows(&normalized_url_obj, &normalized_url)
+                && state
+                    .spider_traps
+                    .allows(&traps, &normalized_url, &normalized_final_url)
+                && state.budget_allows(&normalized_url_obj, &normalized_url)
             {
+                state
+                    .spider_traps
+                    .record(traps, &normalized_url, &normalized_final_url);
+                state.note_discovery(&normalized_url, source, Some(&normalized_final_url));
                 state.queue.push_back((normalized_url_obj, depth + 1));
                 state.queued_url_set.insert(normalized_url.clone());
                 state.total_urls += 1;
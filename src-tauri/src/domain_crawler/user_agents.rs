@@ -1,3 +1,225 @@
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_LANGUAGE};
+
+use crate::settings::settings::Settings;
+
+/// Used when `user_agents` is empty.
+const FALLBACK_BROWSER_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+/// Smartphone browser used as the mobile counterpart of desktop identities in parity checks.
+const MOBILE_BROWSER_UA: &str = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36";
+
+const GOOGLEBOT_SMARTPHONE_UA: &str = "Mozilla/5.0 (Linux; Android 6.0.1; Nexus 5X Build/MMB29P) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.6367.201 Mobile Safari/537.36 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+const GOOGLEBOT_DESKTOP_UA: &str = "Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; Googlebot/2.1; +http://www.google.com/bot.html) Chrome/124.0.6367.201 Safari/537.36";
+const BINGBOT_UA: &str = "Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm) Chrome/116.0.1938.76 Safari/537.36";
+const BINGBOT_MOBILE_UA: &str = "Mozilla/5.0 (Linux; Android 6.0.1; Nexus 5X Build/MMB29P) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.1938.76 Mobile Safari/537.36 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)";
+const GPTBOT_UA: &str = "Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; GPTBot/1.2; +https://openai.com/gptbot)";
+
+/// Who the crawler presents itself as (`Settings::crawl_user_agent`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserAgentPreset {
+    /// The first entry of `Settings::user_agents`
+    Browser,
+    GooglebotSmartphone,
+    GooglebotDesktop,
+    Bingbot,
+    GptBot,
+    /// `Settings::custom_user_agent`
+    Custom,
+}
+
+impl UserAgentPreset {
+    pub fn from_key(key: &str) -> Self {
+        match key.trim().to_ascii_lowercase().as_str() {
+            "googlebot-smartphone" => UserAgentPreset::GooglebotSmartphone,
+            "googlebot-desktop" => UserAgentPreset::GooglebotDesktop,
+            "bingbot" => UserAgentPreset::Bingbot,
+            "gptbot" => UserAgentPreset::GptBot,
+            "custom" => UserAgentPreset::Custom,
+            _ => UserAgentPreset::Browser,
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            UserAgentPreset::Browser => "browser",
+            UserAgentPreset::GooglebotSmartphone => "googlebot-smartphone",
+            UserAgentPreset::GooglebotDesktop => "googlebot-desktop",
+            UserAgentPreset::Bingbot => "bingbot",
+            UserAgentPreset::GptBot => "gptbot",
+            UserAgentPreset::Custom => "custom",
+        }
+    }
+}
+
+/// The identity a crawl uses: the User-Agent header sent by the page client, the
+/// link checker and headless Chrome, and the token used to pick the robots.txt group.
+#[derive(Debug, Clone)]
+pub struct CrawlIdentity {
+    pub preset: UserAgentPreset,
+    pub user_agent: String,
+    /// Product token matched against robots.txt `User-agent` lines. For browser and
+    /// custom identities this is the full UA string, which usually falls through to `*`.
+    pub robots_token: String,
+}
+
+impl CrawlIdentity {
+    pub fn from_settings(settings: &Settings) -> Self {
+        let preset = UserAgentPreset::from_key(&settings.crawl_user_agent);
+        let browser_ua = || {
+            settings
+                .user_agents
+                .iter()
+                .map(|ua| ua.trim())
+                .find(|ua| !ua.is_empty())
+                .unwrap_or(FALLBACK_BROWSER_UA)
+                .to_string()
+        };
+
+        let (user_agent, robots_token) = match preset {
+            UserAgentPreset::Browser => {
+                let ua = browser_ua();
+                (ua.clone(), ua)
+            }
+            UserAgentPreset::GooglebotSmartphone => {
+                (GOOGLEBOT_SMARTPHONE_UA.to_string(), "Googlebot".to_string())
+            }
+            UserAgentPreset::GooglebotDesktop => {
+                (GOOGLEBOT_DESKTOP_UA.to_string(), "Googlebot".to_string())
+            }
+            UserAgentPreset::Bingbot => (BINGBOT_UA.to_string(), "bingbot".to_string()),
+            UserAgentPreset::GptBot => (GPTBOT_UA.to_string(), "GPTBot".to_string()),
+            UserAgentPreset::Custom => {
+                let ua = settings.custom_user_agent.trim();
+                let ua = if ua.is_empty() { browser_ua() } else { ua.to_string() };
+                (ua.clone(), ua)
+            }
+        };
+
+        CrawlIdentity {
+            preset,
+            user_agent,
+            robots_token,
+        }
+    }
+
+    /// Crawlers don't send the `Sec-*` headers a browser does.
+    pub fn is_bot(&self) -> bool {
+        matches!(
+            self.preset,
+            UserAgentPreset::GooglebotSmartphone
+                | UserAgentPreset::GooglebotDesktop
+                | UserAgentPreset::Bingbot
+                | UserAgentPreset::GptBot
+        )
+    }
+
+    /// The crawler whose meta robots and `X-Robots-Tag` directives decide
+    /// indexability: the identity's own token for search bots, Googlebot for
+    /// browser and custom identities.
+    pub fn indexing_agent(&self) -> &str {
+        if self.is_bot() {
+            &self.robots_token
+        } else {
+            "googlebot"
+        }
+    }
+
+    pub fn is_mobile(&self) -> bool {
+        self.user_agent.contains("Mobile")
+    }
+
+    /// The same crawler on the other kind of device, for mobile/desktop parity
+    /// checks: Googlebot Smartphone <-> Desktop, Bingbot mobile <-> desktop, and
+    /// a smartphone or desktop Chrome for everything else. Keeps the robots.txt
+    /// token so both profiles obey the same group.
+    pub fn counterpart(&self) -> CrawlIdentity {
+        let mobile = !self.is_mobile();
+        let (preset, user_agent) = match (self.preset, mobile) {
+            (UserAgentPreset::GooglebotSmartphone | UserAgentPreset::GooglebotDesktop, true) => {
+                (UserAgentPreset::GooglebotSmartphone, GOOGLEBOT_SMARTPHONE_UA)
+            }
+            (UserAgentPreset::GooglebotSmartphone | UserAgentPreset::GooglebotDesktop, false) => {
+                (UserAgentPreset::GooglebotDesktop, GOOGLEBOT_DESKTOP_UA)
+            }
+            (UserAgentPreset::Bingbot, true) => (UserAgentPreset::Bingbot, BINGBOT_MOBILE_UA),
+            (UserAgentPreset::Bingbot, false) => (UserAgentPreset::Bingbot, BINGBOT_UA),
+            (_, true) => (UserAgentPreset::Browser, MOBILE_BROWSER_UA),
+            (_, false) => (UserAgentPreset::Browser, FALLBACK_BROWSER_UA),
+        };
+
+        CrawlIdentity {
+            preset,
+            user_agent: user_agent.to_string(),
+            robots_token: self.robots_token.clone(),
+        }
+    }
+
+    /// `Accept`/`Accept-Language` plus the browser-only `Sec-*` headers that match
+    /// the user agent (Client Hints are only sent for Chromium user agents).
+    pub fn default_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7"));
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.9"));
+        for (name, value) in self.browser_headers() {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        }
+        headers
+    }
+
+    /// `Sec-Ch-Ua*` and `Sec-Fetch-*` headers; empty for bot identities.
+    pub fn browser_headers(&self) -> Vec<(&'static str, String)> {
+        if self.is_bot() {
+            return Vec::new();
+        }
+
+        let mut headers = Vec::new();
+        if let Some(version) = chrome_major_version(&self.user_agent) {
+            headers.push((
+                "Sec-Ch-Ua",
+                format!(
+                    "\"Chromium\";v=\"{v}\", \"Google Chrome\";v=\"{v}\", \"Not-A.Brand\";v=\"99\"",
+                    v = version
+                ),
+            ));
+            headers.push((
+                "Sec-Ch-Ua-Mobile",
+                if self.is_mobile() { "?1" } else { "?0" }.to_string(),
+            ));
+            headers.push(("Sec-Ch-Ua-Platform", format!("\"{}\"", self.platform())));
+        }
+        headers.push(("Sec-Fetch-Dest", "document".to_string()));
+        headers.push(("Sec-Fetch-Mode", "navigate".to_string()));
+        headers.push(("Sec-Fetch-Site", "none".to_string()));
+        headers.push(("Sec-Fetch-User", "?1".to_string()));
+        headers
+    }
+
+    /// Platform name as reported by `Sec-Ch-Ua-Platform` and `navigator.platform`.
+    pub fn platform(&self) -> &'static str {
+        let ua = &self.user_agent;
+        if ua.contains("Android") {
+            "Android"
+        } else if ua.contains("iPhone") || ua.contains("iPad") {
+            "iOS"
+        } else if ua.contains("Windows") {
+            "Windows"
+        } else if ua.contains("Macintosh") {
+            "macOS"
+        } else if ua.contains("Linux") || ua.contains("X11") {
+            "Linux"
+        } else {
+            "Unknown"
+        }
+    }
+}
+
+fn chrome_major_version(user_agent: &str) -> Option<u32> {
+    let rest = &user_agent[user_agent.find("Chrome/")? + "Chrome/".len()..];
+    rest.split('.').next()?.parse().ok()
+}
+
 pub fn agents() -> Vec<String> {
     vec![
         // Desktop Chrome (Various OS/versions)
@@ -56,3 +278,41 @@ pub fn agents() -> Vec<String> {
         "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 VivoBrowser/9.8.0.0".to_string(),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_pick_user_agent_headers_and_robots_token() {
+        let mut settings = Settings {
+            crawl_user_agent: "googlebot-smartphone".to_string(),
+            ..Default::default()
+        };
+        let identity = CrawlIdentity::from_settings(&settings);
+        assert_eq!(identity.robots_token, "Googlebot");
+        assert!(identity.is_mobile());
+        assert!(identity.browser_headers().is_empty());
+        let desktop = identity.counterpart();
+        assert_eq!(desktop.preset, UserAgentPreset::GooglebotDesktop);
+        assert!(!desktop.is_mobile());
+        assert_eq!(desktop.robots_token, "Googlebot");
+
+        settings.crawl_user_agent = "browser".to_string();
+        settings.user_agents = vec![
+            "Mozilla/5.0 (Linux; Android 13; SM-G991B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36".to_string(),
+        ];
+        let identity = CrawlIdentity::from_settings(&settings);
+        let headers = identity.browser_headers();
+        assert!(headers.contains(&("Sec-Ch-Ua-Mobile", "?1".to_string())));
+        assert!(headers.contains(&("Sec-Ch-Ua-Platform", "\"Android\"".to_string())));
+        assert!(headers[0].1.contains("v=\"120\""));
+        assert!(!identity.counterpart().is_mobile());
+
+        settings.crawl_user_agent = "custom".to_string();
+        settings.custom_user_agent = "MyAuditBot/1.0".to_string();
+        let identity = CrawlIdentity::from_settings(&settings);
+        assert_eq!(identity.user_agent, "MyAuditBot/1.0");
+        assert_eq!(identity.robots_token, "MyAuditBot/1.0");
+    }
+}
@@ -262,6 +262,12 @@ async fn main() {
                 });
             }
 
+            // Scheduled crawls run in the background while the app is open
+            if let Err(e) = domain_crawler::scheduler::close_interrupted_runs() {
+                eprintln!("Error closing interrupted scheduled crawls: {}", e);
+            }
+            domain_crawler::scheduler::start(app.handle().clone());
+
             std::thread::spawn(move || {
                 while let Ok(log) = rx.recv() {
                     let _ = handle.emit("tui-log", log);
@@ -345,6 +351,21 @@ async fn main() {
             commands::read_matched_keywords_from_db_command,
             commands::fetch_keywords_summarized_matched_command,
             domain_commands::domain_crawl_command,
+            domain_commands::list_crawl_command,
+            domain_commands::resume_crawl,
+            domain_commands::get_crawl_checkpoint_command,
+            domain_commands::preview_url_rewrite_command,
+            domain_crawler::crawl_projects::list_crawl_projects_command,
+            domain_crawler::crawl_projects::get_active_crawl_project_command,
+            domain_crawler::crawl_projects::open_crawl_project_command,
+            domain_crawler::crawl_projects::rename_crawl_project_command,
+            domain_crawler::crawl_projects::delete_crawl_project_command,
+            domain_crawler::scheduler::list_crawl_jobs_command,
+            domain_crawler::scheduler::save_crawl_job_command,
+            domain_crawler::scheduler::delete_crawl_job_command,
+            domain_crawler::scheduler::get_crawl_job_runs_command,
+            domain_commands::compare_crawls_command,
+            domain_commands::export_crawl_comparison_command,
             domain_commands::create_excel,
             domain_commands::create_excel_main_table,
             domain_commands::export_full_crawl_to_excel_command,
@@ -374,7 +395,15 @@ async fn main() {
             domain_commands::get_crawl_page_command,
             domain_commands::get_crawl_total_count_command,
             domain_commands::get_crawl_summary_stats_command,
+            domain_commands::get_excluded_urls_command,
+            domain_commands::get_spider_traps_command,
+            domain_commands::get_render_diffs_command,
+            domain_commands::get_mobile_parity_command,
             domain_commands::get_link_scores_command,
+            domain_commands::get_orphan_pages_command,
+            domain_commands::get_crawl_resources_command,
+            domain_commands::get_tls_audit_command,
+            domain_commands::get_security_headers_command,
             domain_commands::find_duplicate_content_command,
             domain_commands::export_images_to_excel_command,
             domain_commands::export_keywords_to_excel_command,
@@ -449,6 +478,7 @@ async fn main() {
             settings::settings::get_settings_command,
             update_settings_command,
             toggle_javascript_rendering,
+            settings::settings::set_rate_limit_command,
             url_checker::http_check::check_url,
             loganalyser::log_commands::save_gsc_data,
             loganalyser::log_commands::save_crawl_data,
@@ -9,7 +9,11 @@ use tokio::io::AsyncWriteExt;
 use toml;
 use uuid::Uuid;
 
+use crate::domain_crawler::helpers::crawl_budget::{CrawlBudget, CrawlBudgets};
+use crate::domain_crawler::helpers::headless_fetch::{self, JsWaitStrategy};
 use crate::domain_crawler::helpers::keyword_selector::default_stop_words;
+use crate::domain_crawler::helpers::rate_limit;
+use crate::domain_crawler::helpers::url_rewrite::{RewriteRule, UrlRewriter};
 use crate::domain_crawler::user_agents;
 use crate::loganalyser::log_state::set_taxonomies;
 use crate::settings::utils;
@@ -32,6 +36,13 @@ pub struct Settings {
     // --- General Crawler Settings ---
     /// List of user agents to rotate
     pub user_agents: Vec<String>,
+    /// Crawl identity: "browser" (first of `user_agents`), "googlebot-smartphone",
+    /// "googlebot-desktop", "bingbot", "gptbot" or "custom"
+    #[serde(default = "default_crawl_user_agent")]
+    pub crawl_user_agent: String,
+    /// User-Agent string used when `crawl_user_agent` is "custom"
+    #[serde(default)]
+    pub custom_user_agent: String,
     /// Number of concurrent requests for domain crawling
     pub concurrent_requests: usize,
     /// Number of URLs to process between sleeps/checks
@@ -44,6 +55,29 @@ pub struct Settings {
     /// They will always be fetched via sqlite DB
     /// NOTE: check the GlobalCrawlDataStore
     pub max_urls_stored: usize,
+    /// Obey robots.txt (Allow/Disallow and Crawl-delay) for the crawl's user agent
+    #[serde(default = "default_true")]
+    pub obey_robots_txt: bool,
+    /// Regex patterns a URL must match (at least one) to be crawled. Empty = no restriction
+    #[serde(default)]
+    pub crawl_include_patterns: Vec<String>,
+    /// Regex patterns that exclude a URL from the crawl. Exclusions win over inclusions
+    #[serde(default)]
+    pub crawl_exclude_patterns: Vec<String>,
+    /// Rewrite rules applied to discovered URLs before they are queued
+    /// (see `url_rewrite`)
+    #[serde(default)]
+    pub url_rewrite_rules: Vec<RewriteRule>,
+    /// Caps on the URLs queued per path prefix or URL pattern (see `crawl_budget`)
+    #[serde(default)]
+    pub crawl_budgets: Vec<CrawlBudget>,
+    /// Stop queueing URLs of a suspected spider trap once it reaches
+    /// `spider_trap_threshold` URLs (see `spider_trap`)
+    #[serde(default)]
+    pub spider_trap_auto_stop: bool,
+    /// URLs per suspected trap pattern before `spider_trap_auto_stop` kicks in
+    #[serde(default = "default_spider_trap_threshold")]
+    pub spider_trap_threshold: usize,
 
     // --- Timing & Throttling (Adaptive) ---
     /// Enable adaptive crawling speed based on server response
@@ -54,8 +88,19 @@ pub struct Settings {
     pub max_delay: u64,
     /// Minimum delay allowed in adaptive mode (ms)
     pub min_crawl_delay: u64,
+    /// Hard cap on requests per second to each host, across page fetches, link
+    /// checks and image checks. 0 = no limit. Changes apply to a running crawl.
+    #[serde(default)]
+    pub rate_limit_requests_per_second: f64,
+    /// Requests a host may get back to back before the per-second limit applies
+    #[serde(default = "default_rate_limit_burst")]
+    pub rate_limit_burst: u32,
     /// Total timeout for a crawl job (seconds)
     pub crawl_timeout: u64,
+    /// How often the crawl frontier and visited set are checkpointed to SQLite so an
+    /// interrupted crawl can be resumed (seconds, 0 = disabled)
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: u64,
     /// Interval to check for stalled crawlers (seconds)
     pub stall_check_interval: u64,
     /// Maximum time a URL can be pending before considered stalled (seconds)
@@ -71,6 +116,18 @@ pub struct Settings {
     pub redirect_policy: usize,
     /// Maximum retries for failed requests
     pub max_retries: u32,
+    /// Proxy for crawl traffic, e.g. `http://proxy:8080` or `socks5://proxy:1080`.
+    /// Empty for a direct connection
+    #[serde(default)]
+    pub proxy_url: String,
+    /// Proxy credentials (leave empty when the proxy needs none)
+    #[serde(default)]
+    pub proxy_username: String,
+    #[serde(default)]
+    pub proxy_password: String,
+    /// Hosts or domains (`.example.com`) that bypass the proxy
+    #[serde(default)]
+    pub proxy_no_proxy: Vec<String>,
 
     // --- JavaScript & Rendering ---
     /// Whether to expect HTML content
@@ -79,6 +136,16 @@ pub struct Settings {
     pub javascript_rendering: bool,
     /// Concurrency for Headless Chrome
     pub javascript_concurrency: usize,
+    /// What a render waits for after the page loads: "network_idle", "selector" or "timeout"
+    #[serde(default)]
+    pub javascript_wait_strategy: JsWaitStrategy,
+    /// CSS selector to wait for with the "selector" strategy
+    #[serde(default)]
+    pub javascript_wait_selector: String,
+    /// Longest wait after the page loads, in milliseconds (the whole wait for "timeout").
+    /// At most 30000, as the whole render is limited to 45s
+    #[serde(default = "default_javascript_wait_timeout_ms")]
+    pub javascript_wait_timeout_ms: u64,
 
     // --- Link Processor (Internal/External Check) ---
     /// Max concurrent checks for link status
@@ -107,6 +174,35 @@ pub struct Settings {
     /// pages afterwards. Off by default — it's an opt-in, since it does extra text
     /// processing per page.
     pub duplicate_content_check_enabled: bool,
+    /// Fetch every page a second time as the other device (smartphone for desktop
+    /// crawls, desktop for mobile ones) and report mobile/desktop differences.
+    /// Doubles the requests per page.
+    #[serde(default)]
+    pub mobile_parity_check: bool,
+    /// Request every image, stylesheet, script, font, PDF and archive the crawled
+    /// pages reference (once each) and report status, size and cache headers.
+    #[serde(default)]
+    pub crawl_resources: bool,
+    /// Also queue the canonical and hreflang URLs pages declare, not only the
+    /// URLs they link to. Off by default, since it widens the crawl.
+    #[serde(default)]
+    pub follow_canonical_hreflang: bool,
+    /// Queue linked PDFs and audit them as documents (metadata, page and word
+    /// counts, language, outbound links) instead of skipping them.
+    #[serde(default)]
+    pub crawl_pdfs: bool,
+    /// Size at which a PDF download stops; the audit covers what was read (MB)
+    #[serde(default = "default_pdf_max_size_mb")]
+    pub pdf_max_size_mb: u64,
+    /// Audit every crawled host's TLS setup (certificate, chain, HTTP→HTTPS
+    /// redirect, HSTS) at the end of the crawl. Off by default, as it makes
+    /// extra connections to each host.
+    #[serde(default)]
+    pub tls_audit: bool,
+    /// Hosts whose TLS certificate expires within this many days are reported
+    /// as issues in the end-of-crawl TLS audit
+    #[serde(default = "default_tls_expiry_warning_days")]
+    pub tls_expiry_warning_days: i64,
 
     // --- Extraction & Content ---
     /// Enable N-gram extraction
@@ -163,18 +259,30 @@ impl Settings {
 
             // --- General Crawler Settings ---
             user_agents: user_agents::agents(),
+            crawl_user_agent: default_crawl_user_agent(),
+            custom_user_agent: String::new(),
             concurrent_requests: 5, // Reduced from 10
             batch_size: 40,
             max_depth: 50,
             max_urls_per_domain: 100000,
             max_urls_stored: 5000,
+            obey_robots_txt: true,
+            crawl_include_patterns: Vec::new(),
+            crawl_exclude_patterns: Vec::new(),
+            url_rewrite_rules: Vec::new(),
+            crawl_budgets: Vec::new(),
+            spider_trap_auto_stop: false,
+            spider_trap_threshold: default_spider_trap_threshold(),
 
             // --- Timing & Throttling ---
             adaptive_crawling: true,
             base_delay: 1500,     // Increased from 1000
             max_delay: 30000,     // Increased from 10000 — gives adaptive system more room
             min_crawl_delay: 500, // Increased from 300
+            rate_limit_requests_per_second: 0.0,
+            rate_limit_burst: default_rate_limit_burst(),
             crawl_timeout: 28800,
+            checkpoint_interval: default_checkpoint_interval(),
             stall_check_interval: 30, // SECONDS
             max_pending_time: 900,    // SECONDS
 
@@ -183,11 +291,18 @@ impl Settings {
             client_connect_timeout: 15,
             redirect_policy: 5,
             max_retries: 5,
+            proxy_url: String::new(),
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            proxy_no_proxy: Vec::new(),
 
             // --- JavaScript & Rendering ---
             html: false,
             javascript_rendering: false,
             javascript_concurrency: 3,
+            javascript_wait_strategy: JsWaitStrategy::Timeout,
+            javascript_wait_selector: String::new(),
+            javascript_wait_timeout_ms: default_javascript_wait_timeout_ms(),
 
             // --- Link Processor ---
             links_max_concurrent_requests: 50, // Increased to avoid link checking bottleneck
@@ -202,6 +317,13 @@ impl Settings {
             // --- Crawl Analysis ---
             link_score_enabled: true,
             duplicate_content_check_enabled: false,
+            mobile_parity_check: false,
+            crawl_resources: false,
+            follow_canonical_hreflang: false,
+            crawl_pdfs: false,
+            pdf_max_size_mb: default_pdf_max_size_mb(),
+            tls_audit: false,
+            tls_expiry_warning_days: default_tls_expiry_warning_days(),
 
             // --- Extraction & Content ---
             extract_ngrams: false,
@@ -253,6 +375,11 @@ impl Settings {
         let ua = serde_json::to_string(&self.user_agents).unwrap_or_else(|_| "[]".to_string());
         s.push_str(&format!("user_agents = {}\n", ua));
 
+        s.push_str("# Crawl identity: browser, googlebot-smartphone, googlebot-desktop, bingbot, gptbot or custom\n");
+        s.push_str(&format!("crawl_user_agent = {:?}\n", self.crawl_user_agent));
+        s.push_str("# User-Agent string used when crawl_user_agent = \"custom\"\n");
+        s.push_str(&format!("custom_user_agent = {:?}\n", self.custom_user_agent));
+
         s.push_str("# Number of concurrent requests for domain crawling\n");
         s.push_str(&format!(
             "concurrent_requests = {}\n",
@@ -275,6 +402,40 @@ impl Settings {
         s.push_str("# Max URLS TO SHOWCASE IN THE FRONTEND, JAvascript HEAP\n");
         s.push_str(&format!("max_urls_stored = {}\n", self.max_urls_stored));
 
+        s.push_str("# Obey robots.txt (Allow/Disallow and Crawl-delay) for the crawl's user agent\n");
+        s.push_str(&format!("obey_robots_txt = {}\n", self.obey_robots_txt));
+
+        s.push_str("# Regex patterns a URL must match (any of them) to be crawled. Empty = crawl everything\n");
+        let include =
+            serde_json::to_string(&self.crawl_include_patterns).unwrap_or_else(|_| "[]".to_string());
+        s.push_str(&format!("crawl_include_patterns = {}\n", include));
+
+        s.push_str("# Regex patterns that exclude a URL from the crawl (checked before includes)\n");
+        let exclude =
+            serde_json::to_string(&self.crawl_exclude_patterns).unwrap_or_else(|_| "[]".to_string());
+        s.push_str(&format!("crawl_exclude_patterns = {}\n", exclude));
+
+        s.push_str("# Rewrite rules for discovered URLs, applied in order. Types: regex_replace (pattern,\n");
+        s.push_str("# replacement), drop_params (params), drop_all_params, keep_params (params),\n");
+        s.push_str("# sort_params, lowercase. e.g. [{ type = \"drop_params\", params = [\"sid\"] }]\n");
+        let rewrite_rules = toml::Value::try_from(&self.url_rewrite_rules)
+            .map(|v| v.to_string())
+            .unwrap_or_else(|_| "[]".to_string());
+        s.push_str(&format!("url_rewrite_rules = {}\n", rewrite_rules));
+
+        s.push_str("# Max URLs queued per path prefix or URL pattern. Types: path_prefix (prefix, max_urls),\n");
+        s.push_str("# pattern (pattern, max_urls; capture groups give each captured value its own budget).\n");
+        s.push_str("# e.g. [{ type = \"pattern\", pattern = \"/category/([^/]+)/\", max_urls = 200 }]\n");
+        let crawl_budgets = toml::Value::try_from(&self.crawl_budgets)
+            .map(|v| v.to_string())
+            .unwrap_or_else(|_| "[]".to_string());
+        s.push_str(&format!("crawl_budgets = {}\n", crawl_budgets));
+
+        s.push_str("# Stop queueing a suspected spider trap (calendar, repeating path, session IDs...)\n");
+        s.push_str("# once spider_trap_threshold URLs matched its pattern\n");
+        s.push_str(&format!("spider_trap_auto_stop = {}\n", self.spider_trap_auto_stop));
+        s.push_str(&format!("spider_trap_threshold = {}\n", self.spider_trap_threshold));
+
         s.push_str("\n# --- Timing & Throttling (Adaptive) ---\n");
         s.push_str("# Enable adaptive crawling speed based on server response\n");
         s.push_str(&format!("adaptive_crawling = {}\n", self.adaptive_crawling));
@@ -288,9 +449,21 @@ impl Settings {
         s.push_str("# Minimum delay allowed in adaptive mode (ms)\n");
         s.push_str(&format!("min_crawl_delay = {}\n", self.min_crawl_delay));
 
+        s.push_str("# Hard cap on requests per second to each host (pages, link checks and image\n");
+        s.push_str("# checks combined). 0 = no limit. Fractions allowed, e.g. 0.5 = one every 2s\n");
+        s.push_str(&format!(
+            "rate_limit_requests_per_second = {:?}\n",
+            self.rate_limit_requests_per_second
+        ));
+        s.push_str("# Requests a host may get back to back before the per-second limit applies\n");
+        s.push_str(&format!("rate_limit_burst = {}\n", self.rate_limit_burst));
+
         s.push_str("# Total timeout for a crawl job (seconds)\n");
         s.push_str(&format!("crawl_timeout = {}\n", self.crawl_timeout));
 
+        s.push_str("# Checkpoint the crawl frontier to disk every N seconds so it can be resumed (0 = disabled)\n");
+        s.push_str(&format!("checkpoint_interval = {}\n", self.checkpoint_interval));
+
         s.push_str("# Interval to check for stalled crawlers (seconds)\n");
         s.push_str(&format!(
             "stall_check_interval = {}\n",
@@ -316,6 +489,14 @@ impl Settings {
         s.push_str("# Maximum retries for failed requests\n");
         s.push_str(&format!("max_retries = {}\n", self.max_retries));
 
+        s.push_str("# Proxy for crawl traffic: http://, https://, socks5:// or socks5h:// (empty = direct)\n");
+        s.push_str(&format!("proxy_url = {:?}\n", self.proxy_url));
+        s.push_str("# Proxy credentials\n");
+        s.push_str(&format!("proxy_username = {:?}\n", self.proxy_username));
+        s.push_str(&format!("proxy_password = {:?}\n", self.proxy_password));
+        s.push_str("# Hosts or domains (e.g. \".example.com\") that bypass the proxy\n");
+        s.push_str(&format!("proxy_no_proxy = {:?}\n", self.proxy_no_proxy));
+
         s.push_str("\n# --- JavaScript & Rendering ---\n");
         s.push_str("# Whether to expect HTML content\n");
         s.push_str(&format!("html = {}\n", self.html));
@@ -332,6 +513,25 @@ impl Settings {
             self.javascript_concurrency
         ));
 
+        s.push_str("# What a render waits for after the page loads: \"network_idle\", \"selector\" or \"timeout\"\n");
+        s.push_str(&format!(
+            "javascript_wait_strategy = {}\n",
+            toml::Value::try_from(self.javascript_wait_strategy)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|_| "\"timeout\"".to_string())
+        ));
+        s.push_str("# CSS selector to wait for with the \"selector\" strategy\n");
+        s.push_str(&format!(
+            "javascript_wait_selector = {:?}\n",
+            self.javascript_wait_selector
+        ));
+        s.push_str("# Longest wait after the page loads, in milliseconds (the whole wait for \"timeout\")\n");
+        s.push_str("# At most 30000, as the whole render is limited to 45s\n");
+        s.push_str(&format!(
+            "javascript_wait_timeout_ms = {}\n",
+            self.javascript_wait_timeout_ms
+        ));
+
         s.push_str("\n# --- Link Processor (Internal/External Check) ---\n");
         s.push_str("# Max concurrent checks for link status\n");
         s.push_str(&format!(
@@ -389,6 +589,35 @@ impl Settings {
             self.duplicate_content_check_enabled
         ));
 
+        s.push_str("# Fetch every page again as the other device (smartphone/desktop) and\n");
+        s.push_str("# report mobile vs desktop differences. Doubles the requests per page.\n");
+        s.push_str(&format!(
+            "mobile_parity_check = {}\n",
+            self.mobile_parity_check
+        ));
+
+        s.push_str("# Request every image, CSS, JS, font, PDF and archive the pages reference\n");
+        s.push_str("# and report status, content type, size and cache headers\n");
+        s.push_str(&format!("crawl_resources = {}\n", self.crawl_resources));
+
+        s.push_str("# Also crawl the canonical and hreflang URLs pages declare, not only links\n");
+        s.push_str(&format!(
+            "follow_canonical_hreflang = {}\n",
+            self.follow_canonical_hreflang
+        ));
+
+        s.push_str("# Crawl linked PDFs and audit them (metadata, page/word count, language, links)\n");
+        s.push_str(&format!("crawl_pdfs = {}\n", self.crawl_pdfs));
+        s.push_str("# Size at which a PDF download stops (MB)\n");
+        s.push_str(&format!("pdf_max_size_mb = {}\n", self.pdf_max_size_mb));
+        s.push_str("# Audit each crawled host's certificate, chain, HTTPS redirect and HSTS\n");
+        s.push_str(&format!("tls_audit = {}\n", self.tls_audit));
+        s.push_str("# Report hosts whose TLS certificate expires within this many days\n");
+        s.push_str(&format!(
+            "tls_expiry_warning_days = {}\n",
+            self.tls_expiry_warning_days
+        ));
+
         s.push_str("\n# --- Extraction & Content ---\n");
         s.push_str("# Enable N-gram extraction\n");
         s.push_str(&format!("extract_ngrams = {}\n", self.extract_ngrams));
@@ -496,6 +725,38 @@ impl Settings {
     }
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_checkpoint_interval() -> u64 {
+    30
+}
+
+fn default_rate_limit_burst() -> u32 {
+    1
+}
+
+fn default_spider_trap_threshold() -> usize {
+    500
+}
+
+fn default_pdf_max_size_mb() -> u64 {
+    10
+}
+
+fn default_tls_expiry_warning_days() -> i64 {
+    30
+}
+
+fn default_javascript_wait_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_crawl_user_agent() -> String {
+    user_agents::UserAgentPreset::Browser.key().to_string()
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self::new()
@@ -671,6 +932,10 @@ pub async fn override_settings(updates: &str) -> Result<Settings, String> {
         settings.crawl_timeout = val as u64;
     }
 
+    if let Some(val) = updates.get("checkpoint_interval").and_then(|v| v.as_integer()) {
+        settings.checkpoint_interval = val as u64;
+    }
+
     if let Some(val) = updates.get("client_timeout").and_then(|v| v.as_integer()) {
         settings.client_timeout = val as u64;
     }
@@ -690,6 +955,25 @@ pub async fn override_settings(updates: &str) -> Result<Settings, String> {
         settings.max_retries = val as u32;
     }
 
+    if let Some(val) = updates.get("proxy_url").and_then(|v| v.as_str()) {
+        settings.proxy_url = val.trim().to_string();
+    }
+
+    if let Some(val) = updates.get("proxy_username").and_then(|v| v.as_str()) {
+        settings.proxy_username = val.to_string();
+    }
+
+    if let Some(val) = updates.get("proxy_password").and_then(|v| v.as_str()) {
+        settings.proxy_password = val.to_string();
+    }
+
+    if let Some(val) = updates.get("proxy_no_proxy").and_then(|v| v.as_array()) {
+        settings.proxy_no_proxy = val
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect();
+    }
+
     if let Some(val) = updates.get("base_delay").and_then(|v| v.as_integer()) {
         settings.base_delay = val as u64;
     }
@@ -698,6 +982,17 @@ pub async fn override_settings(updates: &str) -> Result<Settings, String> {
         settings.max_delay = val as u64;
     }
 
+    if let Some(val) = updates
+        .get("rate_limit_requests_per_second")
+        .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+    {
+        settings.rate_limit_requests_per_second = val.max(0.0);
+    }
+
+    if let Some(val) = updates.get("rate_limit_burst").and_then(|v| v.as_integer()) {
+        settings.rate_limit_burst = val.max(1) as u32;
+    }
+
     if let Some(val) = updates.get("min_crawl_delay").and_then(|v| v.as_integer()) {
         settings.min_crawl_delay = val as u64;
     }
@@ -729,6 +1024,58 @@ pub async fn override_settings(updates: &str) -> Result<Settings, String> {
             .collect();
     }
 
+    if let Some(val) = updates.get("crawl_user_agent").and_then(|v| v.as_str()) {
+        settings.crawl_user_agent = user_agents::UserAgentPreset::from_key(val).key().to_string();
+    }
+
+    if let Some(val) = updates.get("custom_user_agent").and_then(|v| v.as_str()) {
+        settings.custom_user_agent = val.to_string();
+    }
+
+    if let Some(val) = updates.get("obey_robots_txt").and_then(|v| v.as_bool()) {
+        settings.obey_robots_txt = val;
+    }
+
+    if let Some(val) = updates.get("crawl_include_patterns").and_then(|v| v.as_array()) {
+        settings.crawl_include_patterns = val
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect();
+    }
+
+    if let Some(val) = updates.get("crawl_exclude_patterns").and_then(|v| v.as_array()) {
+        settings.crawl_exclude_patterns = val
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect();
+    }
+
+    if let Some(val) = updates.get("url_rewrite_rules") {
+        let rules: Vec<RewriteRule> = val
+            .clone()
+            .try_into()
+            .map_err(|e| format!("Invalid url_rewrite_rules: {}", e))?;
+        UrlRewriter::from_rules(&rules)?;
+        settings.url_rewrite_rules = rules;
+    }
+
+    if let Some(val) = updates.get("crawl_budgets") {
+        let budgets: Vec<CrawlBudget> = val
+            .clone()
+            .try_into()
+            .map_err(|e| format!("Invalid crawl_budgets: {}", e))?;
+        CrawlBudgets::from_rules(&budgets)?;
+        settings.crawl_budgets = budgets;
+    }
+
+    if let Some(val) = updates.get("spider_trap_auto_stop").and_then(|v| v.as_bool()) {
+        settings.spider_trap_auto_stop = val;
+    }
+
+    if let Some(val) = updates.get("spider_trap_threshold").and_then(|v| v.as_integer()) {
+        settings.spider_trap_threshold = val.max(1) as usize;
+    }
+
     if let Some(val) = updates.get("html").and_then(|v| v.as_bool()) {
         settings.html = val;
     }
@@ -836,6 +1183,30 @@ pub async fn override_settings(updates: &str) -> Result<Settings, String> {
         settings.javascript_concurrency = val as usize;
     }
 
+    if let Some(val) = updates.get("javascript_wait_strategy") {
+        settings.javascript_wait_strategy = val
+            .clone()
+            .try_into()
+            .map_err(|e| format!("Invalid javascript_wait_strategy: {}", e))?;
+    }
+
+    if let Some(val) = updates
+        .get("javascript_wait_selector")
+        .and_then(|v| v.as_str())
+    {
+        settings.javascript_wait_selector = val.to_string();
+    }
+
+    if let Some(val) = updates
+        .get("javascript_wait_timeout_ms")
+        .and_then(|v| v.as_integer())
+    {
+        // Capped below the render's own timeout, or long waits would time out
+        // every render
+        settings.javascript_wait_timeout_ms =
+            (val.max(0) as u64).min(headless_fetch::MAX_WAIT_TIMEOUT_MS);
+    }
+
     if let Some(val) = updates
         .get("stall_check_interval")
         .and_then(|v| v.as_integer())
@@ -893,6 +1264,40 @@ pub async fn override_settings(updates: &str) -> Result<Settings, String> {
         settings.duplicate_content_check_enabled = val;
     }
 
+    if let Some(val) = updates
+        .get("mobile_parity_check")
+        .and_then(|v| v.as_bool())
+    {
+        settings.mobile_parity_check = val;
+    }
+
+    if let Some(val) = updates.get("crawl_resources").and_then(|v| v.as_bool()) {
+        settings.crawl_resources = val;
+    }
+
+    if let Some(val) = updates
+        .get("follow_canonical_hreflang")
+        .and_then(|v| v.as_bool())
+    {
+        settings.follow_canonical_hreflang = val;
+    }
+
+    if let Some(val) = updates.get("crawl_pdfs").and_then(|v| v.as_bool()) {
+        settings.crawl_pdfs = val;
+    }
+
+    if let Some(val) = updates.get("pdf_max_size_mb").and_then(|v| v.as_integer()) {
+        settings.pdf_max_size_mb = val.max(1) as u64;
+    }
+
+    if let Some(val) = updates.get("tls_audit").and_then(|v| v.as_bool()) {
+        settings.tls_audit = val;
+    }
+
+    if let Some(val) = updates.get("tls_expiry_warning_days").and_then(|v| v.as_integer()) {
+        settings.tls_expiry_warning_days = val.max(0);
+    }
+
     if let Some(val) = updates
         .get("db_chunk_size_domain_crawler")
         .and_then(|v| v.as_integer())
@@ -1086,6 +1491,8 @@ pub async fn update_settings_command(
     settings_state: tauri::State<'_, crate::AppState>,
 ) -> Result<Settings, String> {
     let updated_settings = override_settings(&updates).await?;
+    // Rate limit changes reach a running crawl straight away
+    rate_limit::configure(&updated_settings);
 
     let mut settings_lock = settings_state.settings.write().await;
     *settings_lock = updated_settings.clone();
@@ -1117,3 +1524,24 @@ pub async fn toggle_javascript_rendering(
 
     Ok(())
 }
+
+/// Changes the per-host rate limit, including for a crawl that's running.
+#[tauri::command]
+pub async fn set_rate_limit_command(
+    requests_per_second: f64,
+    burst: Option<u32>,
+    settings_state: tauri::State<'_, crate::AppState>,
+) -> Result<Settings, String> {
+    let mut updates = format!("rate_limit_requests_per_second = {:?}", requests_per_second.max(0.0));
+    if let Some(burst) = burst {
+        updates.push_str(&format!("\nrate_limit_burst = {}", burst));
+    }
+
+    let updated_settings = override_settings(&updates).await?;
+    rate_limit::configure(&updated_settings);
+
+    let mut settings_lock = settings_state.settings.write().await;
+    *settings_lock = updated_settings.clone();
+
+    Ok(updated_settings)
+}